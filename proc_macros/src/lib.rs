@@ -1,3 +1,7 @@
+// The darling derives expand `#[darling(default)]` into code that trips this
+// lint on newer toolchains.
+#![allow(clippy::manual_unwrap_or_default)]
+
 use darling::FromDeriveInput;
 use darling::FromField;
 use darling::FromMeta;
@@ -66,7 +70,7 @@ pub fn c_api_wrapper_derive(input: TokenStream) -> TokenStream {
     };
 
     let name = &opts.ident;
-    let new_name = Ident::new(&format!("{}{}", &opts.prefix, name), name.span());
+    let new_name = Ident::new(&format!("{}{}", opts.prefix, name), name.span());
 
     let repr_c_token = if opts.repr_c {
         quote!(#[repr(C)])
@@ -191,6 +195,7 @@ pub fn c_api_wrapper_derive(input: TokenStream) -> TokenStream {
                     #[cfg(feature = "c_api")]
                     #repr_c_token
                     #derives
+                    #[allow(clippy::enum_variant_names)]
                     pub(crate) enum #new_name {
                         #(#variants),*
                     }
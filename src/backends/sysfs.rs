@@ -15,17 +15,23 @@ use crate::pd::MessageRecipient;
 use crate::pd::MessageResponseType;
 use crate::pd::Pdo;
 use crate::ucsi::AlternateMode;
+use crate::ucsi::AttentionVdo;
 use crate::ucsi::CableProperty;
 use crate::ucsi::Capability;
 use crate::ucsi::ConnectorCapability;
 use crate::ucsi::ConnectorCapabilityOperationMode;
 use crate::ucsi::ConnectorStatus;
+use crate::ucsi::ErrorStatus;
 use crate::ucsi::GetAlternateModesRecipient;
+use crate::ucsi::LpmPpmInfo;
 use crate::ucsi::PdoSourceCapabilitiesType;
 use crate::ucsi::PdoType;
+use crate::ucsi::PowerDirection;
+use crate::ucsi::PowerLevel;
 use crate::BcdWrapper;
 use crate::Error;
 use crate::OsBackend;
+use crate::OsControl;
 use crate::Result;
 
 use sysfs_reader::SysfsReader;
@@ -49,14 +55,9 @@ fn check_path(path: &str) -> Result<PathBuf> {
 
 pub mod sysfs_reader {
     use std::io;
-    use std::io::Cursor;
     use std::path::Path;
     use std::path::PathBuf;
 
-    use crate::pd::pd3p2::vdo::CertStat;
-    use crate::pd::pd3p2::vdo::IdHeader;
-    use crate::pd::pd3p2::vdo::Product;
-    use crate::pd::pd3p2::vdo::ProductType;
     use crate::pd::pd3p2::BatterySupplyPdo;
     use crate::pd::pd3p2::DiscoverIdentityResponse;
     use crate::pd::pd3p2::FastRoleSwap;
@@ -68,10 +69,10 @@ pub mod sysfs_reader {
     use crate::ucsi::CablePropertyType;
     use crate::ucsi::ConnectorCapabilityOperationMode;
     use crate::ucsi::PdoType;
+    use crate::ucsi::PowerDirection;
+    use crate::ucsi::PowerOperationMode;
     use crate::BcdWrapper;
-    use crate::BitReader;
     use crate::Error;
-    use crate::FromBytes;
     use crate::Result;
 
     use super::SYSFS_TYPEC_PATH;
@@ -136,6 +137,36 @@ pub mod sysfs_reader {
             }
         }
 
+        pub fn read_power_direction(&mut self) -> Result<PowerDirection> {
+            // The current role is the one in brackets, i.e. "[source] sink".
+            let content = self.read_file()?;
+            if content.contains("[source]") {
+                Ok(PowerDirection::Provider)
+            } else {
+                Ok(PowerDirection::Consumer)
+            }
+        }
+
+        pub fn read_power_operation_mode(&mut self) -> Result<PowerOperationMode> {
+            let content = self.read_file()?;
+            let mode = match content.trim() {
+                "default" => PowerOperationMode::UsbDefaultOperation,
+                "1.5A" => PowerOperationMode::UsbTypeCCurrent1_5A,
+                "3.0A" => PowerOperationMode::UsbTypeCCurrent3A,
+                "usb_power_delivery" => PowerOperationMode::PowerDelivery,
+                _ => {
+                    return Err(Error::ParseStringError {
+                        field: "power_operation_mode".to_string(),
+                        value: content,
+                        #[cfg(feature = "backtrace")]
+                        backtrace: std::backtrace::Backtrace::capture(),
+                    })
+                }
+            };
+
+            Ok(mode)
+        }
+
         pub fn read_pd_revision(&mut self) -> Result<u8> {
             let content = self.read_file()?;
             let mut chars = content.chars();
@@ -188,9 +219,13 @@ pub mod sysfs_reader {
 
         pub fn read_bit(&mut self) -> Result<bool> {
             let content = self.read_file()?;
-            let bit = content.trim().parse::<bool>().map_err(|_| {
-                io::Error::new(io::ErrorKind::InvalidData, "Could not parse bool value")
-            })?;
+            let bit = match content.trim() {
+                "1" | "yes" => true,
+                "0" | "no" => false,
+                other => other.parse::<bool>().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "Could not parse bool value")
+                })?,
+            };
             Ok(bit)
         }
 
@@ -272,9 +307,9 @@ pub mod sysfs_reader {
                             backtrace: std::backtrace::Backtrace::capture(),
                         })?;
                     self.set_path(&path.join("voltage").to_string_lossy())?;
-                    let voltage = (self.read_u32()? / 50).into();
+                    let voltage = self.read_u32()?.into();
                     self.set_path(&path.join("maximum_current").to_string_lossy())?;
-                    let operational_current = (self.read_u32()? / 10).into();
+                    let operational_current = self.read_u32()?.into();
 
                     Ok(FixedSupplyPdo {
                         dual_role_power,
@@ -308,9 +343,9 @@ pub mod sysfs_reader {
                             backtrace: std::backtrace::Backtrace::capture(),
                         })?;
                     self.set_path(&path.join("voltage").to_string_lossy())?;
-                    let voltage = (self.read_u32()? / 50).into();
+                    let voltage = self.read_u32()?.into();
                     self.set_path(&path.join("operational_current").to_string_lossy())?;
-                    let operational_current = (self.read_u32()? / 10).into();
+                    let operational_current = self.read_u32()?.into();
 
                     Ok(FixedSupplyPdo {
                         dual_role_power,
@@ -332,9 +367,9 @@ pub mod sysfs_reader {
             src_or_sink: PdoType,
         ) -> Result<SprProgrammableSupplyPdo> {
             self.set_path(&path.join("maximum_voltage").to_string_lossy())?;
-            let max_voltage = (self.read_u32()? / 50).into();
+            let max_voltage = self.read_u32()?.into();
             self.set_path(&path.join("minimum_voltage").to_string_lossy())?;
-            let min_voltage = (self.read_u32()? / 50).into();
+            let min_voltage = self.read_u32()?.into();
             let max_current = (match src_or_sink {
                 PdoType::Source => {
                     self.set_path(&path.join("maximum_current").to_string_lossy())?;
@@ -344,8 +379,8 @@ pub mod sysfs_reader {
                     self.set_path(&path.join("operational_current").to_string_lossy())?;
                     self.read_u32()?
                 }
-            } / 10)
-                .into();
+            })
+            .into();
 
             Ok(SprProgrammableSupplyPdo {
                 max_voltage,
@@ -360,9 +395,9 @@ pub mod sysfs_reader {
             src_or_sink: PdoType,
         ) -> Result<BatterySupplyPdo> {
             self.set_path(&path.join("maximum_voltage").to_string_lossy())?;
-            let max_voltage = (self.read_u32()? / 50).into();
+            let max_voltage = self.read_u32()?.into();
             self.set_path(&path.join("minimum_voltage").to_string_lossy())?;
-            let min_voltage = (self.read_u32()? / 50).into();
+            let min_voltage = self.read_u32()?.into();
             let operational_power = (match src_or_sink {
                 PdoType::Source => {
                     self.set_path(&path.join("maximum_power").to_string_lossy())?;
@@ -372,8 +407,8 @@ pub mod sysfs_reader {
                     self.set_path(&path.join("operational_power").to_string_lossy())?;
                     self.read_u32()?
                 }
-            } / 250)
-                .into();
+            })
+            .into();
 
             Ok(BatterySupplyPdo {
                 max_voltage,
@@ -388,11 +423,11 @@ pub mod sysfs_reader {
             _src_or_sink: PdoType,
         ) -> Result<VariableSupplyPdo> {
             self.set_path(&path.join("maximum_voltage").to_string_lossy())?;
-            let max_voltage = (self.read_u32()? / 100).into();
+            let max_voltage = self.read_u32()?.into();
             self.set_path(&path.join("minimum_voltage").to_string_lossy())?;
-            let min_voltage = (self.read_u32()? / 100).into();
+            let min_voltage = self.read_u32()?.into();
             self.set_path(&path.join("maximum_current").to_string_lossy())?;
-            let max_current = (self.read_u32()? / 50).into();
+            let max_current = self.read_u32()?.into();
 
             Ok(VariableSupplyPdo {
                 max_voltage,
@@ -424,50 +459,31 @@ pub mod sysfs_reader {
                 }
             };
 
-            let binding = id_header.to_le_bytes();
-            let mut br = BitReader::new(Cursor::new(&binding));
-            let id_header_vdo = IdHeader::from_bytes(&mut br)?;
-
-            let binding = cert_stat.to_le_bytes();
-            let mut br = BitReader::new(Cursor::new(&binding));
-            let cert_stat = CertStat::from_bytes(&mut br)?;
-
-            let binding = product.to_le_bytes();
-            let mut br = BitReader::new(Cursor::new(&binding));
-            let product_vdo = Product::from_bytes(&mut br)?;
-
-            Ok(DiscoverIdentityResponse {
-                header: Default::default(),
-                id_header_vdo,
-                cert_stat,
-                product_vdo,
-                product_type_vdo,
-            })
+            DiscoverIdentityResponse::from_vdos(
+                &[
+                    id_header,
+                    cert_stat,
+                    product,
+                    product_type_vdo[0],
+                    product_type_vdo[1],
+                    product_type_vdo[2],
+                ],
+                recipient,
+            )
         }
 
-        fn read_identity(&mut self, path: &str) -> Result<(u32, u32, u32, [ProductType; 3])> {
+        fn read_identity(&mut self, path: &str) -> Result<(u32, u32, u32, [u32; 3])> {
+            // The identity files are formatted as "0x%08x".
             self.set_path(&format!("{}/{}", path, "cert_stat"))?;
-            let cert_stat = self.read_u32()?;
+            let cert_stat = self.read_hex_u32()?;
             self.set_path(&format!("{}/{}", path, "id_header"))?;
-            let id_header = self.read_u32()?;
+            let id_header = self.read_hex_u32()?;
             self.set_path(&format!("{}/{}", path, "product"))?;
-            let product = self.read_u32()?;
-            let mut product_type_vdo = [
-                ProductType::default(),
-                ProductType::default(),
-                ProductType::default(),
-            ];
+            let product = self.read_hex_u32()?;
+            let mut product_type_vdo = [0; 3];
             for (i, vdo) in product_type_vdo.iter_mut().enumerate() {
                 self.set_path(&format!("{}/product_type_vdo{}", path, i + 1))?;
-                let value = self.read_u32()?;
-                if value != 0 {
-                    *vdo = ProductType::n(value).ok_or(Error::ParseError {
-                        field: "product_type_vdo".to_string(),
-                        value,
-                        #[cfg(feature = "backtrace")]
-                        backtrace: std::backtrace::Backtrace::capture(),
-                    })?;
-                }
+                *vdo = self.read_hex_u32()?;
             }
             Ok((cert_stat, id_header, product, product_type_vdo))
         }
@@ -493,6 +509,58 @@ impl SysfsBackend {
             reader: SysfsReader::new()?,
         })
     }
+
+    /// Returns the directory of the altmode at `index` of `recipient`.
+    fn alternate_mode_path(
+        recipient: GetAlternateModesRecipient,
+        connector_nr: usize,
+        index: usize,
+    ) -> Result<String> {
+        match recipient {
+            GetAlternateModesRecipient::Connector => Ok(format!(
+                "{}/port{}/port{}.{}",
+                SYSFS_TYPEC_PATH, connector_nr, connector_nr, index
+            )),
+            GetAlternateModesRecipient::Sop => Ok(format!(
+                "{}/port{}/port{}-partner/port{}-partner.{}",
+                SYSFS_TYPEC_PATH, connector_nr, connector_nr, connector_nr, index
+            )),
+            GetAlternateModesRecipient::SopPrime => Ok(format!(
+                "{}/port{}-cable/port{}-plug0/port{}-plug0.{}",
+                SYSFS_TYPEC_PATH, connector_nr, connector_nr, connector_nr, index
+            )),
+            _ => Err(Error::NotSupported {
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            }),
+        }
+    }
+
+    /// Returns the indices of the altmodes of `recipient` whose `active`
+    /// attribute is set.
+    fn active_alternate_modes(
+        &mut self,
+        recipient: GetAlternateModesRecipient,
+        connector_nr: usize,
+    ) -> Result<Vec<usize>> {
+        let mut active = vec![];
+        for index in 0.. {
+            let path_str = Self::alternate_mode_path(recipient, connector_nr, index)?;
+            if self
+                .reader
+                .set_path(&format!("{}/active", path_str))
+                .is_err()
+            {
+                break;
+            }
+
+            if self.reader.read_bit()? {
+                active.push(index);
+            }
+        }
+
+        Ok(active)
+    }
 }
 
 impl OsBackend for SysfsBackend {
@@ -502,19 +570,20 @@ impl OsBackend for SysfsBackend {
         let mut pd_version = Default::default();
         let mut usb_type_c_version = Default::default();
 
+        let port_re = Regex::new(r"^port\d+$").unwrap();
+        let alt_mode_re = Regex::new(r"^port\d\.\d$").unwrap();
+
         for entry in WalkDir::new(SYSFS_TYPEC_PATH) {
             let entry = entry?;
             let entry_name = entry.file_name().to_string_lossy();
 
-            let re = Regex::new(r"^port\d+$").unwrap();
-            if re.is_match(&entry_name) {
+            if port_re.is_match(&entry_name) {
                 num_ports += 1;
                 for port_entry in WalkDir::new(entry.path()) {
                     let port_entry = port_entry?;
                     let port_entry_name = port_entry.file_name().to_string_lossy();
 
-                    let re = Regex::new(r"^port\d\.\d$").unwrap();
-                    if re.is_match(&port_entry_name) {
+                    if alt_mode_re.is_match(&port_entry_name) {
                         num_alt_modes += 1;
                     }
                 }
@@ -589,33 +658,7 @@ impl OsBackend for SysfsBackend {
         let mut alt_modes = vec![];
 
         loop {
-            let num_alt_mode = alt_modes.len();
-            let path_str = match recipient {
-                crate::ucsi::GetAlternateModesRecipient::Connector => {
-                    format!(
-                        "{}/port{}/port{}.{}",
-                        SYSFS_TYPEC_PATH, connector_nr, connector_nr, num_alt_mode
-                    )
-                }
-                crate::ucsi::GetAlternateModesRecipient::Sop => {
-                    format!(
-                        "{}/port{}/port{}-partner/port{}-partner.{}",
-                        SYSFS_TYPEC_PATH, connector_nr, connector_nr, connector_nr, num_alt_mode
-                    )
-                }
-                crate::ucsi::GetAlternateModesRecipient::SopPrime => {
-                    format!(
-                        "{}/port{}-cable/port{}-plug0/port{}-plug0.{}",
-                        SYSFS_TYPEC_PATH, connector_nr, connector_nr, connector_nr, num_alt_mode
-                    )
-                }
-                _ => {
-                    return Err(Error::NotSupported {
-                        #[cfg(feature = "backtrace")]
-                        backtrace: std::backtrace::Backtrace::capture(),
-                    })
-                }
-            };
+            let path_str = Self::alternate_mode_path(recipient, connector_nr, alt_modes.len())?;
 
             let mut alt_mode = crate::ucsi::AlternateMode::default();
 
@@ -638,6 +681,35 @@ impl OsBackend for SysfsBackend {
         Ok(alt_modes)
     }
 
+    fn supported_alternate_modes(&mut self, connector_nr: usize) -> Result<Vec<usize>> {
+        // The port altmodes that are active can be entered.
+        self.active_alternate_modes(GetAlternateModesRecipient::Connector, connector_nr)
+    }
+
+    fn current_alternate_modes(&mut self, connector_nr: usize) -> Result<Vec<usize>> {
+        // The partner altmodes that are active were entered. Map them back to
+        // the port altmodes with the same SVID.
+        let port_modes =
+            self.alternate_modes(GetAlternateModesRecipient::Connector, connector_nr)?;
+        let partner_modes = self.alternate_modes(GetAlternateModesRecipient::Sop, connector_nr)?;
+
+        let mut current = vec![];
+        for index in self.active_alternate_modes(GetAlternateModesRecipient::Sop, connector_nr)? {
+            let Some(partner_mode) = partner_modes.get(index) else {
+                continue;
+            };
+            let svid = partner_mode.svid[0];
+            if let Some(offset) = port_modes.iter().position(|mode| mode.svid[0] == svid) {
+                if !current.contains(&offset) {
+                    current.push(offset);
+                }
+            }
+        }
+
+        current.sort();
+        Ok(current)
+    }
+
     fn cable_properties(&mut self, connector_nr: usize) -> Result<CableProperty> {
         let mut cable_property = CableProperty::default();
         let path_str = format!("{}/port{}-cable", SYSFS_TYPEC_PATH, connector_nr);
@@ -669,6 +741,17 @@ impl OsBackend for SysfsBackend {
         );
         connector_status.connect_status = Path::new(&partner_path_str).exists();
 
+        let port_path_str = format!("{}/port{}", SYSFS_TYPEC_PATH, connector_nr);
+        self.reader
+            .set_path(&format!("{}/{}", port_path_str, "power_role"))?;
+        connector_status.power_direction = self.reader.read_power_direction()?;
+
+        if connector_status.connect_status {
+            self.reader
+                .set_path(&format!("{}/{}", port_path_str, "power_operation_mode"))?;
+            connector_status.power_operation_mode = self.reader.read_power_operation_mode()?;
+        }
+
         let psy_path_str = format!(
             "{}/ucsi-source-psy-USBC000:00{}",
             SYSFS_PSY_PATH,
@@ -798,4 +881,43 @@ impl OsBackend for SysfsBackend {
 
         Ok(pdos)
     }
+
+    fn error_status(&mut self, _connector_nr: Option<usize>) -> Result<ErrorStatus> {
+        Err(Error::NotSupported {
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })
+    }
+
+    fn attention_vdo(&mut self, _connector_nr: usize) -> Result<AttentionVdo> {
+        Err(Error::NotSupported {
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })
+    }
+
+    fn lpm_ppm_info(&mut self, _connector_nr: usize) -> Result<LpmPpmInfo> {
+        Err(Error::NotSupported {
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })
+    }
+
+    fn power_level(
+        &mut self,
+        _connector_nr: usize,
+        _power_direction: PowerDirection,
+    ) -> Result<PowerLevel> {
+        Err(Error::NotSupported {
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })
+    }
+
+    fn control(&mut self) -> Result<&mut dyn OsControl> {
+        Err(Error::NotSupported {
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })
+    }
 }
@@ -6,38 +6,54 @@
 
 use std::io::Cursor;
 
+use crate::pd::pd3p2::DiscoverIdentityResponse;
 use crate::pd::Message;
 use crate::pd::MessageRecipient;
 use crate::pd::MessageResponseType;
 use crate::pd::Pdo;
+use crate::ucsi::commands;
+use crate::ucsi::commands::UcsiCommand;
+use crate::ucsi::Acknowledgement;
 use crate::ucsi::AlternateMode;
+use crate::ucsi::AttentionVdo;
+use crate::ucsi::BmOptionalFeatures;
 use crate::ucsi::CableProperty;
 use crate::ucsi::Capability;
 use crate::ucsi::Command;
 use crate::ucsi::ConnectorCapability;
+use crate::ucsi::ConnectorCapabilityMiscellaneousCapabilities;
+use crate::ucsi::ConnectorResetType;
 use crate::ucsi::ConnectorStatus;
+use crate::ucsi::ErrorInformation;
+use crate::ucsi::ErrorStatus;
 use crate::ucsi::GetAlternateModesRecipient;
+use crate::ucsi::LpmPpmInfo;
+use crate::ucsi::NotificationEnable;
 use crate::ucsi::PdoSourceCapabilitiesType;
 use crate::ucsi::PdoType;
+use crate::ucsi::PowerDirection;
+use crate::ucsi::PowerLevel;
+use crate::ucsi::RawResponse;
+use crate::ucsi::UcsiVersion;
 use crate::BcdWrapper;
 use crate::BitReader;
 use crate::Error;
-use crate::FromBytes;
 use crate::OsBackend;
+use crate::OsControl;
 use crate::Result;
 use crate::ToBytes;
 
-/// A mere convenience to check if a response is null.
-trait NullResponse {
-    fn is_null(&self) -> bool;
-}
+pub mod simulator;
 
-impl NullResponse for Vec<u8> {
-    /// A convenience method to identify a null response.
-    fn is_null(&self) -> bool {
-        self.iter().all(|byte| *byte == 0)
-    }
-}
+/// The size of MESSAGE_IN in UCSI 2.0 and later.
+const MESSAGE_IN_MAX_LEN: usize = 256;
+
+/// See UCSI 3.0 - Table 6-32: GET_PDOS Command. Offsets 0 through 7 address
+/// the SPR PDOs and 8 through 13 the EPR PDOs.
+const EPR_PDO_OFFSET: u32 = 8;
+const MAX_PDO_OFFSET: u32 = 14;
+/// The number of PDOs that fit in a response.
+const MAX_PDOS_PER_RESPONSE: u32 = 4;
 
 mod driver {
     use std::fs::File;
@@ -49,6 +65,9 @@ mod driver {
     use crate::Error;
     use crate::Result;
 
+    /// The debugfs directory of the first UCSI interface.
+    const DEBUGFS_PATH: &str = "/sys/kernel/debug/usb/ucsi/USBC000:00";
+
     pub struct Driver {
         /// The file descriptor used to send commands.
         command_fd: File,
@@ -60,11 +79,11 @@ mod driver {
         pub fn new() -> Result<Self> {
             let command_fd = std::fs::OpenOptions::new()
                 .write(true)
-                .open("/sys/kernel/debug/usb/ucsi/USBC000:00/command")?;
+                .open(format!("{DEBUGFS_PATH}/command"))?;
 
             let mut response_fd = std::fs::OpenOptions::new()
                 .read(true)
-                .open("/sys/kernel/debug/usb/ucsi/USBC000:00/response")?;
+                .open(format!("{DEBUGFS_PATH}/response"))?;
 
             response_fd.seek(std::io::SeekFrom::Start(0))?;
 
@@ -106,36 +125,225 @@ mod driver {
 
 use driver::Driver;
 
+/// A channel to a PPM.
+pub trait UcsiTransport {
+    /// Writes `control` to the CONTROL data structure and waits for the
+    /// command to complete. Returns the contents of MESSAGE_IN.
+    fn execute(&mut self, control: u64) -> Result<Vec<u8>>;
+
+    /// Returns the bcdUCSIVersion of the PPM, or `None` if the transport has
+    /// no way to read it.
+    fn ucsi_version(&mut self) -> Result<Option<BcdWrapper>> {
+        Ok(None)
+    }
+
+    /// The number of bytes of MESSAGE_IN the transport can return.
+    fn max_message_in_len(&self) -> usize {
+        MESSAGE_IN_MAX_LEN
+    }
+
+    /// Executes `control` like [`UcsiTransport::execute`], but returns
+    /// failures reported by the PPM in the CCI. Transports that cannot read
+    /// the CCI return it as `None` and report failures as errors.
+    fn execute_raw(&mut self, control: u64) -> Result<RawResponse> {
+        Ok(RawResponse {
+            cci: None,
+            message_in: self.execute(control)?,
+        })
+    }
+
+    /// Writes `message_out` to MESSAGE_OUT before executing `control`, for
+    /// the commands that carry a payload.
+    fn execute_with_message_out(&mut self, _control: u64, _message_out: &[u8]) -> Result<Vec<u8>> {
+        Err(Error::NotSupported {
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })
+    }
+}
+
+impl UcsiTransport for Driver {
+    fn execute(&mut self, control: u64) -> Result<Vec<u8>> {
+        let cmd_str = UcsiDebugfsBackend::stringify_command_val(control)?;
+        self.submit_command(&cmd_str)?;
+
+        let response = self.wait_response()?;
+        UcsiDebugfsBackend::parse_response(response)
+    }
+
+    // The kernel does not expose the VERSION data structure: debugfs only
+    // has the command and response files, and GET_CAPABILITY does not carry
+    // bcdUCSIVersion.
+
+    fn max_message_in_len(&self) -> usize {
+        // The kernel only prints the first 16 bytes of MESSAGE_IN.
+        16
+    }
+
+    // The kernel offers no way to write MESSAGE_OUT through debugfs.
+}
+
 pub struct UcsiDebugfsBackend {
-    /// The driver abstraction.
-    driver: Driver,
+    /// The channel commands are sent through.
+    transport: Box<dyn UcsiTransport>,
+    /// The bcdUCSIVersion of the PPM, if it is known.
+    ucsi_version: Option<BcdWrapper>,
+    /// The optional features of the PPM, read on first use.
+    optional_features: Option<BmOptionalFeatures>,
 }
 
 impl UcsiDebugfsBackend {
     /// Instantiates a new UCSI backend for Linux.
     pub fn new() -> Result<Self> {
         let driver = Driver::new()?;
-        Ok(Self { driver })
+        Ok(Self::with_transport(Box::new(driver)))
     }
 
-    /// Parses the response from the Linux UCSI driver. It currently replies
-    /// with two u64s because it conforms to UCSI 1.2.
-    fn parse_response(response: Vec<u8>) -> Result<Vec<u8>> {
-        let response = std::str::from_utf8(&response)?;
-        // Remove the "0x" prefix and \n at the end
-        let hex_string = &response[2..response.len() - 1];
-        let (first, second) = hex_string.split_at(16);
+    /// Instantiates a UCSI backend sending its commands through `transport`,
+    /// e.g. a [`simulator::PpmSimulator`].
+    ///
+    /// The UCSI revision is read from the transport through
+    /// [`UcsiTransport::ucsi_version`]. This is only a hook for transports
+    /// that can read it, such as the simulator: the Linux debugfs interface
+    /// cannot, so the revision of a real PPM is unknown unless it is set with
+    /// [`UcsiDebugfsBackend::set_ucsi_version`]. PPMs whose revision is
+    /// unknown are sent every command, and all the fields of their responses
+    /// are decoded.
+    pub fn with_transport(mut transport: Box<dyn UcsiTransport>) -> Self {
+        let ucsi_version = transport.ucsi_version().ok().flatten();
+        Self {
+            transport,
+            ucsi_version,
+            optional_features: None,
+        }
+    }
+
+    /// The UCSI revision whose layouts are used to talk to the PPM, or
+    /// `None` if it is unknown.
+    pub fn ucsi_version(&self) -> Option<UcsiVersion> {
+        self.ucsi_version.map(UcsiVersion::from)
+    }
+
+    /// Overrides the detected bcdUCSIVersion of the PPM.
+    pub fn set_ucsi_version(&mut self, ucsi_version: BcdWrapper) {
+        self.ucsi_version = Some(ucsi_version);
+    }
 
-        let high = u64::from_str_radix(first, 16).unwrap();
-        let low = u64::from_str_radix(second, 16).unwrap();
+    /// Fails with [`Error::NotSupported`] unless the PPM reports `feature` in
+    /// its optional features.
+    fn require_feature(&mut self, feature: fn(&BmOptionalFeatures) -> bool) -> Result<()> {
+        if self.optional_features.is_none() {
+            self.optional_features = Some(self.capabilities()?.bm_optional_features);
+        }
+
+        if self.optional_features.as_ref().is_some_and(feature) {
+            Ok(())
+        } else {
+            Err(Error::NotSupported {
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })
+        }
+    }
+
+    /// The number of bytes of MESSAGE_IN that can be retrieved at once.
+    fn message_in_len(&self) -> usize {
+        self.ucsi_version()
+            .map_or(MESSAGE_IN_MAX_LEN, |version| version.message_in_len())
+            .min(self.transport.max_message_in_len())
+    }
+
+    /// Parses the response from the Linux UCSI driver, which prints
+    /// MESSAGE_IN as a single big-endian hex number, e.g. `0x` followed by 32
+    /// hex digits for the 16 bytes of UCSI 1.2. The bytes are returned in
+    /// MESSAGE_IN order.
+    fn parse_response(response: Vec<u8>) -> Result<Vec<u8>> {
+        let response = std::str::from_utf8(&response)?.trim_end();
+        let parse_error = || Error::ParseError {
+            field: "response".into(),
+            value: response.len() as u32,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        };
+
+        let hex_string = response.strip_prefix("0x").ok_or_else(parse_error)?;
+        if hex_string.is_empty()
+            || hex_string.len() % 2 != 0
+            || hex_string.len() / 2 > MESSAGE_IN_MAX_LEN
+        {
+            return Err(parse_error());
+        }
 
-        let mut result = Vec::new();
-        result.extend(&low.to_ne_bytes());
-        result.extend(&high.to_ne_bytes());
+        let mut result = (0..hex_string.len())
+            .step_by(2)
+            .map(|i| {
+                hex_string
+                    .get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(parse_error)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        result.reverse();
 
         Ok(result)
     }
 
+    /// Translates the errno the kernel derived from the CCI of a failed
+    /// command back into the matching [`Error`]. Other errors are returned
+    /// unchanged.
+    ///
+    /// The kernel has already issued GET_ERROR_STATUS when it returns an
+    /// errno for a failed command, so the reasons of the failure are
+    /// recovered from the errno rather than by asking the PPM again. EINVAL
+    /// does not tell an invalid connector number from invalid parameters,
+    /// and EIO covers every reason the kernel does not translate.
+    fn transport_error(err: Error) -> Error {
+        let errno = match &err {
+            Error::IoError { source, .. } => source.raw_os_error(),
+            _ => None,
+        };
+
+        let error_information = match errno {
+            Some(nix::libc::EBUSY) => {
+                return Error::PpmBusy {
+                    #[cfg(feature = "backtrace")]
+                    backtrace: std::backtrace::Backtrace::capture(),
+                }
+            }
+            // The kernel also returns EOPNOTSUPP for an incompatible
+            // connector partner and for the commands debugfs does not
+            // forward, so it does not mean that the PPM rejected the command.
+            Some(nix::libc::EOPNOTSUPP) => {
+                return Error::NotSupported {
+                    #[cfg(feature = "backtrace")]
+                    backtrace: std::backtrace::Backtrace::capture(),
+                }
+            }
+            Some(nix::libc::ETIMEDOUT) => {
+                return Error::TimeoutError {
+                    #[cfg(feature = "backtrace")]
+                    backtrace: std::backtrace::Backtrace::capture(),
+                }
+            }
+            Some(nix::libc::EINVAL) => {
+                ErrorInformation::INVALID_COMMAND_SPECIFIC_PARAMETERS
+                    | ErrorInformation::NON_EXISTENT_CONNECTOR_NUMBER
+            }
+            Some(nix::libc::ECOMM) => ErrorInformation::CC_COMMUNICATION_ERROR,
+            Some(nix::libc::EPROTO) => ErrorInformation::CONTRACT_NEGOTIATION_FAILURE,
+            Some(nix::libc::EPERM) => ErrorInformation::COMMAND_UNSUCCESSFUL_DUE_TO_DEAD_BATTERY,
+            Some(nix::libc::EOVERFLOW) => ErrorInformation::OVERCURRENT,
+            Some(nix::libc::EIO) => ErrorInformation::empty(),
+            _ => return err,
+        };
+
+        Error::CommandFailed {
+            error_information,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        }
+    }
+
     /// Builds a u64 value from a UCSI command.
     fn build_command_value(command: &Command) -> Result<u64> {
         let mut buf = [0; 8];
@@ -156,31 +364,105 @@ impl UcsiDebugfsBackend {
         Ok(c_string.into_bytes_with_nul())
     }
 
-    /// Execute the command, returning a string of bytes as a result.
-    pub fn execute(&mut self, command: Command) -> Result<Vec<u8>> {
-        let cmd_val = Self::build_command_value(&command)?;
-        let cmd_str = Self::stringify_command_val(cmd_val)?;
+    /// Executes `command`, one of the structs in [`commands`], and decodes
+    /// its response.
+    ///
+    /// Commands not defined by the UCSI revision of the PPM fail with
+    /// [`Error::NotSupported`] without being sent. All commands are sent if
+    /// the revision is unknown.
+    pub fn execute<C: UcsiCommand>(&mut self, command: C) -> Result<C::Response> {
+        let cmd = command.command();
+        if self
+            .ucsi_version()
+            .is_some_and(|version| cmd.min_version() > version)
+        {
+            return Err(Error::NotSupported {
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            });
+        }
+
+        let cmd_val = Self::build_command_value(&cmd)?;
+        let message_out = command.message_out();
+        let response = if message_out.is_empty() {
+            self.transport.execute(cmd_val)
+        } else {
+            self.transport
+                .execute_with_message_out(cmd_val, message_out)
+        };
+        let message_in = response.map_err(Self::transport_error)?;
+        command.parse_response(&message_in, self.ucsi_version())
+    }
+
+    /// Sends `request` through MESSAGE_OUT with `command`, which builds the
+    /// command for each chunk from its number, whether it is the last one
+    /// and its data. Empty requests are rejected, and requests that do not
+    /// fit in MESSAGE_OUT are only sent if the PPM supports chunking. Returns
+    /// the response to the last chunk.
+    fn message_out_request<'r, C: UcsiCommand<Response = Vec<u8>>>(
+        &mut self,
+        request: &'r [u8],
+        command: impl Fn(u8, bool, &'r [u8]) -> C,
+    ) -> Result<Vec<u8>> {
+        // MESSAGE_OUT is as large as MESSAGE_IN, but the data length of the
+        // command is a single byte.
+        let chunk_len = self.message_in_len().min(u8::MAX as usize);
+        if request.len() > chunk_len {
+            self.require_feature(|features| features.chunking_supported)?;
+        }
+
+        let chunks = request.chunks(chunk_len).collect::<Vec<_>>();
+        if chunks.is_empty() || chunks.len() > usize::from(u8::MAX) + 1 {
+            return Err(Error::ParseError {
+                field: "request".into(),
+                value: request.len() as u32,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            });
+        }
+
+        let mut response = vec![];
+        for (chunk_number, chunk) in chunks.iter().enumerate() {
+            let last_chunk = chunk_number + 1 == chunks.len();
+            response = self.execute(command(chunk_number as u8, last_chunk, chunk))?;
+        }
 
-        self.driver.submit_command(&cmd_str)?;
+        Ok(response)
+    }
 
-        let response = self.driver.wait_response()?;
-        Self::parse_response(response)
+    /// Fails with [`Error::NotSupported`] unless `connector_nr` reports
+    /// `capability`.
+    fn require_connector_capability(
+        &mut self,
+        connector_nr: usize,
+        capability: ConnectorCapabilityMiscellaneousCapabilities,
+    ) -> Result<()> {
+        if self
+            .connector_capabilties(connector_nr)?
+            .miscellaneous_capabilities
+            .contains(capability)
+        {
+            Ok(())
+        } else {
+            Err(Error::NotSupported {
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })
+        }
     }
 }
 
 impl OsBackend for UcsiDebugfsBackend {
     fn capabilities(&mut self) -> Result<Capability> {
-        let cmd = Command::GetCapability;
-        let response = self.execute(cmd)?;
-        let mut bitreader = BitReader::new(Cursor::new(&response[..]));
-        Capability::from_bytes(&mut bitreader)
+        let mut capability = self.execute(commands::GetCapability)?;
+        if let Some(ucsi_version) = self.ucsi_version {
+            capability.ucsi_version = ucsi_version;
+        }
+        Ok(capability)
     }
 
     fn connector_capabilties(&mut self, connector_nr: usize) -> Result<ConnectorCapability> {
-        let cmd = Command::GetConnectorCapability { connector_nr };
-        let response = self.execute(cmd)?;
-        let mut bitreader = BitReader::new(Cursor::new(&response[..]));
-        ConnectorCapability::from_bytes(&mut bitreader)
+        self.execute(commands::GetConnectorCapability { connector_nr })
     }
 
     fn alternate_modes(
@@ -190,50 +472,89 @@ impl OsBackend for UcsiDebugfsBackend {
     ) -> Result<Vec<AlternateMode>> {
         let mut alternate_modes = vec![];
         let mut offset = 0;
-        loop {
-            let cmd = Command::GetAlternateModes {
-                recipient,
-                connector_nr,
-                offset,
-            };
-
-            let response = self.execute(cmd)?;
-            if response.is_null() {
-                break;
-            }
-
-            let mut bitreader = BitReader::new(Cursor::new(&response[..]));
-            alternate_modes.push(AlternateMode::from_bytes(&mut bitreader)?);
+        while let Some(alternate_mode) = self.execute(commands::GetAlternateModes {
+            recipient,
+            connector_nr,
+            offset,
+        })? {
+            alternate_modes.push(alternate_mode);
             offset += 1;
         }
 
         Ok(alternate_modes)
     }
 
+    fn supported_alternate_modes(&mut self, connector_nr: usize) -> Result<Vec<usize>> {
+        Ok(self
+            .execute(commands::GetCamSupported { connector_nr })?
+            .offsets())
+    }
+
+    fn current_alternate_modes(&mut self, connector_nr: usize) -> Result<Vec<usize>> {
+        Ok(self
+            .execute(commands::GetCurrentCam { connector_nr })?
+            .offsets()
+            .to_vec())
+    }
+
     fn cable_properties(&mut self, connector_nr: usize) -> Result<CableProperty> {
-        let cmd = Command::GetCableProperty { connector_nr };
-        let response = self.execute(cmd)?;
-        let mut bitreader = BitReader::new(Cursor::new(&response[..]));
-        CableProperty::from_bytes(&mut bitreader)
+        self.execute(commands::GetCableProperty { connector_nr })
     }
 
-    fn connector_status(&mut self, _: usize) -> Result<ConnectorStatus> {
-        Err(Error::NotSupported {
-            #[cfg(feature = "backtrace")]
-            backtrace: std::backtrace::Backtrace::capture(),
-        })
+    fn connector_status(&mut self, connector_nr: usize) -> Result<ConnectorStatus> {
+        self.execute(commands::GetConnectorStatus { connector_nr })
     }
 
     fn pd_message(
         &mut self,
-        _: usize,
-        _: MessageRecipient,
-        _: MessageResponseType,
+        connector_nr: usize,
+        recipient: MessageRecipient,
+        response_type: MessageResponseType,
     ) -> Result<Message> {
-        Err(Error::NotSupported {
-            #[cfg(feature = "backtrace")]
-            backtrace: std::backtrace::Backtrace::capture(),
-        })
+        match response_type {
+            MessageResponseType::DiscoverIdentity => {
+                // The VDM Header followed by up to six VDOs.
+                const MESSAGE_LEN: usize = 4 * (1 + DiscoverIdentityResponse::MAX_VDOS);
+                let chunk_len = self.message_in_len();
+
+                let mut message = Vec::with_capacity(MESSAGE_LEN);
+                while message.len() < MESSAGE_LEN {
+                    let nr_bytes = std::cmp::min(chunk_len, MESSAGE_LEN - message.len());
+                    let chunk = self.execute(commands::GetPdMessage {
+                        connector_nr: connector_nr as u32,
+                        recipient,
+                        offset: message.len() as u32,
+                        nr_bytes: nr_bytes as u32,
+                        message_type: response_type,
+                    })?;
+                    if chunk.is_empty() {
+                        break;
+                    }
+
+                    message.extend_from_slice(&chunk);
+                }
+
+                if message.len() < 4 {
+                    return Err(Error::NotSupported {
+                        #[cfg(feature = "backtrace")]
+                        backtrace: std::backtrace::Backtrace::capture(),
+                    });
+                }
+
+                let dwords = message
+                    .chunks_exact(4)
+                    .map(|dword| u32::from_le_bytes([dword[0], dword[1], dword[2], dword[3]]))
+                    .collect::<Vec<_>>();
+
+                Ok(Message::Pd3p2DiscoverIdentityResponse(
+                    DiscoverIdentityResponse::from_message(&dwords, recipient)?,
+                ))
+            }
+            _ => Err(Error::NotSupported {
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            }),
+        }
     }
 
     fn pdos(
@@ -246,45 +567,227 @@ impl OsBackend for UcsiDebugfsBackend {
         source_capabilities_type: PdoSourceCapabilitiesType,
         revision: BcdWrapper,
     ) -> Result<Vec<crate::pd::Pdo>> {
+        // The EPR offsets were introduced with UCSI 2.0.
+        let max_offset = match self.ucsi_version() {
+            Some(version) if version < UcsiVersion::V2_0 => EPR_PDO_OFFSET,
+            _ => MAX_PDO_OFFSET,
+        };
+        let end = match nr_pdos {
+            0 => max_offset,
+            nr_pdos => (pdo_offset + nr_pdos as u32).min(max_offset),
+        };
+
         let mut pdos = vec![];
-        let mut nr_pdos_returned = 0;
-        loop {
-            if nr_pdos > 0 && nr_pdos_returned == nr_pdos {
-                break;
-            }
+        let mut offset = pdo_offset;
+        while offset < end {
+            // A single request cannot span both the SPR and the EPR ranges.
+            let range_end = if offset < EPR_PDO_OFFSET {
+                end.min(EPR_PDO_OFFSET)
+            } else {
+                end
+            };
+            let count = (range_end - offset).min(MAX_PDOS_PER_RESPONSE);
 
-            let cmd = Command::GetPdos {
+            let cmd = commands::GetPdos {
                 connector_nr,
                 partner_pdo,
-                pdo_offset,
-                nr_pdos,
+                pdo_offset: offset,
+                nr_pdos: count as usize - 1,
                 pdo_type,
                 source_capabilities_type,
             };
 
-            let response = self.execute(cmd)?;
-            if response.is_null() {
-                break;
-            }
+            let response = match self.execute(cmd) {
+                Ok(response) => response,
+                // PPMs without EPR support may reject the EPR offsets when we
+                // continue past the SPR PDOs.
+                Err(
+                    Error::CommandFailed { .. }
+                    | Error::CommandNotSupported { .. }
+                    | Error::NotSupported { .. },
+                ) if offset == EPR_PDO_OFFSET && pdo_offset < EPR_PDO_OFFSET => {
+                    break;
+                }
+                Err(err) => return Err(err),
+            };
 
-            let mut bitreader = BitReader::new(Cursor::new(&response[..]));
-            let pdo = Pdo::from_bytes(&mut bitreader, revision)?;
-            pdos.push(pdo);
+            for pdo in &response {
+                let pdo = pdo.to_le_bytes();
+                let mut bitreader = BitReader::new(Cursor::new(&pdo[..]));
+                pdos.push(Pdo::from_bytes(&mut bitreader, revision)?);
+            }
 
-            nr_pdos_returned += 1;
+            offset += count;
+            if (response.len() as u32) < count {
+                // The range is exhausted. The EPR PDOs always start at the
+                // same offset, regardless of the number of SPR PDOs.
+                if offset <= EPR_PDO_OFFSET && end > EPR_PDO_OFFSET {
+                    offset = EPR_PDO_OFFSET;
+                } else {
+                    break;
+                }
+            }
         }
 
         Ok(pdos)
     }
+
+    fn error_status(&mut self, connector_nr: Option<usize>) -> Result<ErrorStatus> {
+        // The connector number is only part of the command since UCSI 2.0.
+        let connector_nr = connector_nr.filter(|_| {
+            self.ucsi_version()
+                .is_some_and(|version| version >= UcsiVersion::V2_0)
+        });
+        self.execute(commands::GetErrorStatus { connector_nr })
+    }
+
+    fn attention_vdo(&mut self, connector_nr: usize) -> Result<AttentionVdo> {
+        self.require_feature(|features| features.get_attention_vdo_supported)?;
+        self.execute(commands::GetAttentionVdo { connector_nr })
+    }
+
+    fn lpm_ppm_info(&mut self, connector_nr: usize) -> Result<LpmPpmInfo> {
+        self.execute(commands::GetLpmPpmInfo { connector_nr })
+    }
+
+    fn power_level(
+        &mut self,
+        connector_nr: usize,
+        power_direction: PowerDirection,
+    ) -> Result<PowerLevel> {
+        self.require_feature(|features| features.set_power_level_supported)?;
+        self.execute(commands::ReadPowerLevel {
+            connector_nr,
+            power_direction,
+        })
+    }
+
+    fn control(&mut self) -> Result<&mut dyn OsControl> {
+        Ok(self)
+    }
+}
+
+// Linux debugfs rejects PPM_RESET, ACK_CC_CI, SET_NOTIFICATION_ENABLE,
+// SET_POWER_LEVEL and SET_RETIMER_MODE with EOPNOTSUPP, which surfaces as
+// Error::NotSupported. These only reach a PPM through other transports, such
+// as the simulator.
+impl OsControl for UcsiDebugfsBackend {
+    fn ppm_reset(&mut self) -> Result<()> {
+        self.execute(commands::PpmReset)
+    }
+
+    fn connector_reset(
+        &mut self,
+        connector_nr: usize,
+        reset_type: ConnectorResetType,
+    ) -> Result<()> {
+        self.execute(commands::ConnectorReset {
+            connector_nr,
+            reset_type,
+        })
+    }
+
+    fn ack_cc_ci(&mut self, acknowledgement: Acknowledgement) -> Result<()> {
+        self.execute(commands::AckCcCi { acknowledgement })
+    }
+
+    fn set_notification_enable(&mut self, notification_enable: NotificationEnable) -> Result<()> {
+        self.execute(commands::SetNotificationEnable {
+            notification_enable,
+        })
+    }
+
+    fn set_power_level(
+        &mut self,
+        connector_nr: usize,
+        power_direction: PowerDirection,
+        power_level: PowerLevel,
+    ) -> Result<()> {
+        self.require_feature(|features| features.set_power_level_supported)?;
+        self.execute(commands::SetPowerLevel {
+            connector_nr,
+            power_direction,
+            power_level,
+        })
+    }
+
+    fn set_sink_path(&mut self, connector_nr: usize, enable: bool) -> Result<()> {
+        self.execute(commands::SetSinkPath {
+            connector_nr,
+            enable,
+        })
+    }
+
+    fn fw_update_request(&mut self, connector_nr: usize, request: &[u8]) -> Result<Vec<u8>> {
+        self.require_feature(|features| features.fw_update_request_supported)?;
+        self.require_connector_capability(
+            connector_nr,
+            ConnectorCapabilityMiscellaneousCapabilities::FW_UPDATE,
+        )?;
+        self.message_out_request(request, |chunk_number, last_chunk, data| {
+            commands::LpmFwUpdateRequest {
+                connector_nr,
+                chunk_number,
+                last_chunk,
+                data,
+            }
+        })
+    }
+
+    fn security_request(&mut self, connector_nr: usize, request: &[u8]) -> Result<Vec<u8>> {
+        self.require_feature(|features| features.security_request_supported)?;
+        self.require_connector_capability(
+            connector_nr,
+            ConnectorCapabilityMiscellaneousCapabilities::SECURITY,
+        )?;
+        self.message_out_request(request, |chunk_number, last_chunk, data| {
+            commands::SecurityRequest {
+                connector_nr,
+                chunk_number,
+                last_chunk,
+                data,
+            }
+        })
+    }
+
+    fn set_retimer_mode(&mut self, connector_nr: usize, retimer_mode: u32) -> Result<()> {
+        self.require_feature(|features| features.set_retimer_mode_supported)?;
+        self.execute(commands::SetRetimerMode {
+            connector_nr,
+            retimer_mode,
+        })
+    }
+
+    fn raw_ucsi_command(&mut self, control: &[u8]) -> Result<RawResponse> {
+        if control.is_empty() || control.len() > std::mem::size_of::<u64>() {
+            return Err(Error::ParseError {
+                field: "control".into(),
+                value: control.len() as u32,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            });
+        }
+
+        let mut bytes = [0; 8];
+        bytes[..control.len()].copy_from_slice(control);
+        let control = u64::from_le_bytes(bytes);
+
+        self.transport
+            .execute_raw(control)
+            .map_err(Self::transport_error)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ucsi::UsbTypeCCurrent;
+    use crate::FromBytes;
+    use crate::Milliwatt;
 
     impl From<Driver> for UcsiDebugfsBackend {
         fn from(mock: Driver) -> Self {
-            Self { driver: mock }
+            Self::with_transport(Box::new(mock))
         }
     }
 
@@ -297,6 +800,29 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_parse_response() {
+        let response = b"0x000000000000000100000000deadbeef\n".to_vec();
+        let result = UcsiDebugfsBackend::parse_response(response).unwrap();
+        let mut expected = vec![0xef, 0xbe, 0xad, 0xde, 0, 0, 0, 0];
+        expected.extend([1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(result, expected);
+
+        for response in [
+            &b""[..],
+            b"\n",
+            b"0x\n",
+            b"0x123\n",
+            b"deadbeef\n",
+            b"0xzz\n",
+        ] {
+            assert!(matches!(
+                UcsiDebugfsBackend::parse_response(response.to_vec()),
+                Err(Error::ParseError { .. })
+            ));
+        }
+    }
+
     #[test]
     fn test_build_command_value_get_connector_capability() {
         let command = Command::GetConnectorCapability { connector_nr: 0 };
@@ -305,4 +831,185 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_build_command_value_golden() {
+        // The expected values are built from the field offsets of the Linux
+        // UCSI_* macros, or of the UCSI 3.0 specification for the commands
+        // Linux does not send. The connector number is 1-based in CONTROL.
+        let connector_1 = 1u64 << 16;
+        let golden = [
+            (Command::PpmReset, 0x01),
+            (
+                Command::ConnectorReset {
+                    connector_nr: 0,
+                    reset_type: ConnectorResetType::Data,
+                },
+                0x03 | connector_1 | (1 << 23),
+            ),
+            (
+                Command::AckCcCi {
+                    acknowledgement: Acknowledgement::COMMAND_COMPLETED,
+                },
+                0x04 | (1 << 17),
+            ),
+            (
+                Command::SetNotificationEnable {
+                    notification_enable: NotificationEnable::COMMAND_COMPLETED
+                        | NotificationEnable::CONNECT_CHANGE,
+                },
+                0x05 | (1 << 16) | (1 << 30),
+            ),
+            (Command::GetCapability, 0x06),
+            (
+                Command::GetConnectorCapability { connector_nr: 1 },
+                0x07 | (2 << 16),
+            ),
+            (
+                Command::GetAlternateModes {
+                    recipient: GetAlternateModesRecipient::Sop,
+                    connector_nr: 1,
+                    offset: 2,
+                },
+                0x0c | (1 << 16) | (2 << 24) | (2 << 32),
+            ),
+            (
+                Command::GetCamSupported { connector_nr: 0 },
+                0x0d | connector_1,
+            ),
+            (
+                Command::GetCurrentCam { connector_nr: 0 },
+                0x0e | connector_1,
+            ),
+            (
+                Command::GetPdos {
+                    connector_nr: 0,
+                    partner_pdo: true,
+                    pdo_offset: 4,
+                    nr_pdos: 3,
+                    pdo_type: PdoType::Source,
+                    source_capabilities_type: PdoSourceCapabilitiesType::AdvertisedCapabilities,
+                },
+                0x10 | connector_1 | (1 << 23) | (4 << 24) | (3 << 32) | (1 << 34) | (1 << 35),
+            ),
+            (
+                Command::GetCableProperty { connector_nr: 0 },
+                0x11 | connector_1,
+            ),
+            (
+                Command::GetConnectorStatus { connector_nr: 0 },
+                0x12 | connector_1,
+            ),
+            (Command::GetErrorStatus { connector_nr: None }, 0x13),
+            (
+                Command::GetErrorStatus {
+                    connector_nr: Some(0),
+                },
+                0x13 | connector_1,
+            ),
+            (
+                Command::SetPowerLevel {
+                    connector_nr: 0,
+                    power_direction: PowerDirection::Provider,
+                    power_level: PowerLevel {
+                        max_power: Milliwatt(15000),
+                        usb_type_c_current: UsbTypeCCurrent::Current3A,
+                    },
+                },
+                0x14 | connector_1 | (1 << 23) | (30 << 24) | (1 << 32),
+            ),
+            (
+                Command::GetAttentionVdo { connector_nr: 0 },
+                0x16 | connector_1,
+            ),
+            (
+                Command::LpmFwUpdateRequest {
+                    connector_nr: 0,
+                    data_length: 16,
+                    chunk_number: 2,
+                    last_chunk: true,
+                },
+                0x19 | (16 << 8) | connector_1 | (2 << 24) | (1 << 32),
+            ),
+            (
+                Command::SecurityRequest {
+                    connector_nr: 0,
+                    data_length: 16,
+                    chunk_number: 0,
+                    last_chunk: false,
+                },
+                0x1a | (16 << 8) | connector_1,
+            ),
+            (
+                Command::SetRetimerMode {
+                    connector_nr: 0,
+                    retimer_mode: 0x12345678,
+                },
+                0x1b | connector_1 | (0x12345678 << 32),
+            ),
+            (
+                Command::SetSinkPath {
+                    connector_nr: 0,
+                    enable: true,
+                },
+                0x1c | connector_1 | (1 << 23),
+            ),
+            (
+                Command::ReadPowerLevel {
+                    connector_nr: 0,
+                    power_direction: PowerDirection::Provider,
+                },
+                0x1e | connector_1 | (1 << 23),
+            ),
+            (
+                Command::GetLpmPpmInfo { connector_nr: 0 },
+                0x22 | connector_1,
+            ),
+        ];
+
+        for (command, expected) in golden {
+            let result = UcsiDebugfsBackend::build_command_value(&command).unwrap();
+            assert_eq!(result, expected, "{command:?}");
+
+            // The simulator decodes CONTROL with the same layout.
+            let bytes = expected.to_le_bytes();
+            let decoded = Command::from_bytes(&mut BitReader::new(Cursor::new(&bytes))).unwrap();
+            assert_eq!(decoded, command);
+        }
+    }
+
+    #[test]
+    fn test_build_command_value_get_pd_message() {
+        // Discover Identity of SOP on connector 1, 16 bytes from offset 0.
+        let command = Command::GetPdMessage {
+            connector_nr: 0,
+            recipient: MessageRecipient::Sop,
+            offset: 0,
+            nr_bytes: 16,
+            message_type: MessageResponseType::DiscoverIdentity,
+        };
+        let result = UcsiDebugfsBackend::build_command_value(&command).unwrap();
+        let expected = 0x15 | (1 << 16) | (1 << 23) | (16 << 34) | (4 << 40);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_transport_error() {
+        let errno = |errno| {
+            UcsiDebugfsBackend::transport_error(std::io::Error::from_raw_os_error(errno).into())
+        };
+
+        assert!(matches!(
+            errno(nix::libc::ECOMM),
+            Error::CommandFailed { error_information, .. }
+                if error_information == ErrorInformation::CC_COMMUNICATION_ERROR
+        ));
+        assert!(matches!(
+            errno(nix::libc::EOPNOTSUPP),
+            Error::NotSupported { .. }
+        ));
+        assert!(matches!(errno(nix::libc::EBUSY), Error::PpmBusy { .. }));
+        assert!(matches!(errno(nix::libc::ENOENT), Error::IoError { .. }));
+    }
 }
@@ -0,0 +1,871 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! An in-process PPM.
+//!
+//! [`PpmSimulator`] answers the commands issued by [`UcsiDebugfsBackend`]
+//! from a description of its ports, which allows the UCSI code path to be
+//! exercised without hardware.
+//!
+//! [`UcsiDebugfsBackend`]: super::UcsiDebugfsBackend
+
+use std::io::Cursor;
+
+use bitstream_io::BitWrite;
+
+use crate::pd::MessageRecipient;
+use crate::pd::MessageResponseType;
+use crate::ucsi::AlternateMode;
+use crate::ucsi::AttentionVdo;
+use crate::ucsi::CableProperty;
+use crate::ucsi::CamSupported;
+use crate::ucsi::Capability;
+use crate::ucsi::Cci;
+use crate::ucsi::Command;
+use crate::ucsi::ConnectorCapability;
+use crate::ucsi::ConnectorStatus;
+use crate::ucsi::CurrentAlternatingModes;
+use crate::ucsi::ErrorInformation;
+use crate::ucsi::ErrorStatus;
+use crate::ucsi::GetAlternateModesRecipient;
+use crate::ucsi::LpmPpmInfo;
+use crate::ucsi::NotificationEnable;
+use crate::ucsi::PdoType;
+use crate::ucsi::PowerDirection;
+use crate::ucsi::PowerLevel;
+use crate::ucsi::RawResponse;
+use crate::ucsi::SinkPathStatus;
+use crate::BcdWrapper;
+use crate::BitReader;
+use crate::BitWriter;
+use crate::Error;
+use crate::FromBytes;
+use crate::Result;
+use crate::ToBytes;
+
+use super::UcsiTransport;
+
+/// The size of MESSAGE_IN in UCSI 1.2, which is what the Linux driver
+/// exposes through debugfs.
+const MESSAGE_IN_LEN: usize = 16;
+
+/// An alternate mode supported by a simulated connector or its partner.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SimulatedAlternateMode {
+    /// The recipient reporting the mode.
+    pub recipient: GetAlternateModesRecipient,
+    pub svid: u32,
+    /// The mode VDO.
+    pub vdo: u32,
+}
+
+/// A PD message returned by GET_PD_MESSAGE.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SimulatedPdMessage {
+    pub recipient: MessageRecipient,
+    pub message_type: MessageResponseType,
+    /// The message, starting with the VDM header for VDM responses.
+    pub data: Vec<u8>,
+}
+
+/// A connector of the simulated PPM.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SimulatedConnector {
+    /// Returned by GET_CONNECTOR_CAPABILITY.
+    pub capability: ConnectorCapability,
+    /// Returned by GET_CONNECTOR_STATUS.
+    pub status: ConnectorStatus,
+    /// Returned by GET_CABLE_PROPERTY. The command is reported as not
+    /// supported if `None`.
+    pub cable_property: Option<CableProperty>,
+    /// Returned by GET_ALTERNATE_MODES, one mode per offset.
+    pub alternate_modes: Vec<SimulatedAlternateMode>,
+    /// Offsets into the connector's alternate modes, returned by
+    /// GET_CAM_SUPPORTED.
+    pub supported_alternate_modes: Vec<usize>,
+    /// Offsets into the connector's alternate modes, returned by
+    /// GET_CURRENT_CAM.
+    pub current_alternate_modes: Vec<usize>,
+    /// The PDOs of the connector, returned by GET_PDOS.
+    pub source_pdos: Vec<u32>,
+    pub sink_pdos: Vec<u32>,
+    /// The PDOs of the port partner, returned by GET_PDOS.
+    pub partner_source_pdos: Vec<u32>,
+    pub partner_sink_pdos: Vec<u32>,
+    /// Returned by GET_PD_MESSAGE.
+    pub pd_messages: Vec<SimulatedPdMessage>,
+    /// Returned by GET_ATTENTION_VDO. The command is reported as not
+    /// supported if `None`.
+    pub attention_vdo: Option<AttentionVdo>,
+    /// Returned by READ_POWER_LEVEL.
+    pub source_power_level: PowerLevel,
+    pub sink_power_level: PowerLevel,
+    /// Returned by GET_LPM_PPM_INFO. The command is reported as not
+    /// supported if `None`.
+    pub lpm_ppm_info: Option<LpmPpmInfo>,
+    /// Set by SET_RETIMER_MODE.
+    pub retimer_mode: u32,
+    /// Returned by the last chunk of LPM_FW_UPDATE_REQUEST. The command is
+    /// reported as not supported if `None`.
+    pub fw_update_response: Option<Vec<u8>>,
+    /// The last LPM_FW_UPDATE_REQUEST, reassembled from its chunks.
+    pub fw_update_request: Vec<u8>,
+    /// Returned by the last chunk of SECURITY_REQUEST. The command is
+    /// reported as not supported if `None`.
+    pub security_response: Option<Vec<u8>>,
+    /// The last SECURITY_REQUEST, reassembled from its chunks.
+    pub security_request: Vec<u8>,
+}
+
+/// A PPM answering commands from a declarative description of its ports.
+///
+/// Commands that the description does not cover are reported as not
+/// supported, and commands addressing a connector that does not exist fail
+/// with [`ErrorInformation::NON_EXISTENT_CONNECTOR_NUMBER`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PpmSimulator {
+    /// Returned by GET_CAPABILITY.
+    pub capability: Capability,
+    /// The connectors, starting from connector 0.
+    pub connectors: Vec<SimulatedConnector>,
+    /// Why the previous command failed, returned by GET_ERROR_STATUS.
+    error_information: ErrorInformation,
+    /// Set by SET_NOTIFICATION_ENABLE and cleared by PPM_RESET.
+    notification_enable: NotificationEnable,
+}
+
+impl PpmSimulator {
+    /// Creates a PPM with the given connectors. The number of connectors in
+    /// `capability` is set accordingly.
+    pub fn new(mut capability: Capability, connectors: Vec<SimulatedConnector>) -> Self {
+        capability.num_connectors = connectors.len();
+        Self {
+            capability,
+            connectors,
+            error_information: ErrorInformation::empty(),
+            notification_enable: NotificationEnable::empty(),
+        }
+    }
+
+    /// The notifications the OPM enabled.
+    pub fn notification_enable(&self) -> NotificationEnable {
+        self.notification_enable
+    }
+
+    fn connector(&self, connector_nr: usize) -> Result<&SimulatedConnector> {
+        self.connectors
+            .get(connector_nr)
+            .ok_or_else(non_existent_connector)
+    }
+
+    fn connector_mut(&mut self, connector_nr: usize) -> Result<&mut SimulatedConnector> {
+        self.connectors
+            .get_mut(connector_nr)
+            .ok_or_else(non_existent_connector)
+    }
+
+    fn respond(&mut self, command: Command, message_out: &[u8]) -> Result<Vec<u8>> {
+        // Leave room for responses that do not fit in MESSAGE_IN, such as the
+        // connector status of UCSI 3.0. They are truncated.
+        let mut message_in = [0; 2 * MESSAGE_IN_LEN];
+        let mut bw = BitWriter::new(Cursor::new(&mut message_in[..]));
+
+        match command {
+            Command::PpmReset => self.notification_enable = NotificationEnable::empty(),
+            Command::ConnectorReset { connector_nr, .. } => {
+                self.connector(connector_nr)?;
+            }
+            Command::AckCcCi { .. } => {}
+            Command::SetNotificationEnable {
+                notification_enable,
+            } => self.notification_enable = notification_enable,
+            Command::SetPowerLevel {
+                connector_nr,
+                power_direction,
+                power_level,
+            } => {
+                let connector = self.connector_mut(connector_nr)?;
+                match power_direction {
+                    PowerDirection::Provider => connector.source_power_level = power_level,
+                    PowerDirection::Consumer => connector.sink_power_level = power_level,
+                }
+            }
+            Command::SetSinkPath {
+                connector_nr,
+                enable,
+            } => {
+                self.connector_mut(connector_nr)?.status.sink_path_status = if enable {
+                    SinkPathStatus::Ready
+                } else {
+                    SinkPathStatus::NotReady
+                };
+            }
+            Command::SetRetimerMode {
+                connector_nr,
+                retimer_mode,
+            } => self.connector_mut(connector_nr)?.retimer_mode = retimer_mode,
+            Command::LpmFwUpdateRequest {
+                connector_nr,
+                data_length,
+                chunk_number,
+                last_chunk,
+            }
+            | Command::SecurityRequest {
+                connector_nr,
+                data_length,
+                chunk_number,
+                last_chunk,
+            } => {
+                let chunk = message_out
+                    .get(..usize::from(data_length))
+                    .ok_or_else(invalid_parameters)?;
+                let connector = self.connector_mut(connector_nr)?;
+                let (request, response) = match command {
+                    Command::LpmFwUpdateRequest { .. } => (
+                        &mut connector.fw_update_request,
+                        &connector.fw_update_response,
+                    ),
+                    _ => (
+                        &mut connector.security_request,
+                        &connector.security_response,
+                    ),
+                };
+                let response = response.as_ref().ok_or_else(not_supported)?;
+
+                if chunk_number == 0 {
+                    request.clear();
+                }
+                request.extend_from_slice(chunk);
+                if last_chunk {
+                    bw.write_bytes(response)?;
+                }
+            }
+            Command::GetCapability => self.capability.to_bytes(&mut bw)?,
+            Command::GetConnectorCapability { connector_nr } => {
+                self.connector(connector_nr)?.capability.to_bytes(&mut bw)?
+            }
+            Command::GetConnectorStatus { connector_nr } => {
+                self.connector(connector_nr)?.status.to_bytes(&mut bw)?
+            }
+            Command::GetCableProperty { connector_nr } => {
+                match &self.connector(connector_nr)?.cable_property {
+                    Some(cable_property) => cable_property.to_bytes(&mut bw)?,
+                    None => return Err(not_supported()),
+                }
+            }
+            Command::GetAlternateModes {
+                recipient,
+                connector_nr,
+                offset,
+            } => {
+                let mode = self
+                    .connector(connector_nr)?
+                    .alternate_modes
+                    .iter()
+                    .filter(|mode| mode.recipient == recipient)
+                    .nth(offset);
+                if let Some(mode) = mode {
+                    let alternate_mode = AlternateMode {
+                        svid: [mode.svid, 0],
+                        vdo: [mode.vdo, 0],
+                    };
+                    alternate_mode.to_bytes(&mut bw)?;
+                }
+            }
+            Command::GetCamSupported { connector_nr } => {
+                let connector = self.connector(connector_nr)?;
+                CamSupported::from_offsets(&connector.supported_alternate_modes)
+                    .to_bytes(&mut bw)?
+            }
+            Command::GetCurrentCam { connector_nr } => {
+                let connector = self.connector(connector_nr)?;
+                CurrentAlternatingModes::from_offsets(&connector.current_alternate_modes)
+                    .to_bytes(&mut bw)?
+            }
+            Command::GetPdos {
+                connector_nr,
+                partner_pdo,
+                pdo_offset,
+                nr_pdos,
+                pdo_type,
+                ..
+            } => {
+                let connector = self.connector(connector_nr)?;
+                let pdos = match (partner_pdo, pdo_type) {
+                    (false, PdoType::Source) => &connector.source_pdos,
+                    (false, PdoType::Sink) => &connector.sink_pdos,
+                    (true, PdoType::Source) => &connector.partner_source_pdos,
+                    (true, PdoType::Sink) => &connector.partner_sink_pdos,
+                };
+                // A PPM without EPR PDOs rejects the EPR offsets.
+                if pdo_offset >= super::EPR_PDO_OFFSET
+                    && pdos.len() <= super::EPR_PDO_OFFSET as usize
+                {
+                    return Err(invalid_parameters());
+                }
+                for pdo in pdos.iter().skip(pdo_offset as usize).take(nr_pdos + 1) {
+                    bw.write(32, *pdo)?;
+                }
+            }
+            Command::GetPdMessage {
+                connector_nr,
+                recipient,
+                offset,
+                nr_bytes,
+                message_type,
+            } => {
+                let message = self
+                    .connector(connector_nr as usize)?
+                    .pd_messages
+                    .iter()
+                    .find(|message| {
+                        message.recipient == recipient && message.message_type == message_type
+                    })
+                    .ok_or_else(not_supported)?;
+                let chunk = message
+                    .data
+                    .iter()
+                    .skip(offset as usize)
+                    .take(nr_bytes as usize);
+                for byte in chunk {
+                    bw.write(8, *byte)?;
+                }
+            }
+            Command::GetErrorStatus { .. } => ErrorStatus {
+                error_information: self.error_information,
+            }
+            .to_bytes(&mut bw)?,
+            Command::GetAttentionVdo { connector_nr } => {
+                match &self.connector(connector_nr)?.attention_vdo {
+                    Some(attention_vdo) => attention_vdo.to_bytes(&mut bw)?,
+                    None => return Err(not_supported()),
+                }
+            }
+            Command::ReadPowerLevel {
+                connector_nr,
+                power_direction,
+            } => {
+                let connector = self.connector(connector_nr)?;
+                match power_direction {
+                    PowerDirection::Provider => connector.source_power_level.to_bytes(&mut bw)?,
+                    PowerDirection::Consumer => connector.sink_power_level.to_bytes(&mut bw)?,
+                }
+            }
+            Command::GetLpmPpmInfo { connector_nr } => {
+                match &self.connector(connector_nr)?.lpm_ppm_info {
+                    Some(lpm_ppm_info) => lpm_ppm_info.to_bytes(&mut bw)?,
+                    None => return Err(not_supported()),
+                }
+            }
+        }
+
+        bw.byte_align()?;
+        Ok(message_in[..MESSAGE_IN_LEN].to_vec())
+    }
+}
+
+fn non_existent_connector() -> Error {
+    Error::CommandFailed {
+        error_information: ErrorInformation::NON_EXISTENT_CONNECTOR_NUMBER,
+        #[cfg(feature = "backtrace")]
+        backtrace: std::backtrace::Backtrace::capture(),
+    }
+}
+
+fn invalid_parameters() -> Error {
+    Error::CommandFailed {
+        error_information: ErrorInformation::INVALID_COMMAND_SPECIFIC_PARAMETERS,
+        #[cfg(feature = "backtrace")]
+        backtrace: std::backtrace::Backtrace::capture(),
+    }
+}
+
+fn not_supported() -> Error {
+    Error::CommandNotSupported {
+        #[cfg(feature = "backtrace")]
+        backtrace: std::backtrace::Backtrace::capture(),
+    }
+}
+
+impl UcsiTransport for PpmSimulator {
+    fn execute(&mut self, control: u64) -> Result<Vec<u8>> {
+        self.execute_with_message_out(control, &[])
+    }
+
+    fn execute_with_message_out(&mut self, control: u64, message_out: &[u8]) -> Result<Vec<u8>> {
+        let bytes = control.to_le_bytes();
+        let command = Command::from_bytes(&mut BitReader::new(Cursor::new(&bytes)))
+            .map_err(|_| not_supported())?;
+        let is_error_status = matches!(command, Command::GetErrorStatus { .. });
+        let response = self.respond(command, message_out);
+        if !is_error_status {
+            self.error_information = match &response {
+                Err(Error::CommandFailed {
+                    error_information, ..
+                }) => *error_information,
+                _ => ErrorInformation::empty(),
+            };
+        }
+        response
+    }
+
+    fn execute_raw(&mut self, control: u64) -> Result<RawResponse> {
+        let mut cci = Cci {
+            command_completed: true,
+            ..Default::default()
+        };
+        let message_in = match self.execute(control) {
+            Ok(message_in) => message_in,
+            Err(Error::CommandNotSupported { .. }) => {
+                cci.not_supported = true;
+                vec![]
+            }
+            Err(Error::CommandFailed { .. }) => {
+                cci.error = true;
+                vec![]
+            }
+            Err(err) => return Err(err),
+        };
+        cci.data_length = message_in.len().min(u8::MAX.into()) as u8;
+
+        Ok(RawResponse {
+            cci: Some(cci),
+            message_in,
+        })
+    }
+
+    fn ucsi_version(&mut self) -> Result<Option<BcdWrapper>> {
+        Ok(Some(self.capability.ucsi_version).filter(|version| *version != BcdWrapper::default()))
+    }
+
+    fn max_message_in_len(&self) -> usize {
+        MESSAGE_IN_LEN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::ucsi_debugfs::UcsiDebugfsBackend;
+    use crate::pd::pd3p2::DiscoverIdentityResponse;
+    use crate::pd::Message;
+    use crate::ucsi::Acknowledgement;
+    use crate::ucsi::ConnectorCapabilityMiscellaneousCapabilities;
+    use crate::ucsi::ConnectorCapabilityOperationMode;
+    use crate::ucsi::ConnectorOrientation;
+    use crate::ucsi::ConnectorPartnerType;
+    use crate::ucsi::ConnectorResetType;
+    use crate::ucsi::PdoSourceCapabilitiesType;
+    use crate::ucsi::PowerDirection;
+    use crate::ucsi::PowerOperationMode;
+    use crate::ucsi::UcsiVersion;
+    use crate::ucsi::UsbTypeCCurrent;
+    use crate::vdo::displayport::DISPLAYPORT_SVID;
+    use crate::Milliwatt;
+    use crate::OsBackend;
+
+    fn simulator() -> PpmSimulator {
+        let connector = SimulatedConnector {
+            capability: ConnectorCapability {
+                operation_mode: ConnectorCapabilityOperationMode::DRP
+                    | ConnectorCapabilityOperationMode::USB3,
+                provider: true,
+                consumer: true,
+                ..Default::default()
+            },
+            status: ConnectorStatus {
+                power_operation_mode: PowerOperationMode::PowerDelivery,
+                connect_status: true,
+                power_direction: PowerDirection::Provider,
+                connector_partner_type: ConnectorPartnerType::UfpAttached,
+                negotiated_power_level: 0x1304b12c,
+                ..Default::default()
+            },
+            alternate_modes: vec![SimulatedAlternateMode {
+                recipient: GetAlternateModesRecipient::Sop,
+                svid: DISPLAYPORT_SVID,
+                vdo: 0x1c0045,
+            }],
+            supported_alternate_modes: vec![0, 2],
+            current_alternate_modes: vec![2],
+            pd_messages: vec![SimulatedPdMessage {
+                recipient: MessageRecipient::Sop,
+                message_type: MessageResponseType::DiscoverIdentity,
+                // An ACK with an ID Header, a Cert Stat and a Product VDO.
+                data: [0xff00a041u32, 0x544018d1, 0, 0x50480000]
+                    .iter()
+                    .flat_map(|dword| dword.to_le_bytes())
+                    .collect(),
+            }],
+            ..Default::default()
+        };
+
+        let capability = Capability {
+            ucsi_version: BcdWrapper(0x0201),
+            ..Default::default()
+        };
+        PpmSimulator::new(capability, vec![connector])
+    }
+
+    #[test]
+    fn test_simulated_port() {
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(simulator()));
+
+        let capability = backend.capabilities().unwrap();
+        assert_eq!(capability.num_connectors, 1);
+        assert_eq!(capability.ucsi_version, BcdWrapper(0x0201));
+
+        let capability = backend.connector_capabilties(0).unwrap();
+        assert_eq!(capability, simulator().connectors[0].capability);
+
+        let status = backend.connector_status(0).unwrap();
+        assert_eq!(status, simulator().connectors[0].status);
+
+        let modes = backend
+            .alternate_modes(GetAlternateModesRecipient::Sop, 0)
+            .unwrap();
+        assert_eq!(modes.len(), 1);
+        assert_eq!(modes[0].svid[0], DISPLAYPORT_SVID);
+        assert!(backend
+            .alternate_modes(GetAlternateModesRecipient::SopPrime, 0)
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(backend.supported_alternate_modes(0).unwrap(), [0, 2]);
+        assert_eq!(backend.current_alternate_modes(0).unwrap(), [2]);
+
+        let message = backend
+            .pd_message(
+                0,
+                MessageRecipient::Sop,
+                MessageResponseType::DiscoverIdentity,
+            )
+            .unwrap();
+        let Message::Pd3p2DiscoverIdentityResponse(DiscoverIdentityResponse {
+            id_header_vdo, ..
+        }) = message
+        else {
+            panic!("expected a Discover Identity response, got {:?}", message);
+        };
+        assert_eq!(id_header_vdo.usb_vendor_id, 0x18d1);
+    }
+
+    #[test]
+    fn test_simulated_pdos() {
+        let mut ppm = simulator();
+        // Three SPR PDOs: 5V, 9V and 15V at 3A, then 28V, 36V and 48V at 5A.
+        ppm.connectors[0].partner_source_pdos = vec![
+            0x0001912c, 0x0002d12c, 0x0004b12c, 0, 0, 0, 0, 0, 0x0008c1f4, 0x000b41f4, 0x000f01f4,
+        ];
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(ppm));
+        let mut pdos = |pdo_offset, nr_pdos| {
+            backend
+                .pdos(
+                    0,
+                    true,
+                    pdo_offset,
+                    nr_pdos,
+                    PdoType::Source,
+                    PdoSourceCapabilitiesType::CurrentSupportedSourceCapabilities,
+                    BcdWrapper(0x310),
+                )
+                .unwrap()
+                .iter()
+                .map(|pdo| pdo.max_voltage().0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(pdos(0, 0), [5000, 9000, 15000, 28000, 36000, 48000]);
+        assert_eq!(pdos(1, 1), [9000]);
+        assert_eq!(pdos(9, 0), [36000, 48000]);
+    }
+
+    #[test]
+    fn test_simulated_pdos_without_epr() {
+        let spr_pdos = vec![0x0001912c, 0x0002d12c, 0x0004b12c];
+        let epr_pdos = vec![
+            0x0001912c, 0x0002d12c, 0x0004b12c, 0, 0, 0, 0, 0, 0x0008c1f4, 0x000b41f4, 0x000f01f4,
+        ];
+        let pdos = |ucsi_version, partner_source_pdos| {
+            let mut ppm = simulator();
+            ppm.capability.ucsi_version = BcdWrapper(ucsi_version);
+            ppm.connectors[0].partner_source_pdos = partner_source_pdos;
+            UcsiDebugfsBackend::with_transport(Box::new(ppm))
+                .pdos(
+                    0,
+                    true,
+                    0,
+                    0,
+                    PdoType::Source,
+                    PdoSourceCapabilitiesType::CurrentSupportedSourceCapabilities,
+                    BcdWrapper(0x310),
+                )
+                .unwrap()
+                .len()
+        };
+
+        // The PPM rejects offset 8, which ends the EPR range.
+        assert_eq!(pdos(0x0201, spr_pdos), 3);
+        // The EPR range is not requested from a UCSI 1.2 PPM.
+        assert_eq!(pdos(0x0120, epr_pdos.clone()), 3);
+        assert_eq!(pdos(0x0201, epr_pdos), 6);
+    }
+
+    #[test]
+    fn test_simulated_errors() {
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(simulator()));
+
+        assert!(matches!(
+            backend.cable_properties(0),
+            Err(Error::CommandNotSupported { .. })
+        ));
+        assert!(matches!(
+            backend.connector_status(1),
+            Err(Error::CommandFailed { error_information, .. })
+                if error_information == ErrorInformation::NON_EXISTENT_CONNECTOR_NUMBER
+        ));
+    }
+
+    #[test]
+    fn test_simulated_ucsi_1_2() {
+        let mut ppm = simulator();
+        ppm.capability.ucsi_version = BcdWrapper(0x0120);
+        ppm.connectors[0].status.orientation = ConnectorOrientation::Reverse;
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(ppm));
+        assert_eq!(backend.ucsi_version(), Some(UcsiVersion::V1_2));
+
+        // The orientation is a reserved bit in UCSI 1.2.
+        let status = backend.connector_status(0).unwrap();
+        assert_eq!(status.orientation, ConnectorOrientation::Normal);
+
+        assert!(matches!(
+            backend.pd_message(
+                0,
+                MessageRecipient::Sop,
+                MessageResponseType::DiscoverIdentity
+            ),
+            Err(Error::NotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_simulated_unknown_version() {
+        let mut ppm = simulator();
+        ppm.capability.ucsi_version = BcdWrapper::default();
+        ppm.connectors[0].status.orientation = ConnectorOrientation::Reverse;
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(ppm));
+        assert_eq!(backend.ucsi_version(), None);
+
+        // Without a revision, every field is decoded.
+        let status = backend.connector_status(0).unwrap();
+        assert_eq!(status.orientation, ConnectorOrientation::Reverse);
+
+        let message = backend
+            .pd_message(
+                0,
+                MessageRecipient::Sop,
+                MessageResponseType::DiscoverIdentity,
+            )
+            .unwrap();
+        assert!(matches!(message, Message::Pd3p2DiscoverIdentityResponse(_)));
+    }
+
+    #[test]
+    fn test_simulated_info() {
+        let mut ppm = simulator();
+        ppm.capability.ucsi_version = BcdWrapper(0x0300);
+        ppm.capability
+            .bm_optional_features
+            .get_attention_vdo_supported = true;
+        // A DisplayPort Attention with HPD high.
+        ppm.connectors[0].attention_vdo = Some(AttentionVdo {
+            vdm_header: 0xff018106,
+            vdo: 0x9a,
+        });
+        ppm.connectors[0].lpm_ppm_info = Some(LpmPpmInfo {
+            vendor_id: 0x18d1,
+            product_id: 0x5048,
+            fw_version: 0x0102,
+            hw_version: 3,
+            hw_version_valid: true,
+            ..Default::default()
+        });
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(ppm));
+
+        let attention_vdo = backend.attention_vdo(0).unwrap();
+        assert_eq!(attention_vdo.svid(), DISPLAYPORT_SVID);
+        assert_eq!(attention_vdo.vdo, 0x9a);
+
+        let info = backend.lpm_ppm_info(0).unwrap();
+        assert_eq!((info.vendor_id, info.fw_version), (0x18d1, 0x0102));
+        // The hardware version does not fit in MESSAGE_IN.
+        assert!(!info.hw_version_valid);
+
+        // SET_POWER_LEVEL is not advertised by the PPM.
+        assert!(matches!(
+            backend.power_level(0, PowerDirection::Provider),
+            Err(Error::NotSupported { .. })
+        ));
+
+        assert!(backend.connector_status(1).is_err());
+        assert_eq!(
+            backend.error_status(Some(1)).unwrap().error_information,
+            ErrorInformation::NON_EXISTENT_CONNECTOR_NUMBER
+        );
+    }
+
+    #[test]
+    fn test_simulated_control() {
+        let mut ppm = simulator();
+        let mut execute = |command| {
+            let control = UcsiDebugfsBackend::build_command_value(&command).unwrap();
+            ppm.execute(control).map(|_| ())
+        };
+
+        let notification_enable =
+            NotificationEnable::COMMAND_COMPLETED | NotificationEnable::CONNECT_CHANGE;
+        execute(Command::SetNotificationEnable {
+            notification_enable,
+        })
+        .unwrap();
+        execute(Command::AckCcCi {
+            acknowledgement: Acknowledgement::COMMAND_COMPLETED,
+        })
+        .unwrap();
+        assert!(matches!(
+            execute(Command::ConnectorReset {
+                connector_nr: 1,
+                reset_type: ConnectorResetType::Hard,
+            }),
+            Err(Error::CommandFailed { .. })
+        ));
+        execute(Command::SetRetimerMode {
+            connector_nr: 0,
+            retimer_mode: 0x12,
+        })
+        .unwrap();
+        assert_eq!(ppm.notification_enable(), notification_enable);
+        assert_eq!(ppm.connectors[0].retimer_mode, 0x12);
+
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(ppm.clone()));
+        let control = backend.control().unwrap();
+        control
+            .connector_reset(0, ConnectorResetType::Data)
+            .unwrap();
+        control.ppm_reset().unwrap();
+
+        // Data Reset was added in UCSI 2.0.
+        backend.set_ucsi_version(BcdWrapper(0x0120));
+        assert!(matches!(
+            backend
+                .control()
+                .unwrap()
+                .connector_reset(0, ConnectorResetType::Data),
+            Err(Error::NotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_simulated_power_level() {
+        let mut ppm = simulator();
+        ppm.capability.ucsi_version = BcdWrapper(0x0300);
+        ppm.capability
+            .bm_optional_features
+            .set_power_level_supported = true;
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(ppm));
+
+        let power_level = PowerLevel {
+            max_power: Milliwatt(15000),
+            usb_type_c_current: UsbTypeCCurrent::Current1_5A,
+        };
+        let control = backend.control().unwrap();
+        control
+            .set_power_level(0, PowerDirection::Consumer, power_level.clone())
+            .unwrap();
+        control.set_sink_path(0, true).unwrap();
+
+        assert_eq!(
+            backend.power_level(0, PowerDirection::Consumer).unwrap(),
+            power_level
+        );
+        assert_eq!(
+            backend.connector_status(0).unwrap().sink_path_status,
+            SinkPathStatus::Ready
+        );
+    }
+
+    #[test]
+    fn test_simulated_fw_update_request() {
+        let mut ppm = simulator();
+        ppm.capability.ucsi_version = BcdWrapper(0x0300);
+        ppm.capability
+            .bm_optional_features
+            .fw_update_request_supported = true;
+        ppm.connectors[0].capability.miscellaneous_capabilities =
+            ConnectorCapabilityMiscellaneousCapabilities::FW_UPDATE;
+        ppm.connectors[0].fw_update_response = Some(vec![0xaa, 0x55]);
+        let request = (0..40).collect::<Vec<u8>>();
+
+        // The request needs three chunks of MESSAGE_OUT.
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(ppm.clone()));
+        assert!(matches!(
+            backend.control().unwrap().fw_update_request(0, &request),
+            Err(Error::NotSupported { .. })
+        ));
+        assert!(matches!(
+            backend
+                .control()
+                .unwrap()
+                .security_request(0, &request[..4]),
+            Err(Error::NotSupported { .. })
+        ));
+
+        ppm.capability.bm_optional_features.chunking_supported = true;
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(ppm.clone()));
+        let response = backend
+            .control()
+            .unwrap()
+            .fw_update_request(0, &request)
+            .unwrap();
+        assert_eq!(response[..2], [0xaa, 0x55]);
+        assert!(matches!(
+            backend.control().unwrap().fw_update_request(0, &[]),
+            Err(Error::ParseError { value: 0, .. })
+        ));
+
+        // The simulator reassembles the chunks.
+        for chunk in request.chunks(MESSAGE_IN_LEN) {
+            let control = UcsiDebugfsBackend::build_command_value(&Command::LpmFwUpdateRequest {
+                connector_nr: 0,
+                data_length: chunk.len() as u8,
+                chunk_number: (chunk[0] as usize / MESSAGE_IN_LEN) as u8,
+                last_chunk: chunk.len() < MESSAGE_IN_LEN,
+            })
+            .unwrap();
+            ppm.execute_with_message_out(control, chunk).unwrap();
+        }
+        assert_eq!(ppm.connectors[0].fw_update_request, request);
+    }
+
+    #[test]
+    fn test_simulated_raw_command() {
+        let mut backend = UcsiDebugfsBackend::with_transport(Box::new(simulator()));
+        let control = backend.control().unwrap();
+
+        // GET_CONNECTOR_STATUS on connector 1.
+        let response = control.raw_ucsi_command(&[0x12, 0, 1]).unwrap();
+        let cci = response.cci.unwrap();
+        assert!(cci.command_completed);
+        assert_eq!(usize::from(cci.data_length), MESSAGE_IN_LEN);
+
+        // The simulator has no second connector.
+        let response = control.raw_ucsi_command(&[0x12, 0, 2]).unwrap();
+        assert!(response.cci.unwrap().error);
+        assert!(response.message_in.is_empty());
+
+        let response = control.raw_ucsi_command(&[0xff]).unwrap();
+        assert!(response.cci.unwrap().not_supported);
+
+        assert!(control.raw_ucsi_command(&[0; 9]).is_err());
+    }
+}
@@ -8,11 +8,14 @@ use argh::FromArgs;
 
 use libtypec_rs::pd::MessageRecipient;
 use libtypec_rs::pd::MessageResponseType;
+use libtypec_rs::retimer;
+use libtypec_rs::tcpm;
 use libtypec_rs::typec::OsBackends;
 use libtypec_rs::typec::TypecRs;
 use libtypec_rs::ucsi::GetAlternateModesRecipient;
 use libtypec_rs::ucsi::PdoSourceCapabilitiesType;
 use libtypec_rs::ucsi::PdoType;
+use libtypec_rs::vdo::Vdo;
 use libtypec_rs::Error;
 
 #[derive(FromArgs)]
@@ -24,11 +27,87 @@ struct Args {
     /// the backend to use
     #[argh(option)]
     backend: Option<OsBackends>,
+    /// print the decoded TCPM debugfs logs instead
+    #[argh(switch)]
+    tcpm_log: bool,
+    /// print the USB4 retimers and their firmware versions instead
+    #[argh(switch)]
+    retimers: bool,
+}
+
+/// Prints the retimers enumerated by the thunderbolt driver.
+fn print_retimers() {
+    let retimers = retimer::list_retimers().expect("Failed to list the retimers");
+    for retimer in retimers {
+        let connector = match retimer.connector_nr {
+            Some(connector_nr) => format!("connector {connector_nr}"),
+            None => "unknown connector".into(),
+        };
+        println!(
+            "{}: {connector}, vendor {:#06x}, device {:#06x}, NVM {}",
+            retimer.name,
+            retimer.vendor,
+            retimer.device,
+            retimer.nvm_version.as_deref().unwrap_or("unknown")
+        );
+    }
+}
+
+/// Prints the TCPM log of every port managed by TCPM.
+fn print_tcpm_logs() {
+    let logs = tcpm::find_logs().expect("Failed to find the TCPM logs");
+    for path in logs {
+        println!("{}", path.display());
+        match tcpm::read_log(&path) {
+            Ok(entries) => {
+                for entry in entries {
+                    println!("  {entry}");
+                }
+            }
+            Err(e) => println!("  Failed to read the TCPM log: {:?}", e),
+        }
+        println!();
+    }
+}
+
+/// Prints the alternate modes of `recipient`, decoding the VDOs of known SVIDs.
+fn print_alternate_modes(
+    typec: &mut TypecRs,
+    recipient: GetAlternateModesRecipient,
+    connector_nr: usize,
+) {
+    let alternate_modes = typec
+        .alternate_modes(recipient, connector_nr)
+        .expect("Failed to get alternate modes");
+
+    for alternate_mode in alternate_modes {
+        let name = alternate_mode
+            .name()
+            .unwrap_or_else(|| format!("SVID {:#06x}", alternate_mode.svid[0]));
+
+        match alternate_mode.decode(recipient) {
+            Ok(Some(Vdo::DisplayPortCapabilities(capabilities))) => {
+                println!("  {name}: {capabilities}")
+            }
+            Ok(Some(vdo)) => println!("  {name}: {:?}", vdo),
+            Ok(None) | Err(_) => println!("  {name}: {:#010x}", alternate_mode.vdo[0]),
+        }
+    }
 }
 
 fn main() {
     let args: Args = argh::from_env();
 
+    if args.tcpm_log {
+        print_tcpm_logs();
+        return;
+    }
+
+    if args.retimers {
+        print_retimers();
+        return;
+    }
+
     let backends = if let Some(backend) = args.backend {
         // Use the backend selected by the user
         vec![backend]
@@ -56,6 +135,12 @@ fn main() {
         println!("{:#?}", conn_capability);
         println!();
 
+        if let Ok(lpm_ppm_info) = typec.lpm_ppm_info(connector_nr) {
+            println!("Connector {connector_nr} LPM Info");
+            println!("{:#?}", lpm_ppm_info);
+            println!();
+        }
+
         match typec.pdos(
             connector_nr,
             false,
@@ -109,20 +194,20 @@ fn main() {
         }
         println!();
 
-        let alternate_modes = typec
-            .alternate_modes(GetAlternateModesRecipient::Connector, connector_nr)
-            .expect("Failed to get alternate modes");
-
         println!("Connector {connector_nr} Alternate Modes");
-        println!("{:#?}", alternate_modes);
+        print_alternate_modes(
+            &mut typec,
+            GetAlternateModesRecipient::Connector,
+            connector_nr,
+        );
         println!();
 
-        let alternate_modes = typec
-            .alternate_modes(GetAlternateModesRecipient::SopPrime, connector_nr)
-            .expect("Failed to get alternate modes");
-
         println!("Connector {connector_nr} SOP' Alternate Modes");
-        println!("{:#?}", alternate_modes);
+        print_alternate_modes(
+            &mut typec,
+            GetAlternateModesRecipient::SopPrime,
+            connector_nr,
+        );
         println!();
 
         match typec.pd_message(
@@ -142,12 +227,8 @@ fn main() {
         };
         println!();
 
-        let alternate_modes = typec
-            .alternate_modes(GetAlternateModesRecipient::Sop, connector_nr)
-            .expect("Failed to get alternate modes");
-
-        println!("Connector {connector_nr} SOP' Alternate Modes");
-        println!("{:#?}", alternate_modes);
+        println!("Connector {connector_nr} SOP Alternate Modes");
+        print_alternate_modes(&mut typec, GetAlternateModesRecipient::Sop, connector_nr);
         println!();
 
         match typec.pd_message(
@@ -16,18 +16,27 @@ fn read_power_contract() {
     println!("Number of USB-C port(s): {}", capabilities.num_connectors);
 
     for connector_nr in 0..capabilities.num_connectors {
-        let conn_status = typec
-            .connector_status(connector_nr)
-            .expect("Failed to get connector capabilities");
-
-        if conn_status.negotiated_power_level > 0 {
-            let operating_power =
-                (((conn_status.negotiated_power_level >> 10) & 0x3ff) * 250) / 1000;
-            let max_power = ((conn_status.negotiated_power_level & 0x3ff) * 250) / 1000;
+        let contract = typec
+            .power_contract(connector_nr)
+            .expect("Failed to get the power contract");
 
+        if contract.power.0 > 0 {
+            println!(
+                "\tUSB-C power contract {:?} ({:?}), {:?} at {:?}, {:?}",
+                contract.mode,
+                contract.direction,
+                contract.current,
+                contract.voltage,
+                contract.power
+            );
             println!(
-                "\tUSB-C power contract Operating Power {} W, with Max Power {} W\n",
-                operating_power, max_power
+                "\tPartner maximum power {:?}{}\n",
+                contract.partner_max_power,
+                if contract.cable_limited {
+                    ", limited by the cable"
+                } else {
+                    ""
+                }
             );
 
             let mut reader = SysfsReader::new().unwrap();
@@ -0,0 +1,327 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! Send UCSI commands to the PPM and print the responses.
+//!
+//! Commands are given by name, optionally followed by the command specific
+//! parameters as a number, e.g. `GET_CONNECTOR_STATUS 1`, or as the raw
+//! CONTROL data structure in hex, either as a single number (`0x10012`) or
+//! as bytes in little-endian order (`12 00 01`).
+
+use std::io::BufRead;
+use std::io::Cursor;
+use std::io::Write;
+
+use argh::FromArgs;
+
+use libtypec_rs::pd::Pdo;
+use libtypec_rs::typec::OsBackends;
+use libtypec_rs::typec::TypecRs;
+use libtypec_rs::ucsi::trace::command_name;
+use libtypec_rs::ucsi::trace::command_number;
+use libtypec_rs::ucsi::trace::decode_control;
+use libtypec_rs::ucsi::AlternateMode;
+use libtypec_rs::ucsi::AttentionVdo;
+use libtypec_rs::ucsi::CableProperty;
+use libtypec_rs::ucsi::CamSupported;
+use libtypec_rs::ucsi::Capability;
+use libtypec_rs::ucsi::Command;
+use libtypec_rs::ucsi::ConnectorCapability;
+use libtypec_rs::ucsi::ConnectorStatus;
+use libtypec_rs::ucsi::CurrentAlternatingModes;
+use libtypec_rs::ucsi::ErrorStatus;
+use libtypec_rs::ucsi::LpmPpmInfo;
+use libtypec_rs::ucsi::PowerLevel;
+use libtypec_rs::ucsi::RawResponse;
+use libtypec_rs::ucsi::UcsiVersion;
+use libtypec_rs::BcdWrapper;
+use libtypec_rs::BitReader;
+use libtypec_rs::FromBytes;
+
+/// The highest command number defined by UCSI 3.0.
+const MAX_COMMAND_NUMBER: u32 = 0x22;
+
+#[derive(FromArgs)]
+/// Send UCSI commands to the PPM. Reads commands from stdin if none is given.
+struct Args {
+    /// the backend to use
+    #[argh(option, default = "OsBackends::UcsiDebugfs")]
+    backend: OsBackends,
+    /// the command to send, by name or as hex
+    #[argh(positional)]
+    command: Vec<String>,
+}
+
+/// The revisions the responses of the PPM are decoded against.
+#[derive(Clone, Copy, Default)]
+struct Revisions {
+    /// The UCSI revision, or `None` if it is unknown, in which case every
+    /// field is decoded.
+    ucsi: Option<UcsiVersion>,
+    /// The USB PD revision, which selects the layout of the PDOs.
+    pd: BcdWrapper,
+}
+
+impl Revisions {
+    fn new(capability: &Capability) -> Self {
+        let ucsi = Some(capability.ucsi_version)
+            .filter(|version| *version != BcdWrapper::default())
+            .map(UcsiVersion::from);
+        Self {
+            ucsi,
+            pd: capability.pd_version,
+        }
+    }
+}
+
+/// Parses a decimal or `0x`-prefixed hex number.
+fn parse_number(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// Builds the CONTROL data structure from a command line.
+fn parse_control(words: &[&str]) -> Result<u64, String> {
+    let Some(first) = words.first() else {
+        return Err("empty command".into());
+    };
+
+    if let Some(cmd_number) = command_number(&first.to_uppercase()) {
+        let parameters = match words.get(1..) {
+            Some([]) | None => 0,
+            Some([parameters]) => parse_number(parameters)
+                .ok_or_else(|| format!("invalid parameters: {parameters}"))?,
+            Some(_) => return Err("expected a single parameters value".into()),
+        };
+        if parameters >> 48 != 0 {
+            return Err(format!("parameters do not fit in CONTROL: {parameters:#x}"));
+        }
+
+        return Ok(u64::from(cmd_number) | (parameters << 16));
+    }
+
+    if let [control] = words {
+        if control.starts_with("0x") {
+            return parse_number(control).ok_or_else(|| format!("invalid CONTROL: {control}"));
+        }
+    }
+
+    if words.len() > std::mem::size_of::<u64>() {
+        return Err("CONTROL is at most 8 bytes".into());
+    }
+    let mut bytes = [0; 8];
+    for (byte, word) in bytes.iter_mut().zip(words) {
+        *byte = u8::from_str_radix(word, 16).map_err(|_| format!("unknown command: {word}"))?;
+    }
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Decodes MESSAGE_IN with the parser matching `command`. Returns `None` if
+/// there is no such parser or the data does not parse.
+fn decode_response(command: &Command, revisions: Revisions, message_in: &[u8]) -> Option<String> {
+    let mut reader = BitReader::new(Cursor::new(message_in));
+    let reader = &mut reader;
+    let decoded = match command {
+        Command::GetCapability => format!("{:#?}", Capability::from_bytes(reader).ok()?),
+        Command::GetConnectorCapability { .. } => match revisions.ucsi {
+            Some(version) => format!(
+                "{:#?}",
+                ConnectorCapability::from_bytes_for_version(reader, version).ok()?
+            ),
+            None => format!("{:#?}", ConnectorCapability::from_bytes(reader).ok()?),
+        },
+        Command::GetAlternateModes { .. } => {
+            format!("{:#?}", AlternateMode::from_bytes(reader).ok()?)
+        }
+        Command::GetCamSupported { .. } => {
+            format!("{:#?}", CamSupported::from_bytes(reader).ok()?)
+        }
+        Command::GetCurrentCam { .. } => {
+            format!("{:#?}", CurrentAlternatingModes::from_bytes(reader).ok()?)
+        }
+        Command::GetCableProperty { .. } => {
+            format!("{:#?}", CableProperty::from_bytes(reader).ok()?)
+        }
+        Command::GetPdos { nr_pdos, .. } => {
+            let pdos = (0..=*nr_pdos)
+                .map(|_| Pdo::from_bytes(reader, revisions.pd))
+                .collect::<Result<Vec<_>, _>>()
+                .ok()?;
+            format!("{pdos:#?}")
+        }
+        Command::GetConnectorStatus { .. } => match revisions.ucsi {
+            Some(version) => format!(
+                "{:#?}",
+                ConnectorStatus::from_bytes_for_version(reader, version).ok()?
+            ),
+            None => format!("{:#?}", ConnectorStatus::from_bytes(reader).ok()?),
+        },
+        Command::GetErrorStatus { .. } => format!("{:#?}", ErrorStatus::from_bytes(reader).ok()?),
+        Command::GetAttentionVdo { .. } => {
+            format!("{:#?}", AttentionVdo::from_bytes(reader).ok()?)
+        }
+        Command::ReadPowerLevel { .. } => format!("{:#?}", PowerLevel::from_bytes(reader).ok()?),
+        Command::GetLpmPpmInfo { .. } => format!("{:#?}", LpmPpmInfo::from_bytes(reader).ok()?),
+        _ => return None,
+    };
+
+    Some(decoded)
+}
+
+/// Prints `data` 16 bytes per line, prefixed by their offset.
+fn print_hexdump(data: &[u8]) {
+    for (i, line) in data.chunks(16).enumerate() {
+        let bytes = line
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("{:04x}: {bytes}", i * 16);
+    }
+}
+
+fn print_response(control: u64, revisions: Revisions, response: &RawResponse) {
+    match &response.cci {
+        Some(cci) => {
+            let mut status = vec![];
+            for (set, name) in [
+                (cci.command_completed, "command completed"),
+                (cci.busy, "busy"),
+                (cci.not_supported, "not supported"),
+                (cci.error, "error"),
+            ] {
+                if set {
+                    status.push(name);
+                }
+            }
+            println!(
+                "CCI: {}, data length {}",
+                status.join(", "),
+                cci.data_length
+            );
+        }
+        None => println!("CCI: not reported by the backend"),
+    }
+
+    // Only the first data length bytes of MESSAGE_IN are valid.
+    let message_in = match &response.cci {
+        Some(cci) => cci
+            .message_in(&response.message_in)
+            .unwrap_or(&response.message_in),
+        None => &response.message_in,
+    };
+    if message_in.is_empty() {
+        return;
+    }
+
+    let decoded = decode_control(control)
+        .and_then(|command| decode_response(&command, revisions, message_in));
+    match decoded {
+        Some(decoded) => println!("{decoded}"),
+        None => print_hexdump(message_in),
+    }
+}
+
+fn print_help() {
+    println!("Commands, optionally followed by their parameters:");
+    for name in (1..=MAX_COMMAND_NUMBER).filter_map(command_name) {
+        if name.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+            println!("  {name}");
+        }
+    }
+    println!("Or the CONTROL data structure in hex, e.g. 0x10012 or 12 00 01.");
+    println!("Type 'quit' to exit.");
+}
+
+/// Runs a command line. Returns `false` when the user asks to quit.
+fn run_line(typec: &mut TypecRs, revisions: Revisions, line: &str) -> bool {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    match words.as_slice() {
+        [] => return true,
+        ["help"] => {
+            print_help();
+            return true;
+        }
+        ["quit" | "exit"] => return false,
+        _ => {}
+    }
+
+    let control = match parse_control(&words) {
+        Ok(control) => control,
+        Err(err) => {
+            eprintln!("{err}");
+            return true;
+        }
+    };
+
+    match typec.raw_ucsi_command(&control.to_le_bytes()) {
+        Ok(response) => print_response(control, revisions, &response),
+        Err(err) => eprintln!("Failed to send {control:#x}: {err}"),
+    }
+
+    true
+}
+
+fn main() {
+    let args: Args = argh::from_env();
+
+    let mut typec = TypecRs::new(args.backend).expect("Failed to get a library instance");
+    // The layouts of some responses depend on the revision of the PPM.
+    let revisions = typec
+        .capabilities()
+        .map(|capabilities| Revisions::new(&capabilities))
+        .unwrap_or_default();
+
+    if !args.command.is_empty() {
+        run_line(&mut typec, revisions, &args.command.join(" "));
+        return;
+    }
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("ucsi> ");
+        let _ = std::io::stdout().flush();
+
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        if !run_line(&mut typec, revisions, &line) {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_control_by_name() {
+        assert_eq!(parse_control(&["GET_CAPABILITY"]), Ok(0x06));
+        assert_eq!(parse_control(&["get_connector_status", "1"]), Ok(0x10012));
+        assert_eq!(
+            parse_control(&["GET_ALTERNATE_MODES", "0x20101"]),
+            Ok(0x0c | (0x20101 << 16))
+        );
+        assert!(parse_control(&["GET_CONNECTOR_STATUS", "1", "2"]).is_err());
+        assert!(parse_control(&["GET_CONNECTOR_STATUS", "x"]).is_err());
+        assert!(parse_control(&["GET_CONNECTOR_STATUS", "0x1000000000000"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_control_raw() {
+        assert_eq!(parse_control(&["0x10012"]), Ok(0x10012));
+        assert_eq!(parse_control(&["12", "00", "01"]), Ok(0x10012));
+        assert_eq!(
+            parse_control(&["01", "02", "03", "04", "05", "06", "07", "08"]),
+            Ok(0x0807060504030201)
+        );
+        assert!(parse_control(&[]).is_err());
+        assert!(parse_control(&["01", "02", "03", "04", "05", "06", "07", "08", "09"]).is_err());
+        assert!(parse_control(&["zz"]).is_err());
+    }
+}
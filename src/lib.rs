@@ -20,24 +20,39 @@
 use std::io::Cursor;
 
 use bitstream_io::LittleEndian;
+use pd::pd3p2::DiscoverModesResponse;
+use pd::pd3p2::DiscoverSvidsResponse;
 use pd::Message;
 use pd::MessageRecipient;
 use pd::MessageResponseType;
 use pd::Pdo;
+use ucsi::Acknowledgement;
 use ucsi::AlternateMode;
+use ucsi::AttentionVdo;
 use ucsi::CableProperty;
 use ucsi::Capability;
 use ucsi::ConnectorCapability;
+use ucsi::ConnectorResetType;
 use ucsi::ConnectorStatus;
+use ucsi::ErrorInformation;
+use ucsi::ErrorStatus;
 use ucsi::GetAlternateModesRecipient;
+use ucsi::LpmPpmInfo;
+use ucsi::NotificationEnable;
 use ucsi::PdoSourceCapabilitiesType;
 use ucsi::PdoType;
+use ucsi::PowerDirection;
+use ucsi::PowerLevel;
+use ucsi::RawResponse;
 
 pub mod backends;
 pub mod pd;
+pub mod retimer;
+pub mod tcpm;
 pub mod typec;
 pub mod ucsi;
 pub mod vdo;
+pub mod vendor;
 
 pub type BitWriter<'a> = bitstream_io::BitWriter<Cursor<&'a mut [u8]>, LittleEndian>;
 pub type BitReader<'a> = bitstream_io::BitReader<Cursor<&'a [u8]>, LittleEndian>;
@@ -59,6 +74,7 @@ macro_rules! bitflags_wrapper {
         $(#[$outer:meta])*
         $vis:vis struct $name:ident: $t:ty {
             $(
+                $(#[$inner:ident $($args:tt)*])*
                 const $flag:ident = $value:expr;
             )*
         }) => {
@@ -67,6 +83,7 @@ macro_rules! bitflags_wrapper {
             /// cbindgen:ignore
             $vis struct $name: $t {
                 $(
+                    $(#[$inner $($args)*])*
                     const $flag = $value;
                 )*
             }
@@ -117,6 +134,50 @@ pub trait OsBackend {
         connector_nr: usize,
     ) -> Result<Vec<AlternateMode>>;
 
+    /// Builds the Discover SVIDs responses of `recipient` from its
+    /// alternate modes. The OS only keeps the SVIDs, so the responses are
+    /// rebuilt as the responder would have sent them.
+    fn discover_svids(
+        &mut self,
+        recipient: GetAlternateModesRecipient,
+        connector_nr: usize,
+    ) -> Result<Vec<DiscoverSvidsResponse>> {
+        let mut svids = vec![];
+        for alt_mode in self.alternate_modes(recipient, connector_nr)? {
+            if !svids.contains(&alt_mode.svid[0]) {
+                svids.push(alt_mode.svid[0]);
+            }
+        }
+
+        Ok(DiscoverSvidsResponse::from_svids(&svids))
+    }
+
+    /// Builds the Discover Modes response of `recipient` for `svid` from the
+    /// alternate modes of that SVID, in mode order.
+    fn discover_modes(
+        &mut self,
+        recipient: GetAlternateModesRecipient,
+        connector_nr: usize,
+        svid: u32,
+    ) -> Result<DiscoverModesResponse> {
+        let modes = self
+            .alternate_modes(recipient, connector_nr)?
+            .into_iter()
+            .filter(|alt_mode| alt_mode.svid[0] == svid)
+            .map(|alt_mode| alt_mode.vdo[0])
+            .collect::<Vec<_>>();
+
+        Ok(DiscoverModesResponse::from_vdos(svid, &modes))
+    }
+
+    /// Returns the offsets, into the list of Alternate Modes of the
+    /// connector, of the modes that are currently supported.
+    fn supported_alternate_modes(&mut self, connector_nr: usize) -> Result<Vec<usize>>;
+
+    /// Returns the offsets, into the list of Alternate Modes of the
+    /// connector, of the modes the connector is operating in.
+    fn current_alternate_modes(&mut self, connector_nr: usize) -> Result<Vec<usize>>;
+
     fn cable_properties(&mut self, connector_nr: usize) -> Result<CableProperty>;
 
     fn connector_status(&mut self, connector_nr: usize) -> Result<ConnectorStatus>;
@@ -139,6 +200,107 @@ pub trait OsBackend {
         source_capabilities_type: PdoSourceCapabilitiesType,
         revision: BcdWrapper,
     ) -> Result<Vec<Pdo>>;
+
+    /// Returns the reasons the previous command failed. `connector_nr` is
+    /// the connector that command targeted, if any.
+    fn error_status(&mut self, connector_nr: Option<usize>) -> Result<ErrorStatus>;
+
+    /// Returns the most recent Attention VDM received on the connector.
+    fn attention_vdo(&mut self, connector_nr: usize) -> Result<AttentionVdo>;
+
+    /// Returns the vendor, product and firmware version of the LPM.
+    fn lpm_ppm_info(&mut self, connector_nr: usize) -> Result<LpmPpmInfo>;
+
+    /// Returns the source or sink power level of the connector.
+    ///
+    /// READ_POWER_LEVEL is not forwarded by Linux debugfs, so the UCSI
+    /// backend only supports this with the PPM simulator.
+    fn power_level(
+        &mut self,
+        connector_nr: usize,
+        power_direction: PowerDirection,
+    ) -> Result<PowerLevel>;
+
+    /// Returns the commands that change the state of the PPM, if the backend
+    /// can send them.
+    fn control(&mut self) -> Result<&mut dyn OsControl>;
+}
+
+/// Commands that change the state of the PPM or of its connectors. These
+/// are kept apart from [`OsBackend`] so that they are not issued by mistake.
+///
+/// The Linux UCSI debugfs interface only forwards an allowlist of commands
+/// to the PPM, since the others would desynchronize the kernel driver from
+/// it. Of the commands below, only CONNECTOR_RESET and SET_SINK_PATH are on
+/// that list: the others fail with [`Error::NotSupported`] on Linux and only
+/// work with the PPM simulator.
+pub trait OsControl {
+    /// Resets the PPM. Notifications are disabled afterwards.
+    ///
+    /// Not forwarded by Linux debugfs.
+    fn ppm_reset(&mut self) -> Result<()>;
+
+    /// Resets `connector_nr`.
+    fn connector_reset(
+        &mut self,
+        connector_nr: usize,
+        reset_type: ConnectorResetType,
+    ) -> Result<()>;
+
+    /// Acknowledges the indications in the CCI.
+    ///
+    /// Not forwarded by Linux debugfs, as the kernel acknowledges the
+    /// indications itself.
+    fn ack_cc_ci(&mut self, acknowledgement: Acknowledgement) -> Result<()>;
+
+    /// Enables `notification_enable` and disables the other notifications.
+    ///
+    /// Not forwarded by Linux debugfs.
+    fn set_notification_enable(&mut self, notification_enable: NotificationEnable) -> Result<()>;
+
+    /// Limits the power `connector_nr` sources or sinks.
+    ///
+    /// Not forwarded by Linux debugfs.
+    fn set_power_level(
+        &mut self,
+        connector_nr: usize,
+        power_direction: PowerDirection,
+        power_level: PowerLevel,
+    ) -> Result<()>;
+
+    /// Enables or disables the Sink Path of `connector_nr`.
+    fn set_sink_path(&mut self, connector_nr: usize, enable: bool) -> Result<()>;
+
+    /// Sets the mode of the retimers of `connector_nr`. `retimer_mode` is
+    /// the Retimer Mode field of SET_RETIMER_MODE.
+    ///
+    /// Not forwarded by Linux debugfs.
+    fn set_retimer_mode(&mut self, connector_nr: usize, retimer_mode: u32) -> Result<()>;
+
+    /// Passes the vendor-defined firmware update `request` to the LPM of
+    /// `connector_nr`. Returns the response of the LPM.
+    ///
+    /// The request is written to MESSAGE_OUT, which the Linux UCSI debugfs
+    /// interface cannot do, so the UCSI backend always fails with
+    /// [`Error::NotSupported`] on it. Only the PPM simulator supports this
+    /// today.
+    fn fw_update_request(&mut self, connector_nr: usize, request: &[u8]) -> Result<Vec<u8>>;
+
+    /// Passes the security `request` to `connector_nr`. Returns the
+    /// response.
+    ///
+    /// Like [`OsControl::fw_update_request`], this needs MESSAGE_OUT and is
+    /// only supported by the PPM simulator today.
+    fn security_request(&mut self, connector_nr: usize, request: &[u8]) -> Result<Vec<u8>>;
+
+    /// Writes `control`, the CONTROL data structure in little-endian order,
+    /// to the PPM without any validation. At most 8 bytes can be written.
+    ///
+    /// Failures reported by the PPM are returned in the CCI rather than as
+    /// an [`Error`], and GET_ERROR_STATUS is not issued on behalf of the
+    /// caller. Backends that cannot read the CCI, such as Linux debugfs,
+    /// return no CCI and report failures as errors instead.
+    fn raw_ucsi_command(&mut self, control: &[u8]) -> Result<RawResponse>;
 }
 
 /// A trait for serializing an object to a byte stream.
@@ -192,6 +354,22 @@ pub enum Error {
         #[cfg(feature = "backtrace")]
         backtrace: std::backtrace::Backtrace,
     },
+    #[error("The PPM is busy")]
+    PpmBusy {
+        #[cfg(feature = "backtrace")]
+        backtrace: std::backtrace::Backtrace,
+    },
+    #[error("The PPM does not support this command")]
+    CommandNotSupported {
+        #[cfg(feature = "backtrace")]
+        backtrace: std::backtrace::Backtrace,
+    },
+    #[error("The command failed: {error_information:?}")]
+    CommandFailed {
+        error_information: ErrorInformation,
+        #[cfg(feature = "backtrace")]
+        backtrace: std::backtrace::Backtrace,
+    },
     #[error("Could not parse field {field} with value {value}")]
     ParseError {
         field: String,
@@ -320,6 +498,36 @@ impl std::fmt::Debug for Error {
                 #[cfg(feature = "backtrace")]
                 write!(f, "\n\nerror stack backtrace:\n{}", backtrace)
             }
+            Self::PpmBusy {
+                #[cfg(feature = "backtrace")]
+                backtrace,
+            } => {
+                f.debug_struct("PpmBusy").finish()?;
+
+                #[cfg(feature = "backtrace")]
+                write!(f, "\n\nerror stack backtrace:\n{}", backtrace)
+            }
+            Self::CommandNotSupported {
+                #[cfg(feature = "backtrace")]
+                backtrace,
+            } => {
+                f.debug_struct("CommandNotSupported").finish()?;
+
+                #[cfg(feature = "backtrace")]
+                write!(f, "\n\nerror stack backtrace:\n{}", backtrace)
+            }
+            Self::CommandFailed {
+                error_information,
+                #[cfg(feature = "backtrace")]
+                backtrace,
+            } => {
+                f.debug_struct("CommandFailed")
+                    .field("error_information", error_information)
+                    .finish()?;
+
+                #[cfg(feature = "backtrace")]
+                write!(f, "\n\nerror stack backtrace:\n{}", backtrace)
+            }
             Self::ParseError {
                 field,
                 value,
@@ -378,6 +586,9 @@ impl From<Error> for CError {
                 CError(source.raw_os_error().unwrap_or(nix::libc::EIO))
             }
             Error::NotSupported { .. } => CError(nix::libc::EOPNOTSUPP),
+            Error::PpmBusy { .. } => CError(nix::libc::EBUSY),
+            Error::CommandNotSupported { .. } => CError(nix::libc::EOPNOTSUPP),
+            Error::CommandFailed { .. } => CError(nix::libc::EIO),
             Error::ParseError { .. }
             | Error::Utf8Error { .. }
             | Error::NulError { .. }
@@ -6,42 +6,76 @@
 //!
 //! See "Universal Serial Bus Power Delivery Specification"
 
+use std::io::Cursor;
+
 use bitstream_io::BitRead;
+use bitstream_io::BitWrite;
 use enumn::N;
 use proc_macros::CApiWrapper;
 
 use crate::BcdWrapper;
 use crate::BitReader;
+use crate::BitWriter;
 use crate::Error;
 use crate::FromBytes;
+use crate::Milliamp;
+use crate::Millivolt;
+use crate::Milliwatt;
 use crate::Result;
+use crate::ToBytes;
 
+use crate::pd::pd3p2::AlertDataObject as Pd3p2AlertDataObject;
 use crate::pd::pd3p2::BatteryCapData as Pd3p2BatteryCapData;
 use crate::pd::pd3p2::BatteryStatusData as Pd3p2BatteryStatusData;
 use crate::pd::pd3p2::BatterySupplyPdo as Pd3p2BatterySupplyPdo;
+use crate::pd::pd3p2::BistDataObject as Pd3p2BistDataObject;
+use crate::pd::pd3p2::Capabilities as Pd3p2Capabilities;
+use crate::pd::pd3p2::ControlMessageType as Pd3p2ControlMessageType;
 use crate::pd::pd3p2::DiscoverIdentityResponse as Pd3p2DiscoverIdentityResponse;
+use crate::pd::pd3p2::DiscoverModesResponse as Pd3p2DiscoverModesResponse;
+use crate::pd::pd3p2::DiscoverSvidsResponse as Pd3p2DiscoverSvidsResponse;
+use crate::pd::pd3p2::EnterUsbDataObject as Pd3p2EnterUsbDataObject;
+use crate::pd::pd3p2::EprRequest as Pd3p2EprRequest;
 use crate::pd::pd3p2::FixedSupplyPdo as Pd3p2FixedSupplyPdo;
+use crate::pd::pd3p2::RequestDataObject as Pd3p2RequestDataObject;
 use crate::pd::pd3p2::RevisionMessageData as Pd3p2RevisionMessageData;
 use crate::pd::pd3p2::SinkCapabilitiesExtended as Pd3p2SinkCapabilitiesExtended;
 use crate::pd::pd3p2::SourceCapabilitiesExtended as Pd3p2SourceCapabilitiesExtended;
 use crate::pd::pd3p2::SprProgrammableSupplyPdo as Pd3p2SprProgrammableSupplyPdo;
 use crate::pd::pd3p2::VariableSupplyPdo as Pd3p2VariableSupplyPdo;
+use crate::pd::pd3p2::VendorDefinedMessage as Pd3p2VendorDefinedMessage;
 
 #[cfg(feature = "c_api")]
 mod c_api {
+    pub(crate) use crate::pd::pd3p2::Pd3p2AlertDataObject;
     pub(crate) use crate::pd::pd3p2::Pd3p2BatteryCapData;
     pub(crate) use crate::pd::pd3p2::Pd3p2BatteryStatusData;
     pub(crate) use crate::pd::pd3p2::Pd3p2BatterySupplyPdo;
+    pub(crate) use crate::pd::pd3p2::Pd3p2BistDataObject;
+    pub(crate) use crate::pd::pd3p2::Pd3p2Capabilities;
+    pub(crate) use crate::pd::pd3p2::Pd3p2ControlMessageType;
     pub(crate) use crate::pd::pd3p2::Pd3p2DiscoverIdentityResponse;
+    pub(crate) use crate::pd::pd3p2::Pd3p2DiscoverModesResponse;
+    pub(crate) use crate::pd::pd3p2::Pd3p2DiscoverSvidsResponse;
+    pub(crate) use crate::pd::pd3p2::Pd3p2EnterUsbDataObject;
+    pub(crate) use crate::pd::pd3p2::Pd3p2EprRequest;
     pub(crate) use crate::pd::pd3p2::Pd3p2FixedSupplyPdo;
+    pub(crate) use crate::pd::pd3p2::Pd3p2RequestDataObject;
     pub(crate) use crate::pd::pd3p2::Pd3p2RevisionMessageData;
     pub(crate) use crate::pd::pd3p2::Pd3p2SinkCapabilitiesExtended;
     pub(crate) use crate::pd::pd3p2::Pd3p2SourceCapabilitiesExtended;
     pub(crate) use crate::pd::pd3p2::Pd3p2SprProgrammableSupplyPdo;
     pub(crate) use crate::pd::pd3p2::Pd3p2VariableSupplyPdo;
+    pub(crate) use crate::pd::pd3p2::Pd3p2VendorDefinedMessage;
 }
 
 pub mod pd3p2;
+mod raw;
+
+pub use raw::ExtendedHeader;
+pub use raw::MessageHeader;
+pub use raw::MessageType;
+pub use raw::RawMessage;
 
 #[derive(Debug, Clone, PartialEq, Default, N, CApiWrapper)]
 #[c_api(prefix = "Pd", repr_c)]
@@ -66,20 +100,27 @@ pub enum Command {
     /// Command is also used to determine whether a Cable Plug or VPD is
     /// PD-Capable by looking for a GoodCRC Message Response.
     #[default]
-    DiscoverIdentity,
+    DiscoverIdentity = 1,
     DiscoverSVIDs,
     DiscoverModes,
     EnterMode,
     ExitMode,
     Attention,
-    SVIDSpecific,
+    /// Commands 16 to 31, whose meaning is defined by the SVID.
+    SVIDSpecific = 16,
 }
 
+/// The Standard ID (SID) used in structured VDMs defined by the USB PD
+/// specification itself, e.g. Discover Identity and Discover SVIDs.
+pub const PD_SID: u32 = 0xff00;
+
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
 #[c_api(prefix = "Pd", repr_c)]
 /// The VDM header. See table 6.30 in the USB PD Specification for more
 /// information.
 pub struct VdmHeader {
+    /// The Standard or Vendor ID (SVID) the VDM belongs to.
+    pub svid: u32,
     // Whether this is a structured VDM.
     pub structured: bool,
     // The major version number of this VDM.
@@ -98,6 +139,116 @@ pub struct VdmHeader {
     pub command_type: CommandType,
     /// The command.
     pub command: Command,
+    /// The command number for [`Command::SVIDSpecific`] commands, i.e. 16 to
+    /// 31. Zero otherwise.
+    pub svid_specific_command: u8,
+    /// The vendor defined bits 14..0 of an unstructured VDM. Zero for
+    /// structured VDMs.
+    pub vendor_use: u32,
+}
+
+impl VdmHeader {
+    /// The header of a structured ACK to `command` for `svid`, as sent by a
+    /// responder implementing structured VDM version 2.0.
+    pub fn ack(svid: u32, command: Command) -> Self {
+        Self {
+            svid,
+            structured: true,
+            major: 2,
+            minor: 0,
+            object_position: 0,
+            command_type: CommandType::Ack,
+            command,
+            svid_specific_command: 0,
+            vendor_use: 0,
+        }
+    }
+}
+
+impl FromBytes for VdmHeader {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        // See USB PD 3.2 - Table 6.28 “Unstructured VDM Header” and Table
+        // 6.29 “Structured VDM Header”.
+        let vendor_bits: u32 = reader.read(15)?;
+        let structured = reader.read_bit()?;
+        let svid = reader.read(16)?;
+
+        if !structured {
+            return Ok(Self {
+                svid,
+                structured,
+                vendor_use: vendor_bits,
+                ..Default::default()
+            });
+        }
+
+        // The low 15 bits hold the structured VDM fields.
+        let bytes = vendor_bits.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let command = reader.read(5)?;
+        reader.skip(1)?; // Reserved
+        let command_type = reader.read(2)?;
+        let object_position = reader.read(3)?;
+        let minor = reader.read(2)?;
+        let major: u8 = reader.read(2)?;
+
+        let (command, svid_specific_command) = match command {
+            16..=31 => (Command::SVIDSpecific, command as u8),
+            _ => (
+                Command::n(command).ok_or_else(|| Error::ParseError {
+                    field: "command".into(),
+                    value: command,
+                    #[cfg(feature = "backtrace")]
+                    backtrace: std::backtrace::Backtrace::capture(),
+                })?,
+                0,
+            ),
+        };
+
+        let command_type = CommandType::n(command_type).ok_or_else(|| Error::ParseError {
+            field: "command_type".into(),
+            value: command_type,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+
+        Ok(Self {
+            svid,
+            structured,
+            // Version 1.0 is encoded as 0b00, version 2.x as 0b01.
+            major: major + 1,
+            minor,
+            object_position,
+            command_type,
+            command,
+            svid_specific_command,
+            vendor_use: 0,
+        })
+    }
+}
+
+impl ToBytes for VdmHeader {
+    fn to_bytes(&self, bw: &mut BitWriter) -> Result<()> {
+        if self.structured {
+            let command = match self.command {
+                Command::SVIDSpecific => u32::from(self.svid_specific_command),
+                command => command as u32,
+            };
+
+            bw.write(5, command)?;
+            bw.write(1, 0)?; // Reserved
+            bw.write(2, self.command_type.clone() as u32)?;
+            bw.write(3, u32::from(self.object_position))?;
+            bw.write(2, u32::from(self.minor))?;
+            bw.write(2, u32::from(self.major.saturating_sub(1)))?;
+        } else {
+            bw.write(15, self.vendor_use)?;
+        }
+
+        bw.write_bit(self.structured)?;
+        bw.write(16, self.svid)?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, CApiWrapper)]
@@ -111,53 +262,36 @@ pub enum Pdo {
 
 impl Pdo {
     pub fn from_bytes(reader: &mut BitReader, revision: BcdWrapper) -> Result<Self> {
+        // The PDO layouts are the same across the USB PD 3.x revisions.
+        if revision.0 >> 8 != 3 {
+            return Err(Error::UnsupportedUsbRevision {
+                revision,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            });
+        }
+
         // See USB PD 3.2. - Table 6.7 “Power Data Object”
-        let pdo_type = reader.read::<u32>(2)?;
+        //
+        // The PDO type lives in the two most significant bits, so read the
+        // whole object before handing it to the type-specific decoder.
+        let pdo = reader.read::<u32>(32)?;
+        let bytes = pdo.to_le_bytes();
+        let reader = &mut BitReader::new(Cursor::new(&bytes[..]));
+        let pdo_type = pdo >> 30;
         match pdo_type {
-            0 => match revision.0 {
-                0x310 => {
-                    let pdo = Pd3p2FixedSupplyPdo::from_bytes(reader)?;
-                    Ok(Pdo::Pd3p2FixedSupplyPdo(pdo))
-                }
-                _ => Err(Error::UnsupportedUsbRevision {
-                    revision,
-                    #[cfg(feature = "backtrace")]
-                    backtrace: std::backtrace::Backtrace::capture(),
-                }),
-            },
-            1 => match revision.0 {
-                0x310 => {
-                    let pdo = Pd3p2BatterySupplyPdo::from_bytes(reader)?;
-                    Ok(Pdo::Pd3p2BatterySupplyPdo(pdo))
-                }
-                _ => Err(Error::UnsupportedUsbRevision {
-                    revision,
-                    #[cfg(feature = "backtrace")]
-                    backtrace: std::backtrace::Backtrace::capture(),
-                }),
-            },
-            2 => match revision.0 {
-                0x310 => {
-                    let pdo = Pd3p2VariableSupplyPdo::from_bytes(reader)?;
-                    Ok(Pdo::Pd3p2VariableSupplyPdo(pdo))
-                }
-                _ => Err(Error::UnsupportedUsbRevision {
-                    revision,
-                    #[cfg(feature = "backtrace")]
-                    backtrace: std::backtrace::Backtrace::capture(),
-                }),
-            },
-            3 => match revision.0 {
-                0x310 => {
-                    let pdo = Pd3p2SprProgrammableSupplyPdo::from_bytes(reader)?;
-                    Ok(Pdo::Pd3p2AugmentedPdo(pdo))
-                }
-                _ => Err(Error::UnsupportedUsbRevision {
-                    revision,
-                    #[cfg(feature = "backtrace")]
-                    backtrace: std::backtrace::Backtrace::capture(),
-                }),
-            },
+            0 => Ok(Pdo::Pd3p2FixedSupplyPdo(Pd3p2FixedSupplyPdo::from_bytes(
+                reader,
+            )?)),
+            1 => Ok(Pdo::Pd3p2BatterySupplyPdo(
+                Pd3p2BatterySupplyPdo::from_bytes(reader)?,
+            )),
+            2 => Ok(Pdo::Pd3p2VariableSupplyPdo(
+                Pd3p2VariableSupplyPdo::from_bytes(reader)?,
+            )),
+            3 => Ok(Pdo::Pd3p2AugmentedPdo(
+                Pd3p2SprProgrammableSupplyPdo::from_bytes(reader)?,
+            )),
             other => Err(Error::ParseError {
                 field: "pdo_type (i.e.: bits31..30)".into(),
                 value: other,
@@ -166,6 +300,39 @@ impl Pdo {
             }),
         }
     }
+
+    /// The highest voltage this PDO can be operated at.
+    pub fn max_voltage(&self) -> Millivolt {
+        match self {
+            Pdo::Pd3p2FixedSupplyPdo(pdo) => pdo.voltage,
+            Pdo::Pd3p2BatterySupplyPdo(pdo) => pdo.max_voltage,
+            Pdo::Pd3p2VariableSupplyPdo(pdo) => pdo.max_voltage,
+            Pdo::Pd3p2AugmentedPdo(pdo) => pdo.max_voltage,
+        }
+    }
+
+    /// The highest current this PDO allows. For battery supplies this is
+    /// derived from the power rating at the lowest voltage.
+    pub fn max_current(&self) -> Milliamp {
+        match self {
+            Pdo::Pd3p2FixedSupplyPdo(pdo) => pdo.operational_current,
+            Pdo::Pd3p2BatterySupplyPdo(pdo) => Milliamp(
+                (pdo.operational_power.0 * 1000)
+                    .checked_div(pdo.min_voltage.0)
+                    .unwrap_or(0),
+            ),
+            Pdo::Pd3p2VariableSupplyPdo(pdo) => pdo.max_current,
+            Pdo::Pd3p2AugmentedPdo(pdo) => pdo.max_current,
+        }
+    }
+
+    /// The highest power this PDO allows.
+    pub fn max_power(&self) -> Milliwatt {
+        match self {
+            Pdo::Pd3p2BatterySupplyPdo(pdo) => pdo.operational_power,
+            _ => Milliwatt(self.max_voltage().0 * self.max_current().0 / 1000),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, CApiWrapper)]
@@ -183,6 +350,32 @@ pub enum Message {
     Pd3p2DiscoverIdentityResponse(Pd3p2DiscoverIdentityResponse),
     /// Revision (Data Message)
     Pd3p2Revision(Pd3p2RevisionMessageData),
+    /// Discover SVIDs Response – ACK, NAK or BUSY (Structured VDM)
+    Pd3p2DiscoverSvidsResponse(Pd3p2DiscoverSvidsResponse),
+    /// Discover Modes Response – ACK, NAK or BUSY (Structured VDM)
+    Pd3p2DiscoverModesResponse(Pd3p2DiscoverModesResponse),
+    /// A Control Message, which carries no data.
+    Pd3p2Control(Pd3p2ControlMessageType),
+    /// Source Capabilities (Data Message)
+    Pd3p2SourceCapabilities(Pd3p2Capabilities),
+    /// Sink Capabilities (Data Message)
+    Pd3p2SinkCapabilities(Pd3p2Capabilities),
+    /// Request (Data Message)
+    Pd3p2Request(Pd3p2RequestDataObject),
+    /// BIST (Data Message)
+    Pd3p2Bist(Pd3p2BistDataObject),
+    /// Alert (Data Message)
+    Pd3p2Alert(Pd3p2AlertDataObject),
+    /// Enter_USB (Data Message)
+    Pd3p2EnterUsb(Pd3p2EnterUsbDataObject),
+    /// EPR_Request (Data Message)
+    Pd3p2EprRequest(Pd3p2EprRequest),
+    /// Any other Vendor_Defined Message (Data Message)
+    Pd3p2VendorDefined(Pd3p2VendorDefinedMessage),
+    /// EPR Source Capabilities (Extended Message)
+    Pd3p2EprSourceCapabilities(Pd3p2Capabilities),
+    /// EPR Sink Capabilities (Extended Message)
+    Pd3p2EprSinkCapabilities(Pd3p2Capabilities),
 }
 
 /// This enum represents the recipient of the PD message.
@@ -224,3 +417,65 @@ pub enum MessageResponseType {
     /// Reserved values.
     Reserved,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_supply_pdo_from_bytes() {
+        // 5V @ 3A, dual-role power, USB communications capable.
+        let bytes = 0x2401912cu32.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes[..]));
+        let pdo = Pdo::from_bytes(&mut reader, BcdWrapper(0x310)).unwrap();
+
+        assert_eq!(pdo.max_voltage(), Millivolt(5000));
+        assert_eq!(pdo.max_current(), Milliamp(3000));
+        assert_eq!(pdo.max_power(), Milliwatt(15000));
+        match pdo {
+            Pdo::Pd3p2FixedSupplyPdo(pdo) => {
+                assert!(pdo.dual_role_power);
+                assert!(pdo.usb_communications_capable);
+                assert!(!pdo.unconstrained_power);
+            }
+            other => panic!("Unexpected PDO {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pps_apdo_from_bytes() {
+        // 3.3V-21V @ 3A
+        let bytes = 0xc1a4213cu32.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes[..]));
+        let pdo = Pdo::from_bytes(&mut reader, BcdWrapper(0x310)).unwrap();
+
+        match pdo {
+            Pdo::Pd3p2AugmentedPdo(pdo) => {
+                assert_eq!(pdo.min_voltage, Millivolt(3300));
+                assert_eq!(pdo.max_voltage, Millivolt(21000));
+                assert_eq!(pdo.max_current, Milliamp(3000));
+            }
+            other => panic!("Unexpected PDO {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_vdm_header_round_trip() {
+        // A structured VDM version 2.1 Discover Identity BUSY.
+        let raw = 0xff00a8c1u32;
+        let bytes = raw.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let header = VdmHeader::from_bytes(&mut reader).unwrap();
+
+        assert_eq!(header.svid, PD_SID);
+        assert!(header.structured);
+        assert_eq!((header.major, header.minor), (2, 1));
+        assert_eq!(header.command_type, CommandType::Busy);
+        assert_eq!(header.command, Command::DiscoverIdentity);
+
+        let mut buf = [0; 4];
+        let mut bw = BitWriter::new(Cursor::new(&mut buf[..]));
+        header.to_bytes(&mut bw).unwrap();
+        assert_eq!(u32::from_le_bytes(buf), raw);
+    }
+}
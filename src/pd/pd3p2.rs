@@ -4,30 +4,49 @@
 
 //! USB Power Delivery 3.2 (PD3.2) functionality.
 
+use std::io::Cursor;
+
 use bitstream_io::BitRead;
+use bitstream_io::BitWrite;
 use enumn::N;
 use proc_macros::CApiWrapper;
 
+use crate::bitflags_wrapper;
+use crate::pd::Command;
+use crate::pd::CommandType;
+use crate::pd::MessageRecipient;
+use crate::pd::Pdo;
 use crate::pd::VdmHeader;
+use crate::pd::PD_SID;
+use crate::BcdWrapper;
+use crate::BitReader;
+use crate::BitWriter;
 use crate::Error;
 use crate::FromBytes;
 use crate::Milliamp;
 use crate::Millivolt;
 use crate::Milliwatt;
 use crate::Result;
+use crate::ToBytes;
 
 use crate::pd::pd3p2::vdo::CertStat;
 use crate::pd::pd3p2::vdo::IdHeader;
 use crate::pd::pd3p2::vdo::Product;
-use crate::pd::pd3p2::vdo::ProductType;
+use crate::pd::pd3p2::vdo::SopDfpProductType;
+use crate::pd::pd3p2::vdo::SopUfpProductType;
+use crate::pd::pd3p2::vdo::UsbHighestSpeed;
+use crate::pd::pd3p2::vdo::MAX_NUM_ALT_MODE;
+use crate::vdo::read_vdo;
+use crate::vdo::Vdo;
 
 #[cfg(feature = "c_api")]
 pub(crate) mod c {
     pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoCertStat;
     pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoIdHeader;
     pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoProduct;
-    pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoProductType;
+    pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoUsbHighestSpeed;
     pub(crate) use crate::pd::PdVdmHeader;
+    pub(crate) use crate::vdo::TypeCRsOptionalVdo;
 }
 
 #[cfg(feature = "c_api")]
@@ -47,8 +66,292 @@ pub struct DiscoverIdentityResponse {
     pub cert_stat: CertStat,
     #[c_api(rename_type = "Pd3p2VdoProduct")]
     pub product_vdo: Product,
-    #[c_api(rename_type = "[Pd3p2VdoProductType; 3]")]
-    pub product_type_vdo: [ProductType; 3],
+    /// The Product Type VDOs, decoded according to the product type in the
+    /// ID Header. Unused slots are `None`.
+    #[c_api(rename_type = "[TypeCRsOptionalVdo; 3]")]
+    pub product_type_vdo: [Option<Vdo>; 3],
+}
+
+impl DiscoverIdentityResponse {
+    /// The maximum number of VDOs following the VDM Header: the ID Header, Cert
+    /// Stat and Product VDOs plus up to three Product Type VDOs.
+    pub const MAX_VDOS: usize = 6;
+
+    /// Builds a response from a whole Discover Identity message, i.e. the
+    /// VDM Header followed by the VDOs, as sent by `recipient`. NAK and BUSY
+    /// responses carry no VDOs, so only their header is filled in.
+    pub fn from_message(dwords: &[u32], recipient: MessageRecipient) -> Result<Self> {
+        let (header, vdos) = split_header(dwords)?;
+        if header.command_type != CommandType::Ack {
+            return Ok(Self {
+                header,
+                ..Default::default()
+            });
+        }
+
+        Ok(Self {
+            header,
+            ..Self::from_vdos(vdos, recipient)?
+        })
+    }
+
+    /// Builds a response from the VDOs that follow the VDM Header, as sent by
+    /// `recipient`. Missing trailing VDOs are treated as zero. Only ACKs carry
+    /// VDOs, so the header is that of a Discover Identity ACK.
+    pub fn from_vdos(vdos: &[u32], recipient: MessageRecipient) -> Result<Self> {
+        let mut raw = [0; Self::MAX_VDOS];
+        for (dst, src) in raw.iter_mut().zip(vdos) {
+            *dst = *src;
+        }
+
+        let bytes = raw[0].to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let id_header_vdo = IdHeader::from_bytes_for_recipient(&mut reader, recipient)?;
+        let cert_stat = read_vdo::<CertStat>(raw[1])?;
+        let product_vdo = read_vdo::<Product>(raw[2])?;
+        let product_type_vdo =
+            Self::decode_product_type_vdos(&id_header_vdo, recipient, &[raw[3], raw[4], raw[5]])?;
+
+        Ok(Self {
+            header: VdmHeader::ack(PD_SID, Command::DiscoverIdentity),
+            id_header_vdo,
+            cert_stat,
+            product_vdo,
+            product_type_vdo,
+        })
+    }
+
+    /// Decodes the raw Product Type VDOs that follow the Product VDO, choosing
+    /// the VDO layout from the product types advertised in `id_header`.
+    ///
+    /// See USB PD 3.2 - 6.4.4.3.1 Discover Identity for the placement rules.
+    pub fn decode_product_type_vdos(
+        id_header: &IdHeader,
+        recipient: MessageRecipient,
+        vdos: &[u32; 3],
+    ) -> Result<[Option<Vdo>; 3]> {
+        if matches!(
+            recipient,
+            MessageRecipient::SopPrime | MessageRecipient::SopDoublePrime
+        ) {
+            let decoded = match id_header.sop_product_type_ufp {
+                SopUfpProductType::PassiveCable => {
+                    [Some(Vdo::Pd3p2PassiveCable(read_vdo(vdos[0])?)), None, None]
+                }
+                SopUfpProductType::ActiveCable => [
+                    Some(Vdo::Pd3p2ActiveCable1(read_vdo(vdos[0])?)),
+                    Some(Vdo::Pd3p2ActiveCable2(read_vdo(vdos[1])?)),
+                    None,
+                ],
+                SopUfpProductType::VConnPoweredUsbDevice => {
+                    [Some(Vdo::Pd3p2Vpd(read_vdo(vdos[0])?)), None, None]
+                }
+                _ => [None, None, None],
+            };
+
+            return Ok(decoded);
+        }
+
+        let has_ufp_vdo = matches!(
+            id_header.sop_product_type_ufp,
+            SopUfpProductType::PdUsbHub | SopUfpProductType::PdUsbPeripheral
+        );
+        let has_dfp_vdo = id_header.sop_product_type_dfp != SopDfpProductType::NotADfp;
+
+        let decoded = match (has_ufp_vdo, has_dfp_vdo) {
+            (true, false) => [Some(Vdo::Pd3p2Ufp(read_vdo(vdos[0])?)), None, None],
+            (false, true) => [Some(Vdo::Pd3p2Dfp(read_vdo(vdos[0])?)), None, None],
+            // A DRD puts its DFP VDO last, after a padding VDO.
+            (true, true) => [
+                Some(Vdo::Pd3p2Ufp(read_vdo(vdos[0])?)),
+                None,
+                Some(Vdo::Pd3p2Dfp(read_vdo(vdos[2])?)),
+            ],
+            (false, false) => [None, None, None],
+        };
+
+        Ok(decoded)
+    }
+}
+
+/// The maximum number of SVIDs in a single Discover SVIDs response: two per
+/// VDO.
+pub const MAX_SVIDS_PER_RESPONSE: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// The response to a Discover SVIDs command.
+///
+/// A responder supporting more SVIDs than fit in a single response expects
+/// further Discover SVIDs commands until a response is terminated. See USB PD
+/// 3.2 - 6.4.4.3.2 Discover SVIDs.
+pub struct DiscoverSvidsResponse {
+    #[c_api(rename_type = "PdVdmHeader")]
+    pub header: VdmHeader,
+    /// The SVIDs, in the order they were reported.
+    #[c_api(no_prefix)]
+    pub svids: [u32; MAX_SVIDS_PER_RESPONSE],
+    /// The number of valid entries in `svids`.
+    pub num_svids: u32,
+    /// Whether the list ends in this response, i.e. no further Discover SVIDs
+    /// command is needed.
+    pub terminated: bool,
+}
+
+impl DiscoverSvidsResponse {
+    /// Builds a response from a whole Discover SVIDs message, i.e. the VDM
+    /// Header followed by the VDOs. NAK and BUSY responses carry no VDOs.
+    pub fn from_message(dwords: &[u32]) -> Result<Self> {
+        let (header, vdos) = split_header(dwords)?;
+        if header.command_type != CommandType::Ack {
+            return Ok(Self {
+                header,
+                terminated: true,
+                ..Default::default()
+            });
+        }
+
+        Ok(Self {
+            header,
+            ..Self::from_vdos(vdos)
+        })
+    }
+
+    /// Builds a response from the VDOs that follow the VDM Header. Each VDO
+    /// holds two SVIDs, the first one in the upper 16 bits. A zero SVID, or
+    /// a response shorter than the maximum, terminates the list.
+    pub fn from_vdos(vdos: &[u32]) -> Self {
+        let mut response = Self {
+            header: VdmHeader::ack(PD_SID, Command::DiscoverSVIDs),
+            terminated: vdos.len() < MAX_VDM_VDOS,
+            ..Default::default()
+        };
+
+        let svids = vdos
+            .iter()
+            .take(MAX_VDM_VDOS)
+            .flat_map(|vdo| [vdo >> 16, vdo & 0xffff]);
+
+        for svid in svids {
+            if svid == 0 {
+                response.terminated = true;
+                break;
+            }
+
+            response.svids[response.num_svids as usize] = svid;
+            response.num_svids += 1;
+        }
+
+        response
+    }
+
+    /// Splits `svids` into as many responses as a responder would send,
+    /// including a terminating empty response if the last one is full.
+    pub fn from_svids(svids: &[u32]) -> Vec<Self> {
+        let mut responses = svids
+            .chunks(MAX_SVIDS_PER_RESPONSE)
+            .map(|chunk| {
+                let vdos = chunk
+                    .chunks(2)
+                    .map(|pair| pair[0] << 16 | pair.get(1).copied().unwrap_or(0))
+                    .collect::<Vec<_>>();
+                Self::from_vdos(&vdos)
+            })
+            .collect::<Vec<_>>();
+
+        if !responses.last().is_some_and(|last| last.terminated) {
+            responses.push(Self::from_vdos(&[]));
+        }
+
+        responses
+    }
+
+    /// The SVIDs in this response.
+    pub fn svids(&self) -> &[u32] {
+        &self.svids[..self.num_svids as usize]
+    }
+
+    /// Concatenates the SVIDs of consecutive responses, up to and including
+    /// the terminated one.
+    pub fn collect<'a>(responses: impl IntoIterator<Item = &'a Self>) -> Vec<u32> {
+        let mut svids = vec![];
+        for response in responses {
+            svids.extend_from_slice(response.svids());
+            if response.terminated {
+                break;
+            }
+        }
+
+        svids
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// The response to a Discover Modes command for the SVID in the header.
+/// See USB PD 3.2 - 6.4.4.3.3 Discover Modes.
+pub struct DiscoverModesResponse {
+    #[c_api(rename_type = "PdVdmHeader")]
+    pub header: VdmHeader,
+    /// The raw Mode VDOs. Their layout is defined by the SVID, see
+    /// [`crate::vdo::svid::decode`].
+    #[c_api(no_prefix)]
+    pub modes: [u32; MAX_NUM_ALT_MODE],
+    /// The number of valid entries in `modes`.
+    pub num_modes: u32,
+}
+
+impl DiscoverModesResponse {
+    /// Builds a response from a whole Discover Modes message, i.e. the VDM
+    /// Header followed by the VDOs. NAK and BUSY responses carry no VDOs.
+    pub fn from_message(dwords: &[u32]) -> Result<Self> {
+        let (header, vdos) = split_header(dwords)?;
+        let vdos = if header.command_type == CommandType::Ack {
+            vdos
+        } else {
+            &[]
+        };
+
+        Ok(Self {
+            header: header.clone(),
+            ..Self::from_vdos(header.svid, vdos)
+        })
+    }
+
+    /// Builds a response for `svid` from the Mode VDOs that follow the VDM
+    /// Header. VDOs beyond [`MAX_NUM_ALT_MODE`] are ignored.
+    pub fn from_vdos(svid: u32, vdos: &[u32]) -> Self {
+        let mut response = Self {
+            header: VdmHeader::ack(svid, Command::DiscoverModes),
+            ..Default::default()
+        };
+
+        for (dst, src) in response.modes.iter_mut().zip(vdos) {
+            *dst = *src;
+            response.num_modes += 1;
+        }
+
+        response
+    }
+
+    /// The Mode VDOs in this response.
+    pub fn modes(&self) -> &[u32] {
+        &self.modes[..self.num_modes as usize]
+    }
+}
+
+/// Splits a structured VDM into its decoded header and the VDOs that follow.
+fn split_header(dwords: &[u32]) -> Result<(VdmHeader, &[u32])> {
+    let Some((header, vdos)) = dwords.split_first() else {
+        return Err(Error::ParseError {
+            field: "header".into(),
+            value: 0,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        });
+    };
+
+    Ok((read_vdo::<VdmHeader>(*header)?, vdos))
 }
 
 #[cfg(feature = "c_api")]
@@ -62,7 +365,7 @@ impl From<Pd3p2DiscoverIdentityResponse> for DiscoverIdentityResponse {
             product_type_vdo: value
                 .product_type_vdo
                 .into_iter()
-                .map(ProductType::from)
+                .map(Option::<Vdo>::from)
                 .collect::<Vec<_>>()
                 .try_into()
                 .unwrap(),
@@ -81,7 +384,7 @@ impl From<DiscoverIdentityResponse> for Pd3p2DiscoverIdentityResponse {
             product_type_vdo: value
                 .product_type_vdo
                 .into_iter()
-                .map(Pd3p2VdoProductType::from)
+                .map(TypeCRsOptionalVdo::from)
                 .collect::<Vec<_>>()
                 .try_into()
                 .unwrap(),
@@ -332,6 +635,673 @@ pub struct RevisionMessageData {
     pub reserved: u32,
 }
 
+impl FromBytes for BatteryStatusData {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        // See USB PD 3.2 - Table 6.48 “Battery Status Data Object (BSDO)”
+        reader.skip(8)?; // Reserved
+        let invalid_battery_reference = reader.read_bit()?;
+        let battery_present = reader.read_bit()?;
+        let charging_status = reader.read::<u32>(2)?;
+        reader.skip(4)?; // Reserved
+        let battery_present_capacity = reader.read(16)?;
+
+        Ok(Self {
+            battery_present_capacity,
+            battery_info: BSDBatteryInfo {
+                invalid_battery_reference,
+                battery_present,
+                battery_charging: battery_present && charging_status == 0,
+                battery_discharging: battery_present && charging_status == 1,
+                battery_idle: battery_present && charging_status == 2,
+            },
+        })
+    }
+}
+
+impl FromBytes for RevisionMessageData {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        // See USB PD 3.2 - Table 6.56 “Revision Message Data Object (RMDO)”
+        let reserved = reader.read(16)?;
+        let version_minor = reader.read(4)?;
+        let version_major = reader.read(4)?;
+        let revision_minor = reader.read(4)?;
+        let revision_major = reader.read(4)?;
+
+        Ok(Self {
+            revision_major,
+            revision_minor,
+            version_major,
+            version_minor,
+            reserved,
+        })
+    }
+}
+
+impl FromBytes for SinkLoadCharacteristics {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let percent_overload = reader.read::<u32>(5)? != 0;
+        let overload_period = reader.read::<u32>(6)? != 0;
+        let duty_cycle = reader.read::<u32>(4)? != 0;
+        let vbus_voltage_droop = reader.read_bit()?;
+
+        Ok(Self {
+            percent_overload,
+            overload_period,
+            duty_cycle,
+            vbus_voltage_droop,
+        })
+    }
+}
+
+impl FromBytes for ScePeakCurrent {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let percent_overload = reader.read::<u32>(5)? != 0;
+        let overload_period = reader.read::<u32>(6)? != 0;
+        let duty_cycle = reader.read::<u32>(4)? != 0;
+        let vbus_voltage_droop = reader.read_bit()?;
+
+        Ok(Self {
+            percent_overload,
+            overload_period,
+            duty_cycle,
+            vbus_voltage_droop,
+        })
+    }
+}
+
+fn read_touch_temp(reader: &mut BitReader) -> Result<SceTouchTemp> {
+    let touch_temp = reader.read(8)?;
+    SceTouchTemp::n(touch_temp).ok_or_else(|| Error::ParseError {
+        field: "touch_temp".into(),
+        value: touch_temp,
+        #[cfg(feature = "backtrace")]
+        backtrace: std::backtrace::Backtrace::capture(),
+    })
+}
+
+impl FromBytes for SourceCapabilitiesExtended {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        // See USB PD 3.2 - Table 6.61 “Source Capabilities Extended Data
+        // Block (SCEDB)”
+        let vid = reader.read(16)?;
+        let pid = reader.read(16)?;
+        let xid = reader.read(32)?;
+        let fw_version = reader.read(8)?;
+        let hw_version = reader.read(8)?;
+
+        let load_step = reader.read::<u32>(2)?;
+        let ioc_90_percent = reader.read_bit()?;
+        reader.skip(5)?; // Reserved
+        let voltage_regulation = SceVoltageRegulation {
+            load_step_150ma: load_step == 0,
+            load_step_500ma: load_step == 1,
+            ioc_25_percent: !ioc_90_percent,
+            ioc_90_percent,
+        };
+
+        let holdup_time = reader.read(8)?;
+
+        let compliance = SceCompliance {
+            lps_compliant: reader.read_bit()?,
+            ps1_compliant: reader.read_bit()?,
+            ps2_compliant: reader.read_bit()?,
+        };
+        reader.skip(5)?; // Reserved
+
+        let touch_current = SceTouchCurrent {
+            low_touch_current_eps: reader.read_bit()?,
+            ground_pin_supported: reader.read_bit()?,
+            ground_pin_for_protective_earth: reader.read_bit()?,
+        };
+        reader.skip(5)?; // Reserved
+
+        let peak_current1 = ScePeakCurrent::from_bytes(reader)?;
+        let peak_current2 = ScePeakCurrent::from_bytes(reader)?;
+        let peak_current3 = ScePeakCurrent::from_bytes(reader)?;
+        let touch_temp = read_touch_temp(reader)?;
+
+        let external_supply = reader.read_bit()?;
+        let external_supply_unconstrained = reader.read_bit()?;
+        let internal_battery_present = reader.read_bit()?;
+        reader.skip(5)?; // Reserved
+        let source_inputs = SceSourceInputs {
+            no_external_supply: !external_supply,
+            external_supply_constrained: external_supply && !external_supply_unconstrained,
+            internal_battery_present,
+        };
+
+        let num_batteries_slots = reader.read(8)?;
+        let spr_source_pdp_rating = reader.read(8)?;
+        let epr_source_pdp_rating = reader.read(8)?;
+
+        Ok(Self {
+            vid,
+            pid,
+            xid,
+            fw_version,
+            hw_version,
+            voltage_regulation,
+            holdup_time,
+            compliance,
+            touch_current,
+            peak_current1,
+            peak_current2,
+            peak_current3,
+            touch_temp,
+            source_inputs,
+            num_batteries_slots,
+            spr_source_pdp_rating,
+            epr_source_pdp_rating,
+        })
+    }
+}
+
+impl FromBytes for SinkCapabilitiesExtended {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        // See USB PD 3.2 - Table 6.71 “Sink Capabilities Extended Data Block
+        // (SKEDB)”
+        let vid = reader.read(16)?;
+        let pid = reader.read(16)?;
+        let xid = reader.read(32)?;
+        let fw_version = reader.read(8)?;
+        let hw_version = reader.read(8)?;
+        let skedb_version = reader.read(8)?;
+
+        let load_step = reader.read::<u32>(2)?;
+        reader.skip(6)?; // Reserved
+        let load_step = SceLoadStep {
+            load_step_150ma: load_step == 0,
+            load_step_500ma: load_step == 1,
+        };
+
+        let sink_load_characteristics = SinkLoadCharacteristics::from_bytes(reader)?;
+
+        let compliance = SCEDCompliance {
+            requires_lps_source: reader.read_bit()?,
+            requires_ps1_source: reader.read_bit()?,
+            requires_ps2_source: reader.read_bit()?,
+        };
+        reader.skip(5)?; // Reserved
+
+        let touch_temp = read_touch_temp(reader)?;
+        let battery_info = reader.read(8)?;
+
+        let sink_modes = SCEDSinkModes {
+            pps_charging_supported: reader.read_bit()?,
+            vbus_powered: reader.read_bit()?,
+            mains_powered: reader.read_bit()?,
+            battery_powered: reader.read_bit()?,
+            battery_essentially_unlimited: reader.read_bit()?,
+            avs_supported: reader.read_bit()?,
+        };
+        reader.skip(2)?; // Reserved
+
+        let sink_minimum_pdp = reader.read(8)?;
+        let sink_operational_pdp = reader.read(8)?;
+
+        Ok(Self {
+            vid,
+            pid,
+            xid,
+            fw_version,
+            hw_version,
+            skedb_version,
+            load_step,
+            sink_load_characteristics,
+            compliance,
+            touch_temp,
+            battery_info,
+            sink_modes,
+            sink_minimum_pdp,
+            sink_operational_pdp,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// See USB PD 3.2 - Table 6.5 “Control Message Types”
+pub enum ControlMessageType {
+    #[default]
+    GoodCrc = 1,
+    GotoMin,
+    Accept,
+    Reject,
+    Ping,
+    PsRdy,
+    GetSourceCap,
+    GetSinkCap,
+    DrSwap,
+    PrSwap,
+    VconnSwap,
+    Wait,
+    SoftReset,
+    DataReset,
+    DataResetComplete,
+    NotSupported,
+    GetSourceCapExtended,
+    GetStatus,
+    FrSwap,
+    GetPpsStatus,
+    GetCountryCodes,
+    GetSinkCapExtended,
+    GetSourceInfo,
+    GetRevision,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// See USB PD 3.2 - Table 6.6 “Data Message Types”
+pub enum DataMessageType {
+    #[default]
+    SourceCapabilities = 1,
+    Request,
+    Bist,
+    SinkCapabilities,
+    BatteryStatus,
+    Alert,
+    GetCountryInfo,
+    EnterUsb,
+    EprRequest,
+    EprMode,
+    SourceInfo,
+    Revision,
+    VendorDefined = 15,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// See USB PD 3.2 - Table 6.53 “Extended Message Types”
+pub enum ExtendedMessageType {
+    #[default]
+    SourceCapabilitiesExtended = 1,
+    Status,
+    GetBatteryCap,
+    GetBatteryStatus,
+    BatteryCapabilities,
+    GetManufacturerInfo,
+    ManufacturerInfo,
+    SecurityRequest,
+    SecurityResponse,
+    FirmwareUpdateRequest,
+    FirmwareUpdateResponse,
+    PpsStatus,
+    CountryInfo,
+    CountryCodes,
+    SinkCapabilitiesExtended,
+    ExtendedControl,
+    EprSourceCapabilities,
+    EprSinkCapabilities,
+    VendorDefinedExtended = 30,
+}
+
+/// The maximum number of PDOs in a capabilities message. SPR messages carry
+/// up to 7, EPR messages up to 11.
+pub const MAX_CAPABILITY_PDOS: usize = 11;
+
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// The PDOs of a Source_Capabilities, Sink_Capabilities,
+/// EPR_Source_Capabilities or EPR_Sink_Capabilities message.
+pub struct Capabilities {
+    /// The raw PDOs, in object position order. EPR messages pad the SPR PDOs
+    /// with zeros up to position 7.
+    #[c_api(no_prefix)]
+    pub pdos: [u32; MAX_CAPABILITY_PDOS],
+    /// The number of valid entries in `pdos`.
+    pub num_pdos: u32,
+}
+
+impl Capabilities {
+    /// Builds the capabilities from raw PDOs. PDOs beyond
+    /// [`MAX_CAPABILITY_PDOS`] are ignored.
+    pub fn from_pdos(raw: &[u32]) -> Self {
+        let mut capabilities = Self::default();
+        for (dst, src) in capabilities.pdos.iter_mut().zip(raw) {
+            *dst = *src;
+            capabilities.num_pdos += 1;
+        }
+
+        capabilities
+    }
+
+    /// Decodes the PDOs, skipping the zero padding of EPR messages.
+    pub fn pdos(&self) -> Result<Vec<Pdo>> {
+        self.pdos[..self.num_pdos as usize]
+            .iter()
+            .filter(|pdo| **pdo != 0)
+            .map(|pdo| {
+                let bytes = pdo.to_le_bytes();
+                let mut reader = BitReader::new(Cursor::new(&bytes));
+                Pdo::from_bytes(&mut reader, BcdWrapper(0x310))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// See USB PD 3.2 - 6.4.2 Request Message
+///
+/// The meaning of the low 22 bits depends on the type of the requested PDO,
+/// see the accessors below.
+pub struct RequestDataObject {
+    /// The object position of the requested PDO, starting at 1.
+    pub object_position: u8,
+    /// GiveBack flag, deprecated in USB PD 3.x.
+    pub giveback: bool,
+    /// Capability Mismatch
+    pub capability_mismatch: bool,
+    /// USB Communications Capable
+    pub usb_communications_capable: bool,
+    /// No USB Suspend
+    pub no_usb_suspend: bool,
+    /// Unchunked Extended Messages Supported
+    pub unchunked_extended_messages_supported: bool,
+    /// EPR Mode Capable
+    pub epr_mode_capable: bool,
+    /// Bits 21..0 of the RDO.
+    pub payload: u32,
+}
+
+impl RequestDataObject {
+    /// The operating current for a Fixed or Variable Supply PDO.
+    pub fn operating_current(&self) -> Milliamp {
+        Milliamp(((self.payload >> 10) & 0x3ff) * 10)
+    }
+
+    /// The maximum operating current for a Fixed or Variable Supply PDO.
+    pub fn max_operating_current(&self) -> Milliamp {
+        Milliamp((self.payload & 0x3ff) * 10)
+    }
+
+    /// The operating power for a Battery Supply PDO.
+    pub fn operating_power(&self) -> Milliwatt {
+        Milliwatt(((self.payload >> 10) & 0x3ff) * 250)
+    }
+
+    /// The output voltage for a PPS or AVS APDO.
+    pub fn output_voltage(&self) -> Millivolt {
+        Millivolt(((self.payload >> 9) & 0xfff) * 20)
+    }
+
+    /// The operating current for a PPS or AVS APDO.
+    pub fn programmable_operating_current(&self) -> Milliamp {
+        Milliamp((self.payload & 0x7f) * 50)
+    }
+}
+
+impl FromBytes for RequestDataObject {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let payload = reader.read(22)?;
+        let epr_mode_capable = reader.read_bit()?;
+        let unchunked_extended_messages_supported = reader.read_bit()?;
+        let no_usb_suspend = reader.read_bit()?;
+        let usb_communications_capable = reader.read_bit()?;
+        let capability_mismatch = reader.read_bit()?;
+        let giveback = reader.read_bit()?;
+        let object_position = reader.read(4)?;
+
+        Ok(Self {
+            object_position,
+            giveback,
+            capability_mismatch,
+            usb_communications_capable,
+            no_usb_suspend,
+            unchunked_extended_messages_supported,
+            epr_mode_capable,
+            payload,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// See USB PD 3.2 - 6.4.9 EPR_Request Message
+pub struct EprRequest {
+    /// The Request Data Object.
+    pub rdo: RequestDataObject,
+    /// A copy of the requested PDO.
+    pub pdo: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// See USB PD 3.2 - Table 6.27 “BIST Data Object”
+pub enum BistMode {
+    #[default]
+    CarrierMode = 5,
+    TestData = 8,
+    SharedTestModeEntry = 9,
+    SharedTestModeExit = 10,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// See USB PD 3.2 - 6.4.3 BIST Message
+pub struct BistDataObject {
+    /// The requested BIST mode.
+    pub mode: BistMode,
+}
+
+impl FromBytes for BistDataObject {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        reader.skip(28)?; // Reserved
+        let mode = reader.read(4)?;
+        let mode = BistMode::n(mode).ok_or_else(|| Error::ParseError {
+            field: "mode".into(),
+            value: mode,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+
+        Ok(Self { mode })
+    }
+}
+
+bitflags_wrapper! {
+    Pd3p2,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// The Type of Alert field of an Alert Data Object.
+    pub struct AlertType: u32 {
+        const BATTERY_STATUS_CHANGE = 1 << 1;
+        const OCP = 1 << 2;
+        const OTP = 1 << 3;
+        const OPERATING_CONDITION_CHANGE = 1 << 4;
+        const SOURCE_INPUT_CHANGE = 1 << 5;
+        const OVP = 1 << 6;
+        const EXTENDED_ALERT = 1 << 7;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// See USB PD 3.2 - Table 6.50 “Alert Data Object”
+pub struct AlertDataObject {
+    /// Type of Alert
+    pub alert_type: AlertType,
+    /// A bitmap of the Fixed Batteries whose status changed.
+    pub fixed_batteries: u8,
+    /// A bitmap of the Hot Swappable Batteries whose status changed.
+    pub hot_swappable_batteries: u8,
+    /// Extended Alert Event Type, valid if `EXTENDED_ALERT` is set.
+    pub extended_alert_event_type: u8,
+}
+
+impl FromBytes for AlertDataObject {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let extended_alert_event_type = reader.read(4)?;
+        reader.skip(12)?; // Reserved
+        let hot_swappable_batteries = reader.read(4)?;
+        let fixed_batteries = reader.read(4)?;
+        let alert_type = AlertType::from_bits_truncate(reader.read(8)?);
+
+        Ok(Self {
+            alert_type,
+            fixed_batteries,
+            hot_swappable_batteries,
+            extended_alert_event_type,
+        })
+    }
+}
+
+/// The maximum number of VDOs following a VDM Header.
+pub const MAX_VDM_VDOS: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// A Vendor_Defined message not decoded into a more specific response.
+pub struct VendorDefinedMessage {
+    #[c_api(rename_type = "PdVdmHeader")]
+    pub header: VdmHeader,
+    /// The VDOs following the VDM Header.
+    #[c_api(no_prefix)]
+    pub vdos: [u32; MAX_VDM_VDOS],
+    /// The number of valid entries in `vdos`.
+    pub num_vdos: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// The USB mode requested by an Enter_USB message.
+pub enum EnterUsbMode {
+    #[default]
+    /// [USB 2.0]
+    Usb2_0 = 0,
+    /// [USB 3.2]
+    Usb3_2,
+    /// [USB4]
+    Usb4,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// The type of cable, as reported in an Enter_USB message.
+pub enum EnterUsbCableType {
+    #[default]
+    Passive = 0,
+    ActiveRetimer,
+    ActiveRedriver,
+    OpticallyIsolated,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+/// The current capability of the cable, as reported in an Enter_USB message.
+pub enum EnterUsbCableCurrent {
+    #[default]
+    VbusNotSupported = 0,
+    Reserved,
+    /// 3A
+    A3,
+    /// 5A
+    A5,
+}
+
+/// See USB PD 3.2 - 6.4.8 Enter_USB Message
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2", repr_c)]
+pub struct EnterUsbDataObject {
+    /// The USB mode to enter.
+    pub usb_mode: EnterUsbMode,
+    /// Whether the port is [USB4] DRD capable.
+    pub usb4_drd: bool,
+    /// Whether the port is [USB 3.2] DRD capable.
+    pub usb3_drd: bool,
+    /// The highest speed supported by the cable.
+    #[c_api(rename_type = "Pd3p2VdoUsbHighestSpeed")]
+    pub cable_speed: UsbHighestSpeed,
+    /// The type of cable.
+    pub cable_type: EnterUsbCableType,
+    /// The current capability of the cable.
+    pub cable_current: EnterUsbCableCurrent,
+    /// Whether PCIe tunneling is supported.
+    pub pcie_supported: bool,
+    /// Whether DisplayPort tunneling is supported.
+    pub dp_supported: bool,
+    /// Whether Thunderbolt 3 compatibility is supported.
+    pub tbt_supported: bool,
+    /// Whether a USB host is present.
+    pub host_present: bool,
+}
+
+impl FromBytes for EnterUsbDataObject {
+    fn from_bytes(reader: &mut crate::BitReader) -> Result<Self> {
+        reader.skip(13)?; // Reserved
+        let host_present = reader.read_bit()?;
+        let tbt_supported = reader.read_bit()?;
+        let dp_supported = reader.read_bit()?;
+        let pcie_supported = reader.read_bit()?;
+        let cable_current = reader.read(2)?;
+        let cable_current =
+            EnterUsbCableCurrent::n(cable_current).ok_or_else(|| Error::ParseError {
+                field: "cable_current".into(),
+                value: cable_current,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })?;
+        let cable_type = reader.read(2)?;
+        let cable_type = EnterUsbCableType::n(cable_type).ok_or_else(|| Error::ParseError {
+            field: "cable_type".into(),
+            value: cable_type,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+        let cable_speed = reader.read(3)?;
+        let cable_speed = UsbHighestSpeed::n(cable_speed).ok_or_else(|| Error::ParseError {
+            field: "cable_speed".into(),
+            value: cable_speed,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+        reader.skip(1)?; // Reserved
+        let usb3_drd = reader.read_bit()?;
+        let usb4_drd = reader.read_bit()?;
+        reader.skip(1)?; // Reserved
+        let usb_mode = reader.read(3)?;
+        let usb_mode = EnterUsbMode::n(usb_mode).ok_or_else(|| Error::ParseError {
+            field: "usb_mode".into(),
+            value: usb_mode,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+        reader.skip(1)?; // Reserved
+
+        Ok(Self {
+            usb_mode,
+            usb4_drd,
+            usb3_drd,
+            cable_speed,
+            cable_type,
+            cable_current,
+            pcie_supported,
+            dp_supported,
+            tbt_supported,
+            host_present,
+        })
+    }
+}
+
+impl ToBytes for EnterUsbDataObject {
+    fn to_bytes(&self, bw: &mut BitWriter) -> Result<()> {
+        bw.write(13, 0)?; // Reserved
+        bw.write_bit(self.host_present)?;
+        bw.write_bit(self.tbt_supported)?;
+        bw.write_bit(self.dp_supported)?;
+        bw.write_bit(self.pcie_supported)?;
+        bw.write(2, self.cable_current as u32)?;
+        bw.write(2, self.cable_type as u32)?;
+        bw.write(3, self.cable_speed as u32)?;
+        bw.write(1, 0)?; // Reserved
+        bw.write_bit(self.usb3_drd)?;
+        bw.write_bit(self.usb4_drd)?;
+        bw.write(1, 0)?; // Reserved
+        bw.write(3, self.usb_mode as u32)?;
+        bw.write(1, 0)?; // Reserved
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
 #[c_api(prefix = "Pd3p2", repr_c)]
 /// See USB PD 3.2 - Table 6.17 “Fixed Supply PDO – Sink”
@@ -362,12 +1332,9 @@ impl FromBytes for FixedSupplyPdo {
     where
         Self: Sized,
     {
-        let _ = reader.read::<u32>(2)?; // Fixed supply
-        let dual_role_power = reader.read_bit()?;
-        let higher_capability = reader.read_bit()?;
-        let unconstrained_power = reader.read_bit()?;
-        let usb_communications_capable = reader.read_bit()?;
-        let dual_role_data = reader.read_bit()?;
+        let operational_current = Milliamp(reader.read::<u32>(10)? * 10);
+        let voltage = Millivolt(reader.read::<u32>(10)? * 50);
+        reader.skip(3)?; // Reserved
         let fast_role_swap_bits = reader.read::<u32>(2)?;
         let fast_role_swap =
             FastRoleSwap::n(fast_role_swap_bits).ok_or_else(|| Error::ParseError {
@@ -376,8 +1343,12 @@ impl FromBytes for FixedSupplyPdo {
                 #[cfg(feature = "backtrace")]
                 backtrace: std::backtrace::Backtrace::capture(),
             })?;
-        let voltage = (reader.read::<u32>(10)? / 50).into();
-        let operational_current = (reader.read::<u32>(10)? / 10).into();
+        let dual_role_data = reader.read_bit()?;
+        let usb_communications_capable = reader.read_bit()?;
+        let unconstrained_power = reader.read_bit()?;
+        let higher_capability = reader.read_bit()?;
+        let dual_role_power = reader.read_bit()?;
+        reader.skip(2)?; // Fixed supply
 
         Ok(Self {
             dual_role_power,
@@ -405,10 +1376,10 @@ impl FromBytes for BatterySupplyPdo {
     where
         Self: Sized,
     {
-        let _ = bit_reader.read::<u32>(2)?; // Battery
-        let max_voltage = (bit_reader.read::<u32>(10)? / 50).into();
-        let min_voltage = (bit_reader.read::<u32>(10)? / 50).into();
-        let operational_power = (bit_reader.read::<u32>(10)? / 10).into();
+        let operational_power = Milliwatt(bit_reader.read::<u32>(10)? * 250);
+        let min_voltage = Millivolt(bit_reader.read::<u32>(10)? * 50);
+        let max_voltage = Millivolt(bit_reader.read::<u32>(10)? * 50);
+        bit_reader.skip(2)?; // Battery
 
         Ok(Self {
             max_voltage,
@@ -431,10 +1402,10 @@ impl FromBytes for VariableSupplyPdo {
     where
         Self: Sized,
     {
-        let _ = reader.read::<u32>(2)?; // Variable supply
-        let max_voltage = (reader.read::<u32>(10)? / 50).into();
-        let min_voltage = (reader.read::<u32>(10)? / 50).into();
-        let max_current = (reader.read::<u32>(10)? / 10).into();
+        let max_current = Milliamp(reader.read::<u32>(10)? * 10);
+        let min_voltage = Millivolt(reader.read::<u32>(10)? * 50);
+        let max_voltage = Millivolt(reader.read::<u32>(10)? * 50);
+        reader.skip(2)?; // Variable supply
 
         Ok(Self {
             max_voltage,
@@ -457,15 +1428,15 @@ impl FromBytes for SprProgrammableSupplyPdo {
     where
         Self: Sized,
     {
-        let _ = reader.read::<u32>(2)?; // APDO.
-        let _ = reader.read::<u32>(2)?; // Programmable power supply
-        let _ = reader.read_bit()?; // PPS power limited
-        let _reserved1 = reader.read::<u32>(2)?;
-        let max_voltage = (reader.read::<u32>(8)? / 50).into();
-        let _reserved2 = reader.read_bit()?;
-        let min_voltage = (reader.read::<u32>(8)? / 50).into();
-        let _reserved3 = reader.read_bit()?;
-        let max_current = (reader.read::<u32>(7)? / 10).into();
+        let max_current = Milliamp(reader.read::<u32>(7)? * 50);
+        reader.skip(1)?; // Reserved
+        let min_voltage = Millivolt(reader.read::<u32>(8)? * 100);
+        reader.skip(1)?; // Reserved
+        let max_voltage = Millivolt(reader.read::<u32>(8)? * 100);
+        reader.skip(2)?; // Reserved
+        reader.skip(1)?; // PPS power limited
+        reader.skip(2)?; // Programmable power supply
+        reader.skip(2)?; // APDO
 
         Ok(Self {
             max_voltage,
@@ -474,3 +1445,110 @@ impl FromBytes for SprProgrammableSupplyPdo {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pd::pd3p2::vdo::DfpHostCapability;
+    use crate::pd::pd3p2::vdo::UfpAlternateModes;
+    use crate::pd::pd3p2::vdo::UfpDeviceCapability;
+    use crate::pd::pd3p2::vdo::UsbHighestSpeed;
+
+    #[test]
+    fn test_enter_usb_data_object_round_trip() {
+        // USB4, USB3 and USB4 DRD, Gen3 active retimer cable rated for 5A,
+        // PCIe, DP and TBT3 tunneling with a host present.
+        let raw = 0x266fe000u32;
+        let bytes = raw.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let eudo = EnterUsbDataObject::from_bytes(&mut reader).unwrap();
+
+        assert_eq!(
+            eudo,
+            EnterUsbDataObject {
+                usb_mode: EnterUsbMode::Usb4,
+                usb4_drd: true,
+                usb3_drd: true,
+                cable_speed: UsbHighestSpeed::Usb4Gen3,
+                cable_type: EnterUsbCableType::ActiveRetimer,
+                cable_current: EnterUsbCableCurrent::A5,
+                pcie_supported: true,
+                dp_supported: true,
+                tbt_supported: true,
+                host_present: true,
+            }
+        );
+
+        let mut buf = [0; 4];
+        let mut bw = BitWriter::new(Cursor::new(&mut buf[..]));
+        eudo.to_bytes(&mut bw).unwrap();
+        assert_eq!(u32::from_le_bytes(buf), raw);
+    }
+
+    #[test]
+    fn test_decode_product_type_vdos_drd() {
+        let id_header = IdHeader {
+            sop_product_type_ufp: SopUfpProductType::PdUsbPeripheral,
+            sop_product_type_dfp: SopDfpProductType::PdUsbHost,
+            ..Default::default()
+        };
+
+        // The DFP VDO comes last, after a padding VDO.
+        let vdos = DiscoverIdentityResponse::decode_product_type_vdos(
+            &id_header,
+            MessageRecipient::Sop,
+            &[0x65000012, 0, 0x43000001],
+        )
+        .unwrap();
+
+        let Some(Vdo::Pd3p2Ufp(ufp)) = &vdos[0] else {
+            panic!("expected a UFP VDO, got {:?}", vdos[0]);
+        };
+        assert_eq!(
+            ufp.device_capability,
+            UfpDeviceCapability::USB2_0 | UfpDeviceCapability::USB3_2
+        );
+        assert_eq!(ufp.alternate_modes, UfpAlternateModes::RECONFIGURABLE);
+        assert_eq!(ufp.usb_highest_speed, UsbHighestSpeed::Usb4Gen2);
+
+        assert_eq!(vdos[1], None);
+
+        let Some(Vdo::Pd3p2Dfp(dfp)) = &vdos[2] else {
+            panic!("expected a DFP VDO, got {:?}", vdos[2]);
+        };
+        assert_eq!(
+            dfp.host_capability,
+            DfpHostCapability::USB2_0 | DfpHostCapability::USB3_2
+        );
+        assert_eq!(dfp.port_number, 1);
+    }
+
+    #[test]
+    fn test_discover_identity_nak() {
+        // A structured VDM version 2.0 Discover Identity NAK.
+        let response =
+            DiscoverIdentityResponse::from_message(&[0xff00a081], MessageRecipient::SopPrime)
+                .unwrap();
+
+        assert_eq!(response.header.command_type, CommandType::Nak);
+        assert_eq!(response.id_header_vdo, IdHeader::default());
+    }
+
+    #[test]
+    fn test_discover_svids_terminator() {
+        let svids = (1..=13).collect::<Vec<u32>>();
+        let responses = DiscoverSvidsResponse::from_svids(&svids);
+
+        assert_eq!(responses.len(), 2);
+        assert!(!responses[0].terminated);
+        assert_eq!(responses[0].svids().len(), MAX_SVIDS_PER_RESPONSE);
+        assert!(responses[1].terminated);
+        assert_eq!(DiscoverSvidsResponse::collect(&responses), svids);
+
+        // DisplayPort and Thunderbolt followed by a zero terminator.
+        let response =
+            DiscoverSvidsResponse::from_message(&[0xff00a042, 0xff018087, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(response.svids(), [0xff01, 0x8087]);
+        assert!(response.terminated);
+    }
+}
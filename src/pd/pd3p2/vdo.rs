@@ -12,6 +12,9 @@ use bitstream_io::BitRead;
 use enumn::N;
 use proc_macros::CApiWrapper;
 
+use crate::bitflags_wrapper;
+use crate::pd::MessageRecipient;
+use crate::vendor::VendorResolver;
 use crate::BcdWrapper;
 use crate::BitReader;
 use crate::Error;
@@ -19,7 +22,7 @@ use crate::FromBytes;
 use crate::Milliohm;
 use crate::Result;
 
-/// Maximum VPD VBUS Voltage
+/// Maximum VBUS Voltage, as reported by VPDs and cables.
 #[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
 #[c_api(prefix = "Pd3p2Vdo", repr_c)]
 pub enum MaxVbusVoltage {
@@ -69,20 +72,6 @@ pub struct Vpd {
 
 impl FromBytes for Vpd {
     fn from_bytes(bit_reader: &mut BitReader) -> Result<Self> {
-        let hw_version = bit_reader.read(4)?;
-        let firmware_version = bit_reader.read(4)?;
-        let vdo_version = bit_reader.read(3)?;
-        let max_vbus_voltage = bit_reader.read(2)?;
-        let max_vbus_voltage =
-            MaxVbusVoltage::n(max_vbus_voltage).ok_or_else(|| Error::ParseError {
-                field: "max_vbus_voltage".into(),
-                value: max_vbus_voltage,
-                #[cfg(feature = "backtrace")]
-                backtrace: Backtrace::capture(),
-            })?;
-        let charge_through_current_support = bit_reader.read_bit()?;
-        let vbus_impedance = bit_reader.read::<u32>(6)?.into();
-        let ground_impedance = bit_reader.read::<u32>(6)?.into();
         let charge_through_support = bit_reader.read_bit()?;
         let charge_through_support =
             ChargeThroughSupport::n(charge_through_support).ok_or_else(|| Error::ParseError {
@@ -91,6 +80,23 @@ impl FromBytes for Vpd {
                 #[cfg(feature = "backtrace")]
                 backtrace: std::backtrace::Backtrace::capture(),
             })?;
+        // Ground impedance is in 1mΩ increments, VBUS impedance in 2mΩ ones.
+        let ground_impedance = bit_reader.read::<u32>(6)?.into();
+        let vbus_impedance = (bit_reader.read::<u32>(6)? * 2).into();
+        bit_reader.skip(1)?; // Reserved
+        let charge_through_current_support = bit_reader.read_bit()?;
+        let max_vbus_voltage = bit_reader.read(2)?;
+        let max_vbus_voltage =
+            MaxVbusVoltage::n(max_vbus_voltage).ok_or_else(|| Error::ParseError {
+                field: "max_vbus_voltage".into(),
+                value: max_vbus_voltage,
+                #[cfg(feature = "backtrace")]
+                backtrace: Backtrace::capture(),
+            })?;
+        bit_reader.skip(4)?; // Reserved
+        let vdo_version = bit_reader.read(3)?;
+        let firmware_version = bit_reader.read(4)?;
+        let hw_version = bit_reader.read(4)?;
 
         Ok(Self {
             hw_version,
@@ -105,27 +111,414 @@ impl FromBytes for Vpd {
     }
 }
 
+/// USB Type-C plug to USB Type-C/Captive
 #[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
 #[c_api(prefix = "Pd3p2Vdo", repr_c)]
-pub enum UfpVersion {
-    /// Version 1.3 = 011b
+pub enum CablePlugType {
+    /// USB Type-A (Deprecated)
+    UsbTypeA = 0,
+    /// USB Type-B (Deprecated)
+    UsbTypeB,
     #[default]
-    Unknown = 0,
-    V1_3 = 3,
+    /// USB Type-C
+    UsbTypeC,
+    /// Captive
+    Captive,
 }
 
+/// Cable Latency, i.e. the signal propagation delay through the cable.
 #[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
 #[c_api(prefix = "Pd3p2Vdo", repr_c)]
-pub enum UfpDeviceCapability {
+pub enum CableLatency {
     #[default]
-    /// [USB 2.0] Device Capable
+    /// <10ns (~1m)
+    Ns10 = 1,
+    /// 10ns to 20ns (~2m)
+    Ns20,
+    /// 20ns to 30ns (~3m)
+    Ns30,
+    /// 30ns to 40ns (~4m)
+    Ns40,
+    /// 40ns to 50ns (~5m)
+    Ns50,
+    /// 50ns to 60ns (~6m)
+    Ns60,
+    /// 60ns to 70ns (~7m)
+    Ns70,
+    /// >70ns (>~7m) for passive cables, 1000ns (~100m) for active cables
+    Ns1000,
+    /// 2000ns (~200m), active cables only
+    Ns2000,
+    /// 3000ns (~300m), active cables only
+    Ns3000,
+}
+
+/// Cable Termination Type
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2Vdo", repr_c)]
+pub enum CableTermination {
+    #[default]
+    /// VCONN not required (passive cables)
+    VconnNotRequired = 0,
+    /// VCONN required (passive cables)
+    VconnRequired,
+    /// One end active, one end passive, VCONN required (active cables)
+    OneEndActive,
+    /// Both ends active, VCONN required (active cables)
+    BothEndsActive,
+}
+
+/// VBUS Current Handling Capability
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2Vdo", repr_c)]
+pub enum CableVbusCurrent {
+    #[default]
+    Reserved = 0,
+    /// 3A
+    A3,
+    /// 5A
+    A5,
+    Reserved2,
+}
+
+/// USB Highest Speed, as reported by UFPs and cables.
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2Vdo", repr_c)]
+pub enum UsbHighestSpeed {
+    #[default]
+    /// [USB 2.0] only, no SuperSpeed support
     Usb2_0 = 0,
-    /// [USB 2.0] Device Capable (Billboard only)
-    Usb2_0Billboard,
-    /// [USB 3.2] Device Capable
-    Usb3_2,
-    /// [USB4] Device Capable
-    Usb4,
+    /// [USB 3.2] Gen1
+    Usb3_2Gen1,
+    /// [USB 3.2]/[USB4] Gen2
+    Usb4Gen2,
+    /// [USB4] Gen3
+    Usb4Gen3,
+    /// [USB4] Gen4
+    Usb4Gen4,
+}
+
+/// Passive Cable VDO. See USB PD 3.2 - 6.4.4.3.1.6 Passive Cable VDO
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2Vdo", repr_c)]
+pub struct PassiveCable {
+    /// HW Version 0000b…1111b assigned by the VID owner
+    pub hw_version: u8,
+    /// Firmware Version 0000b…1111b assigned by the VID owner
+    pub firmware_version: u8,
+    /// Version Number of the VDO (not this specification Version)
+    pub vdo_version: u8,
+    /// Whether the cable ends in a USB Type-C plug or is captive.
+    pub plug_type: CablePlugType,
+    /// Whether the cable supports voltages in the EPR range.
+    pub epr_capable: bool,
+    /// Cable Latency
+    pub latency: CableLatency,
+    /// Cable Termination Type
+    pub termination: CableTermination,
+    /// Maximum VBUS Voltage
+    pub max_vbus_voltage: MaxVbusVoltage,
+    /// VBUS Current Handling Capability
+    pub vbus_current: CableVbusCurrent,
+    /// USB Highest Speed
+    pub usb_highest_speed: UsbHighestSpeed,
+}
+
+/// The fields shared by the Passive Cable VDO and the Active Cable VDO1, from
+/// bit 9 (Maximum VBUS Voltage) to bit 31.
+struct CableVdoCommon {
+    hw_version: u8,
+    firmware_version: u8,
+    vdo_version: u8,
+    plug_type: CablePlugType,
+    epr_capable: bool,
+    latency: CableLatency,
+    termination: CableTermination,
+    max_vbus_voltage: MaxVbusVoltage,
+}
+
+impl FromBytes for CableVdoCommon {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let max_vbus_voltage = reader.read(2)?;
+        let max_vbus_voltage =
+            MaxVbusVoltage::n(max_vbus_voltage).ok_or_else(|| Error::ParseError {
+                field: "max_vbus_voltage".into(),
+                value: max_vbus_voltage,
+                #[cfg(feature = "backtrace")]
+                backtrace: Backtrace::capture(),
+            })?;
+        let termination = reader.read(2)?;
+        let termination = CableTermination::n(termination).ok_or_else(|| Error::ParseError {
+            field: "termination".into(),
+            value: termination,
+            #[cfg(feature = "backtrace")]
+            backtrace: Backtrace::capture(),
+        })?;
+        let latency = reader.read(4)?;
+        let latency = CableLatency::n(latency).ok_or_else(|| Error::ParseError {
+            field: "latency".into(),
+            value: latency,
+            #[cfg(feature = "backtrace")]
+            backtrace: Backtrace::capture(),
+        })?;
+        let epr_capable = reader.read_bit()?;
+        let plug_type = reader.read(2)?;
+        let plug_type = CablePlugType::n(plug_type).ok_or_else(|| Error::ParseError {
+            field: "plug_type".into(),
+            value: plug_type,
+            #[cfg(feature = "backtrace")]
+            backtrace: Backtrace::capture(),
+        })?;
+        reader.skip(1)?; // Reserved
+        let vdo_version = reader.read(3)?;
+        let firmware_version = reader.read(4)?;
+        let hw_version = reader.read(4)?;
+
+        Ok(Self {
+            hw_version,
+            firmware_version,
+            vdo_version,
+            plug_type,
+            epr_capable,
+            latency,
+            termination,
+            max_vbus_voltage,
+        })
+    }
+}
+
+fn read_cable_vbus_current(reader: &mut BitReader) -> Result<CableVbusCurrent> {
+    let vbus_current = reader.read(2)?;
+    CableVbusCurrent::n(vbus_current).ok_or_else(|| Error::ParseError {
+        field: "vbus_current".into(),
+        value: vbus_current,
+        #[cfg(feature = "backtrace")]
+        backtrace: Backtrace::capture(),
+    })
+}
+
+fn read_usb_highest_speed(reader: &mut BitReader) -> Result<UsbHighestSpeed> {
+    let usb_highest_speed = reader.read(3)?;
+    UsbHighestSpeed::n(usb_highest_speed).ok_or_else(|| Error::ParseError {
+        field: "usb_highest_speed".into(),
+        value: usb_highest_speed,
+        #[cfg(feature = "backtrace")]
+        backtrace: Backtrace::capture(),
+    })
+}
+
+impl FromBytes for PassiveCable {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let usb_highest_speed = read_usb_highest_speed(reader)?;
+        reader.skip(2)?; // Reserved
+        let vbus_current = read_cable_vbus_current(reader)?;
+        reader.skip(2)?; // Reserved
+        let common = CableVdoCommon::from_bytes(reader)?;
+
+        Ok(Self {
+            hw_version: common.hw_version,
+            firmware_version: common.firmware_version,
+            vdo_version: common.vdo_version,
+            plug_type: common.plug_type,
+            epr_capable: common.epr_capable,
+            latency: common.latency,
+            termination: common.termination,
+            max_vbus_voltage: common.max_vbus_voltage,
+            vbus_current,
+            usb_highest_speed,
+        })
+    }
+}
+
+/// Active Cable VDO1. See USB PD 3.2 - 6.4.4.3.1.7 Active Cable VDOs
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2Vdo", repr_c)]
+pub struct ActiveCable1 {
+    /// HW Version 0000b…1111b assigned by the VID owner
+    pub hw_version: u8,
+    /// Firmware Version 0000b…1111b assigned by the VID owner
+    pub firmware_version: u8,
+    /// Version Number of the VDO (not this specification Version)
+    pub vdo_version: u8,
+    /// Whether the cable ends in a USB Type-C plug or is captive.
+    pub plug_type: CablePlugType,
+    /// Whether the cable supports voltages in the EPR range.
+    pub epr_capable: bool,
+    /// Cable Latency
+    pub latency: CableLatency,
+    /// Cable Termination Type
+    pub termination: CableTermination,
+    /// Maximum VBUS Voltage
+    pub max_vbus_voltage: MaxVbusVoltage,
+    /// Whether the SBUs are supported by the cable.
+    pub sbu_supported: bool,
+    /// Whether the SBUs are active, as opposed to passive.
+    pub sbu_active: bool,
+    /// VBUS Current Handling Capability. Only valid if `vbus_through_cable`
+    /// is set.
+    pub vbus_current: CableVbusCurrent,
+    /// Whether the cable carries VBUS from end to end.
+    pub vbus_through_cable: bool,
+    /// Whether there is a SOP'' controller in the cable.
+    pub sop_double_prime_controller_present: bool,
+    /// USB Highest Speed
+    pub usb_highest_speed: UsbHighestSpeed,
+}
+
+impl FromBytes for ActiveCable1 {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let usb_highest_speed = read_usb_highest_speed(reader)?;
+        let sop_double_prime_controller_present = reader.read_bit()?;
+        let vbus_through_cable = reader.read_bit()?;
+        let vbus_current = read_cable_vbus_current(reader)?;
+        let sbu_active = reader.read_bit()?;
+        // 0b means SBUs supported.
+        let sbu_supported = !reader.read_bit()?;
+        let common = CableVdoCommon::from_bytes(reader)?;
+
+        Ok(Self {
+            hw_version: common.hw_version,
+            firmware_version: common.firmware_version,
+            vdo_version: common.vdo_version,
+            plug_type: common.plug_type,
+            epr_capable: common.epr_capable,
+            latency: common.latency,
+            termination: common.termination,
+            max_vbus_voltage: common.max_vbus_voltage,
+            sbu_supported,
+            sbu_active,
+            vbus_current,
+            vbus_through_cable,
+            sop_double_prime_controller_present,
+            usb_highest_speed,
+        })
+    }
+}
+
+/// U3/CLd Power, i.e. the power drawn by the cable while idle.
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2Vdo", repr_c)]
+pub enum ActiveCableIdlePower {
+    #[default]
+    /// >10mW
+    Mw10Plus = 0,
+    /// 5-10mW
+    Mw5To10,
+    /// 1-5mW
+    Mw1To5,
+    /// 0.5-1mW
+    Mw0_5To1,
+    /// 0.2-0.5mW
+    Mw0_2To0_5,
+    /// 50-200µW
+    Uw50To200,
+    /// <50µW
+    Uw50Minus,
+}
+
+/// Active Cable VDO2. See USB PD 3.2 - 6.4.4.3.1.7 Active Cable VDOs
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Pd3p2Vdo", repr_c)]
+pub struct ActiveCable2 {
+    /// The maximum internal operating temperature in °C.
+    pub max_operating_temperature: u8,
+    /// The temperature in °C at which the cable shuts down.
+    pub shutdown_temperature: u8,
+    /// U3/CLd Power
+    pub idle_power: ActiveCableIdlePower,
+    /// Whether U3 to U0 transitions go through U3S, as opposed to directly.
+    pub u3_to_u0_through_u3s: bool,
+    /// Whether the physical connection is optical, as opposed to copper.
+    pub optical: bool,
+    /// Whether the active element is a retimer, as opposed to a redriver.
+    pub retimer: bool,
+    /// Whether [USB4] is supported.
+    pub usb4_supported: bool,
+    /// The number of [USB 2.0] hub hops consumed by the cable.
+    pub usb2_hub_hops_consumed: u8,
+    /// Whether [USB 2.0] is supported.
+    pub usb2_supported: bool,
+    /// Whether [USB 3.2] is supported.
+    pub usb3_2_supported: bool,
+    /// Whether two USB lanes are supported, as opposed to one.
+    pub two_lanes: bool,
+    /// Whether this is an optically isolated active cable.
+    pub optically_isolated: bool,
+    /// Whether the [USB4] asymmetric mode is supported.
+    pub usb4_asymmetric_mode_supported: bool,
+    /// Whether the cable supports USB Gen2 or higher, as opposed to Gen1.
+    pub usb_gen2_or_higher: bool,
+}
+
+impl FromBytes for ActiveCable2 {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let usb_gen2_or_higher = reader.read_bit()?;
+        let usb4_asymmetric_mode_supported = reader.read_bit()?;
+        let optically_isolated = reader.read_bit()?;
+        let two_lanes = reader.read_bit()?;
+        // The "supported" bits are active low.
+        let usb3_2_supported = !reader.read_bit()?;
+        let usb2_supported = !reader.read_bit()?;
+        let usb2_hub_hops_consumed = reader.read(2)?;
+        let usb4_supported = !reader.read_bit()?;
+        let retimer = reader.read_bit()?;
+        let optical = reader.read_bit()?;
+        let u3_to_u0_through_u3s = reader.read_bit()?;
+        let idle_power = reader.read(3)?;
+        let idle_power = ActiveCableIdlePower::n(idle_power).ok_or_else(|| Error::ParseError {
+            field: "idle_power".into(),
+            value: idle_power,
+            #[cfg(feature = "backtrace")]
+            backtrace: Backtrace::capture(),
+        })?;
+        reader.skip(1)?; // Reserved
+        let shutdown_temperature = reader.read(8)?;
+        let max_operating_temperature = reader.read(8)?;
+
+        Ok(Self {
+            max_operating_temperature,
+            shutdown_temperature,
+            idle_power,
+            u3_to_u0_through_u3s,
+            optical,
+            retimer,
+            usb4_supported,
+            usb2_hub_hops_consumed,
+            usb2_supported,
+            usb3_2_supported,
+            two_lanes,
+            optically_isolated,
+            usb4_asymmetric_mode_supported,
+            usb_gen2_or_higher,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Pd3p2Vdo", repr_c)]
+pub enum UfpVersion {
+    #[default]
+    Unknown = 0,
+    /// Version 1.2 = 010b
+    V1_2 = 2,
+    /// Version 1.3 = 011b
+    V1_3 = 3,
+}
+
+bitflags_wrapper! {
+    Pd3p2Vdo,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// The USB device capabilities of a UFP.
+    pub struct UfpDeviceCapability: u32 {
+        /// [USB 2.0] Device Capable
+        const USB2_0 = 0b0001;
+        /// [USB 2.0] Device Capable (Billboard only)
+        const USB2_0_BILLBOARD = 0b0010;
+        /// [USB 3.2] Device Capable
+        const USB3_2 = 0b0100;
+        /// [USB4] Device Capable
+        const USB4 = 0b1000;
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
@@ -148,17 +541,20 @@ pub enum UfpVConnPower {
     W6,
 }
 
-#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
-#[c_api(prefix = "Pd3p2Vdo", repr_c)]
-pub enum UfpAlternateModes {
-    #[default]
-    /// Supports [TBT3] Alternate Mode
-    Tbt3 = 0,
-    /// Supports Alternate Modes that reconfigure the signals on the [USB Type-C 2.3] connector – except for [TBT3].
-    Reconfigurable,
-    /// Supports Alternate Modes that do not reconfigure the signals on the [USB Type-C 2.3] connector
-    NonReconfigurable,
+bitflags_wrapper! {
+    Pd3p2Vdo,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// The kinds of Alternate Modes supported by a UFP.
+    pub struct UfpAlternateModes: u32 {
+        /// Supports [TBT3] Alternate Mode
+        const TBT3 = 0b001;
+        /// Supports Alternate Modes that reconfigure the signals on the [USB Type-C 2.3] connector – except for [TBT3].
+        const RECONFIGURABLE = 0b010;
+        /// Supports Alternate Modes that do not reconfigure the signals on the [USB Type-C 2.3] connector
+        const NON_RECONFIGURABLE = 0b100;
+    }
 }
+
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
 #[c_api(prefix = "Pd3p2Vdo", repr_c)]
 pub struct Ufp {
@@ -174,28 +570,16 @@ pub struct Ufp {
     pub vbus_required: bool,
     /// Alternate Modes
     pub alternate_modes: UfpAlternateModes,
+    /// USB Highest Speed
+    pub usb_highest_speed: UsbHighestSpeed,
 }
 
 impl FromBytes for Ufp {
     fn from_bytes(bit_reader: &mut BitReader) -> Result<Self> {
-        let ufp_vdo_version = bit_reader.read(3)?;
-        let ufp_vdo_version = UfpVersion::n(ufp_vdo_version).ok_or_else(|| Error::ParseError {
-            field: "ufp_vdo_version".into(),
-            value: ufp_vdo_version,
-            #[cfg(feature = "backtrace")]
-            backtrace: std::backtrace::Backtrace::capture(),
-        })?;
-        bit_reader.skip(1)?; // Skip reserved bit
-        let device_capability = bit_reader.read(4)?;
-        let device_capability =
-            UfpDeviceCapability::n(device_capability).ok_or_else(|| Error::ParseError {
-                field: "device_capability".into(),
-                value: device_capability,
-                #[cfg(feature = "backtrace")]
-                backtrace: std::backtrace::Backtrace::capture(),
-            })?;
-        bit_reader.skip(2)?; // Skip Connector Type (Legacy)
-        bit_reader.skip(11)?; // Skip reserved bits
+        let usb_highest_speed = read_usb_highest_speed(bit_reader)?;
+        let alternate_modes = UfpAlternateModes::from_bits_truncate(bit_reader.read(3)?);
+        let vbus_required = bit_reader.read_bit()?;
+        let vconn_required = bit_reader.read_bit()?;
         let vconn_power = bit_reader.read(3)?;
         let vconn_power = UfpVConnPower::n(vconn_power).ok_or_else(|| Error::ParseError {
             field: "vconn_power".into(),
@@ -203,16 +587,17 @@ impl FromBytes for Ufp {
             #[cfg(feature = "backtrace")]
             backtrace: std::backtrace::Backtrace::capture(),
         })?;
-        let vconn_required = bit_reader.read_bit()?;
-        let vbus_required = bit_reader.read_bit()?;
-        let alternate_modes = bit_reader.read(3)?;
-        let alternate_modes =
-            UfpAlternateModes::n(alternate_modes).ok_or_else(|| Error::ParseError {
-                field: "alternate_modes".into(),
-                value: alternate_modes,
-                #[cfg(feature = "backtrace")]
-                backtrace: std::backtrace::Backtrace::capture(),
-            })?;
+        bit_reader.skip(11)?; // Skip reserved bits
+        bit_reader.skip(2)?; // Skip Connector Type (Legacy)
+        let device_capability = UfpDeviceCapability::from_bits_truncate(bit_reader.read(4)?);
+        bit_reader.skip(1)?; // Skip reserved bit
+        let ufp_vdo_version = bit_reader.read(3)?;
+        let ufp_vdo_version = UfpVersion::n(ufp_vdo_version).ok_or_else(|| Error::ParseError {
+            field: "ufp_vdo_version".into(),
+            value: ufp_vdo_version,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
 
         Ok(Self {
             ufp_vdo_version,
@@ -221,6 +606,7 @@ impl FromBytes for Ufp {
             vconn_required,
             vbus_required,
             alternate_modes,
+            usb_highest_speed,
         })
     }
 }
@@ -228,22 +614,26 @@ impl FromBytes for Ufp {
 #[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
 #[c_api(prefix = "Pd3p2Vdo", repr_c)]
 pub enum DfpVersion {
+    /// Version 1.1 = 001b
+    Version11 = 0b001,
     #[default]
     /// Version 1.2 = 010b
     Version12 = 0b010,
     // Values 011b…111b are Reserved and Shall Not be used
 }
 
-#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
-#[c_api(prefix = "Pd3p2Vdo", repr_c)]
-pub enum DfpHostCapability {
-    #[default]
-    /// [USB 2] Host Capable
-    Usb20 = 0,
-    /// [USB 3] Host Capable
-    Usb32 = 1,
-    /// [USB 4] Host Capable
-    Usb4 = 2,
+bitflags_wrapper! {
+    Pd3p2Vdo,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// The USB host capabilities of a DFP.
+    pub struct DfpHostCapability: u32 {
+        /// [USB 2] Host Capable
+        const USB2_0 = 0b001;
+        /// [USB 3] Host Capable
+        const USB3_2 = 0b010;
+        /// [USB 4] Host Capable
+        const USB4 = 0b100;
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
@@ -260,6 +650,15 @@ pub struct Dfp {
 
 impl FromBytes for Dfp {
     fn from_bytes(bit_reader: &mut BitReader) -> Result<Self> {
+        let port_number = bit_reader.read(5)?;
+
+        bit_reader.skip(17)?; // Reserved
+        bit_reader.skip(2)?; // Connector Type (Legacy)
+
+        let host_capability = DfpHostCapability::from_bits_truncate(bit_reader.read(3)?);
+
+        bit_reader.skip(2)?; // Reserved
+
         let dfp_vdo_version = bit_reader.read(3)?;
         let dfp_vdo_version = DfpVersion::n(dfp_vdo_version).ok_or_else(|| Error::ParseError {
             field: "dfp_vdo_version".into(),
@@ -268,21 +667,6 @@ impl FromBytes for Dfp {
             backtrace: std::backtrace::Backtrace::capture(),
         })?;
 
-        bit_reader.skip(2)?;
-
-        let host_capability = bit_reader.read(3)?;
-        let host_capability =
-            DfpHostCapability::n(host_capability).ok_or_else(|| Error::ParseError {
-                field: "host_capability".into(),
-                value: host_capability,
-                #[cfg(feature = "backtrace")]
-                backtrace: std::backtrace::Backtrace::capture(),
-            })?;
-
-        bit_reader.skip(2)?;
-
-        let port_number = bit_reader.read(5)?;
-
         Ok(Dfp {
             dfp_vdo_version,
             host_capability,
@@ -308,8 +692,8 @@ pub struct Product {
 
 impl FromBytes for Product {
     fn from_bytes(reader: &mut BitReader) -> Result<Self> {
-        let product_id = reader.read(16)?;
         let device = reader.read(16)?;
+        let product_id = reader.read(16)?;
 
         Ok(Product {
             product_id,
@@ -349,8 +733,10 @@ pub enum SopDfpProductType {
     PowerBrick,
 }
 
-#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[derive(Debug, Clone, PartialEq, Default, Copy, CApiWrapper)]
 #[c_api(prefix = "Pd3p2Vdo", repr_c)]
+/// See USBPDB 6.4.4.3.1.1. The first four variants are reported by SOP
+/// recipients, the remaining ones by cable plugs (SOP'/SOP'').
 pub enum SopUfpProductType {
     #[default]
     NotAUfp,
@@ -363,6 +749,29 @@ pub enum SopUfpProductType {
     VConnPoweredUsbDevice,
 }
 
+impl SopUfpProductType {
+    /// Decodes the raw field, whose meaning depends on whether the ID Header
+    /// was sent by the port partner or by a cable plug.
+    pub fn from_raw(value: u32, recipient: MessageRecipient) -> Option<Self> {
+        match recipient {
+            MessageRecipient::SopPrime | MessageRecipient::SopDoublePrime => match value {
+                0 => Some(Self::NotACablePlugOrVPD),
+                3 => Some(Self::PassiveCable),
+                4 => Some(Self::ActiveCable),
+                6 => Some(Self::VConnPoweredUsbDevice),
+                _ => None,
+            },
+            _ => match value {
+                0 => Some(Self::NotAUfp),
+                1 => Some(Self::PdUsbHub),
+                2 => Some(Self::PdUsbPeripheral),
+                3 => Some(Self::Psd),
+                _ => None,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
 #[c_api(prefix = "Pd3p2Vdo", repr_c)]
 pub enum IdHeaderConnectorType {
@@ -376,6 +785,8 @@ pub enum IdHeaderConnectorType {
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
 #[c_api(prefix = "Pd3p2Vdo", repr_c, manual_from_impl)]
 pub struct IdHeader {
+    /// The vendor name. Empty until filled in by
+    /// [`IdHeader::resolve_vendor`].
     #[c_api(opaque)]
     pub vendor: String,
     /// USB Communications Capable as USB Host
@@ -437,21 +848,26 @@ impl From<IdHeader> for Pd3p2VdoIdHeader {
     }
 }
 
-impl FromBytes for IdHeader {
-    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
-        let usb_host_capability = reader.read_bit()?;
-        let usb_device_capability = reader.read_bit()?;
+impl IdHeader {
+    /// Parses an ID Header VDO sent by `recipient`. The UFP product type is
+    /// encoded differently by cable plugs, so the recipient must be known.
+    pub fn from_bytes_for_recipient(
+        reader: &mut BitReader,
+        recipient: MessageRecipient,
+    ) -> Result<Self> {
+        let usb_vendor_id = reader.read(16)?;
 
-        let sop_product_type_ufp = reader.read(3)?;
-        let sop_product_type_ufp =
-            SopUfpProductType::n(sop_product_type_ufp).ok_or_else(|| Error::ParseError {
-                field: "sop_product_type_ufp".into(),
-                value: sop_product_type_ufp,
+        reader.skip(5)?;
+
+        let connector_type = reader.read(2)?;
+        let connector_type =
+            IdHeaderConnectorType::n(connector_type).ok_or_else(|| Error::ParseError {
+                field: "connector_type".into(),
+                value: connector_type,
                 #[cfg(feature = "backtrace")]
                 backtrace: std::backtrace::Backtrace::capture(),
             })?;
 
-        let modal_operation_supported = reader.read_bit()?;
         let sop_product_type_dfp = reader.read(3)?;
         let sop_product_type_dfp =
             SopDfpProductType::n(sop_product_type_dfp).ok_or_else(|| Error::ParseError {
@@ -461,30 +877,22 @@ impl FromBytes for IdHeader {
                 backtrace: std::backtrace::Backtrace::capture(),
             })?;
 
-        let connector_type = reader.read(2)?;
-        let connector_type =
-            IdHeaderConnectorType::n(connector_type).ok_or_else(|| Error::ParseError {
-                field: "connector_type".into(),
-                value: connector_type,
+        let modal_operation_supported = reader.read_bit()?;
+
+        let sop_product_type_ufp = reader.read(3)?;
+        let sop_product_type_ufp = SopUfpProductType::from_raw(sop_product_type_ufp, recipient)
+            .ok_or_else(|| Error::ParseError {
+                field: "sop_product_type_ufp".into(),
+                value: sop_product_type_ufp,
                 #[cfg(feature = "backtrace")]
                 backtrace: std::backtrace::Backtrace::capture(),
             })?;
 
-        reader.skip(5)?;
-
-        let usb_vendor_id = reader.read(16)?;
-        let hwdb = udev::Hwdb::new()?;
-        let modalias = format!("usb:v{:04X}*", usb_vendor_id);
-
-        let vendor = hwdb
-            .query(modalias)
-            .next()
-            .map_or(std::ffi::OsString::from("Unknown"), |entry| {
-                entry.name().to_os_string()
-            });
+        let usb_device_capability = reader.read_bit()?;
+        let usb_host_capability = reader.read_bit()?;
 
         Ok(IdHeader {
-            vendor: vendor.into_string().unwrap_or("Invalid vendor name".into()),
+            vendor: String::new(),
             usb_host_capability,
             usb_device_capability,
             sop_product_type_ufp,
@@ -494,20 +902,63 @@ impl FromBytes for IdHeader {
             usb_vendor_id,
         })
     }
+
+    /// Fills in the vendor name from the USB Vendor ID using `resolver`.
+    /// Unknown vendors are reported as "Unknown".
+    pub fn resolve_vendor(&mut self, resolver: &dyn VendorResolver) {
+        self.vendor = resolver
+            .resolve(self.usb_vendor_id)
+            .unwrap_or_else(|| "Unknown".into());
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
-#[c_api(prefix = "Pd3p2Vdo", repr_c)]
-pub enum ProductType {
-    /// See USBPDB 6.4.4.3.1.6
-    #[default]
-    PassiveCableVdo,
-    /// See USBPDB 6.4.4.3.1.7
-    ActiveCableVdo,
-    /// See USBPDB 6.4.4.3.1.9
-    VpdVdo,
-    /// See USBPDB 6.4.4.3.1.4
-    UfpVdo,
-    /// See USBPDB 6.4.4.3.1.5
-    DfpVdo,
+impl FromBytes for IdHeader {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        Self::from_bytes_for_recipient(reader, MessageRecipient::Sop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_passive_cable_from_bytes() {
+        // A 50V/5A EPR capable USB Type-C to USB Type-C USB4 Gen2 cable.
+        let bytes = 0x100a2642u32.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let vdo = PassiveCable::from_bytes(&mut reader).unwrap();
+
+        assert_eq!(
+            vdo,
+            PassiveCable {
+                hw_version: 1,
+                firmware_version: 0,
+                vdo_version: 0,
+                plug_type: CablePlugType::UsbTypeC,
+                epr_capable: true,
+                latency: CableLatency::Ns10,
+                termination: CableTermination::VconnNotRequired,
+                max_vbus_voltage: MaxVbusVoltage::V50,
+                vbus_current: CableVbusCurrent::A5,
+                usb_highest_speed: UsbHighestSpeed::Usb4Gen2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_id_header_vendor_resolution() {
+        // A Google USB PD peripheral capable of alternate modes.
+        let bytes = 0x544018d1u32.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let mut id_header = IdHeader::from_bytes(&mut reader).unwrap();
+
+        assert_eq!(id_header.usb_vendor_id, 0x18d1);
+        assert!(id_header.vendor.is_empty());
+
+        id_header.resolve_vendor(&crate::vendor::NoopResolver);
+        assert_eq!(id_header.vendor, "Unknown");
+    }
 }
@@ -0,0 +1,438 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! Decoding of raw USB PD messages, e.g. as captured by a sniffer or logged
+//! by the Linux TCPM.
+//!
+//! See USB PD 3.2 - 6.2 Messages.
+
+use std::io::Cursor;
+
+use bitstream_io::BitRead;
+
+use crate::pd::pd3p2::AlertDataObject;
+use crate::pd::pd3p2::BatteryStatusData;
+use crate::pd::pd3p2::BistDataObject;
+use crate::pd::pd3p2::Capabilities;
+use crate::pd::pd3p2::ControlMessageType;
+use crate::pd::pd3p2::DataMessageType;
+use crate::pd::pd3p2::DiscoverIdentityResponse;
+use crate::pd::pd3p2::DiscoverModesResponse;
+use crate::pd::pd3p2::DiscoverSvidsResponse;
+use crate::pd::pd3p2::EnterUsbDataObject;
+use crate::pd::pd3p2::EprRequest;
+use crate::pd::pd3p2::ExtendedMessageType;
+use crate::pd::pd3p2::RequestDataObject;
+use crate::pd::pd3p2::RevisionMessageData;
+use crate::pd::pd3p2::SinkCapabilitiesExtended;
+use crate::pd::pd3p2::SourceCapabilitiesExtended;
+use crate::pd::pd3p2::VendorDefinedMessage;
+use crate::pd::Command;
+use crate::pd::Message;
+use crate::pd::MessageRecipient;
+use crate::pd::VdmHeader;
+use crate::BitReader;
+use crate::Error;
+use crate::FromBytes;
+use crate::Result;
+
+/// The Message Header. See USB PD 3.2 - 6.2.1.1 Message Header.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MessageHeader {
+    /// Whether this is an Extended Message.
+    pub extended: bool,
+    /// The number of 32-bit data objects following the header.
+    pub num_data_objects: u8,
+    /// The rolling Message ID.
+    pub message_id: u8,
+    /// The Port Power Role for SOP messages, the Cable Plug bit for SOP'
+    /// and SOP'' messages.
+    pub power_role_or_cable_plug: bool,
+    /// The Specification Revision: 0 for 1.0, 1 for 2.0 and 2 for 3.x.
+    pub spec_revision: u8,
+    /// The Port Data Role for SOP messages, reserved otherwise.
+    pub data_role: bool,
+    /// The raw Message Type, to be interpreted according to `extended` and
+    /// `num_data_objects`.
+    pub message_type: u8,
+}
+
+impl FromBytes for MessageHeader {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let message_type = reader.read(5)?;
+        let data_role = reader.read_bit()?;
+        let spec_revision = reader.read(2)?;
+        let power_role_or_cable_plug = reader.read_bit()?;
+        let message_id = reader.read(3)?;
+        let num_data_objects = reader.read(3)?;
+        let extended = reader.read_bit()?;
+
+        Ok(Self {
+            extended,
+            num_data_objects,
+            message_id,
+            power_role_or_cable_plug,
+            spec_revision,
+            data_role,
+            message_type,
+        })
+    }
+}
+
+/// The Extended Message Header. See USB PD 3.2 - 6.2.1.2 Extended Message
+/// Header.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExtendedHeader {
+    /// Whether the message is sent in chunks.
+    pub chunked: bool,
+    /// The number of this chunk, starting at 0.
+    pub chunk_number: u8,
+    /// Whether this is a request for the chunk numbered `chunk_number`.
+    pub request_chunk: bool,
+    /// The total number of data bytes in the message.
+    pub data_size: u16,
+}
+
+impl FromBytes for ExtendedHeader {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let data_size = reader.read(9)?;
+        reader.skip(1)?; // Reserved
+        let request_chunk = reader.read_bit()?;
+        let chunk_number = reader.read(4)?;
+        let chunked = reader.read_bit()?;
+
+        Ok(Self {
+            chunked,
+            chunk_number,
+            request_chunk,
+            data_size,
+        })
+    }
+}
+
+/// The decoded Message Type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageType {
+    Control(ControlMessageType),
+    Data(DataMessageType),
+    Extended(ExtendedMessageType),
+}
+
+/// A raw USB PD message: the Message Header, followed by the data objects or,
+/// for Extended Messages, by the Extended Message Header and the data block.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RawMessage {
+    /// The Message Header.
+    pub header: MessageHeader,
+    /// The Extended Message Header, for Extended Messages only.
+    pub extended_header: Option<ExtendedHeader>,
+    /// The data objects or, for Extended Messages, the data block received
+    /// so far.
+    pub payload: Vec<u8>,
+}
+
+impl RawMessage {
+    /// The maximum size of the data block in a single chunk.
+    pub const MAX_CHUNK_LEN: usize = 26;
+
+    /// Parses a single message, in the order it is sent on the wire, i.e.
+    /// little-endian. For chunked Extended Messages this is a single chunk,
+    /// see [`RawMessage::from_chunks`].
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let header = read_le::<MessageHeader>(bytes, 0, 2)?;
+        let data_len = 4 * usize::from(header.num_data_objects);
+        let data = take(bytes, 2, data_len)?;
+
+        if !header.extended {
+            return Ok(Self {
+                header,
+                extended_header: None,
+                payload: data.to_vec(),
+            });
+        }
+
+        let extended_header = read_le::<ExtendedHeader>(data, 0, 2)?;
+        let data_size = usize::from(extended_header.data_size);
+        let payload = if extended_header.request_chunk {
+            &[][..]
+        } else if extended_header.chunked {
+            let len = data_size
+                .saturating_sub(usize::from(extended_header.chunk_number) * Self::MAX_CHUNK_LEN);
+            take(data, 2, std::cmp::min(len, Self::MAX_CHUNK_LEN))?
+        } else {
+            take(data, 2, data_size)?
+        };
+
+        Ok(Self {
+            header,
+            extended_header: Some(extended_header),
+            payload: payload.to_vec(),
+        })
+    }
+
+    /// Reassembles a chunked Extended Message from its chunks, in order.
+    /// Chunk requests sent by the other port are skipped.
+    pub fn from_chunks<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> Result<Self> {
+        let mut message: Option<Self> = None;
+        for chunk in chunks {
+            let chunk = Self::parse(chunk)?;
+            let Some(extended_header) = &chunk.extended_header else {
+                return Err(Error::ParseError {
+                    field: "extended".into(),
+                    value: 0,
+                    #[cfg(feature = "backtrace")]
+                    backtrace: std::backtrace::Backtrace::capture(),
+                });
+            };
+
+            if extended_header.request_chunk {
+                continue;
+            }
+
+            let expected = match &message {
+                Some(message) => message.payload.len() / Self::MAX_CHUNK_LEN,
+                None => 0,
+            };
+            if usize::from(extended_header.chunk_number) != expected {
+                return Err(Error::ParseError {
+                    field: "chunk_number".into(),
+                    value: extended_header.chunk_number.into(),
+                    #[cfg(feature = "backtrace")]
+                    backtrace: std::backtrace::Backtrace::capture(),
+                });
+            }
+
+            match &mut message {
+                Some(message) => message.payload.extend_from_slice(&chunk.payload),
+                None => message = Some(chunk),
+            }
+        }
+
+        message.ok_or_else(|| Error::ParseError {
+            field: "chunks".into(),
+            value: 0,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })
+    }
+
+    /// Whether the whole data block of an Extended Message was received.
+    /// Always true for Control and Data Messages.
+    pub fn is_complete(&self) -> bool {
+        self.extended_header
+            .as_ref()
+            .is_none_or(|header| self.payload.len() >= header.data_size.into())
+    }
+
+    /// Decodes the Message Type from the header.
+    pub fn message_type(&self) -> Result<MessageType> {
+        let raw = u32::from(self.header.message_type);
+        let message_type = if self.header.extended {
+            ExtendedMessageType::n(raw).map(MessageType::Extended)
+        } else if self.header.num_data_objects == 0 {
+            ControlMessageType::n(raw).map(MessageType::Control)
+        } else {
+            DataMessageType::n(raw).map(MessageType::Data)
+        };
+
+        message_type.ok_or_else(|| Error::ParseError {
+            field: "message_type".into(),
+            value: raw,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })
+    }
+
+    /// The data objects of a Data Message.
+    pub fn data_objects(&self) -> Vec<u32> {
+        self.payload
+            .chunks_exact(4)
+            .map(|dword| u32::from_le_bytes([dword[0], dword[1], dword[2], dword[3]]))
+            .collect()
+    }
+
+    /// Decodes the message into the matching [`Message`] variant. The
+    /// `recipient` is needed to decode Discover Identity responses from
+    /// cable plugs. Messages without a typed representation yet return
+    /// [`Error::NotSupported`].
+    pub fn decode(&self, recipient: MessageRecipient) -> Result<Message> {
+        if !self.is_complete() {
+            return Err(Error::ParseError {
+                field: "data_size".into(),
+                value: self.payload.len() as u32,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            });
+        }
+
+        let objects = self.data_objects();
+
+        let message = match self.message_type()? {
+            MessageType::Control(message_type) => Message::Pd3p2Control(message_type),
+            MessageType::Data(DataMessageType::SourceCapabilities) => {
+                Message::Pd3p2SourceCapabilities(Capabilities::from_pdos(&objects))
+            }
+            MessageType::Data(DataMessageType::SinkCapabilities) => {
+                Message::Pd3p2SinkCapabilities(Capabilities::from_pdos(&objects))
+            }
+            MessageType::Data(DataMessageType::Request) => {
+                Message::Pd3p2Request(read_first::<RequestDataObject>(&objects)?)
+            }
+            MessageType::Data(DataMessageType::Bist) => {
+                Message::Pd3p2Bist(read_le::<BistDataObject>(&self.payload, 0, 4)?)
+            }
+            MessageType::Data(DataMessageType::BatteryStatus) => {
+                Message::Pd3p2BatteryStatus(read_le::<BatteryStatusData>(&self.payload, 0, 4)?)
+            }
+            MessageType::Data(DataMessageType::Alert) => {
+                Message::Pd3p2Alert(read_le::<AlertDataObject>(&self.payload, 0, 4)?)
+            }
+            MessageType::Data(DataMessageType::EnterUsb) => {
+                Message::Pd3p2EnterUsb(read_le::<EnterUsbDataObject>(&self.payload, 0, 4)?)
+            }
+            MessageType::Data(DataMessageType::EprRequest) => {
+                Message::Pd3p2EprRequest(EprRequest {
+                    rdo: read_first(&objects)?,
+                    pdo: objects.get(1).copied().unwrap_or_default(),
+                })
+            }
+            MessageType::Data(DataMessageType::Revision) => {
+                Message::Pd3p2Revision(read_le::<RevisionMessageData>(&self.payload, 0, 4)?)
+            }
+            MessageType::Data(DataMessageType::VendorDefined) => {
+                Self::decode_vdm(&objects, recipient)?
+            }
+            MessageType::Extended(ExtendedMessageType::SourceCapabilitiesExtended) => {
+                Message::Pd3p2SourceCapabilitiesExtended(read_le::<SourceCapabilitiesExtended>(
+                    &self.payload,
+                    0,
+                    25,
+                )?)
+            }
+            MessageType::Extended(ExtendedMessageType::SinkCapabilitiesExtended) => {
+                Message::Pd3p2SinkCapabilitiesExtended(read_le::<SinkCapabilitiesExtended>(
+                    &self.payload,
+                    0,
+                    20,
+                )?)
+            }
+            MessageType::Extended(ExtendedMessageType::EprSourceCapabilities) => {
+                Message::Pd3p2EprSourceCapabilities(Capabilities::from_pdos(&objects))
+            }
+            MessageType::Extended(ExtendedMessageType::EprSinkCapabilities) => {
+                Message::Pd3p2EprSinkCapabilities(Capabilities::from_pdos(&objects))
+            }
+            _ => {
+                return Err(Error::NotSupported {
+                    #[cfg(feature = "backtrace")]
+                    backtrace: std::backtrace::Backtrace::capture(),
+                })
+            }
+        };
+
+        Ok(message)
+    }
+
+    /// Decodes a Vendor_Defined Message, using the typed Discover responses
+    /// where possible.
+    fn decode_vdm(objects: &[u32], recipient: MessageRecipient) -> Result<Message> {
+        let header = read_first::<VdmHeader>(objects)?;
+        if header.structured {
+            match header.command {
+                Command::DiscoverIdentity => {
+                    return Ok(Message::Pd3p2DiscoverIdentityResponse(
+                        DiscoverIdentityResponse::from_message(objects, recipient)?,
+                    ))
+                }
+                Command::DiscoverSVIDs => {
+                    return Ok(Message::Pd3p2DiscoverSvidsResponse(
+                        DiscoverSvidsResponse::from_message(objects)?,
+                    ))
+                }
+                Command::DiscoverModes => {
+                    return Ok(Message::Pd3p2DiscoverModesResponse(
+                        DiscoverModesResponse::from_message(objects)?,
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        let mut message = VendorDefinedMessage {
+            header,
+            ..Default::default()
+        };
+        for (dst, src) in message.vdos.iter_mut().zip(&objects[1..]) {
+            *dst = *src;
+            message.num_vdos += 1;
+        }
+
+        Ok(Message::Pd3p2VendorDefined(message))
+    }
+}
+
+/// Returns `len` bytes of `bytes` starting at `offset`, or a parse error if
+/// the message is truncated.
+fn take(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    bytes
+        .get(offset..offset + len)
+        .ok_or_else(|| Error::ParseError {
+            field: "length".into(),
+            value: bytes.len() as u32,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })
+}
+
+/// Decodes a `T` from `len` bytes of `bytes` starting at `offset`.
+fn read_le<T: FromBytes>(bytes: &[u8], offset: usize, len: usize) -> Result<T> {
+    let bytes = take(bytes, offset, len)?;
+    let mut reader = BitReader::new(Cursor::new(bytes));
+    T::from_bytes(&mut reader)
+}
+
+/// Decodes the first data object as a `T`.
+fn read_first<T: FromBytes>(objects: &[u32]) -> Result<T> {
+    let first = objects.first().copied().unwrap_or_default().to_le_bytes();
+    read_le(&first, 0, 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pd::Pdo;
+    use crate::Millivolt;
+
+    #[test]
+    fn test_source_capabilities() {
+        // Source_Capabilities, PD 3.0, two Fixed Supply PDOs: 5V/3A, 9V/3A.
+        let bytes = [0xa1, 0x21, 0x2c, 0x91, 0x01, 0x00, 0x2c, 0xd1, 0x02, 0x00];
+        let message = RawMessage::parse(&bytes).unwrap();
+        assert_eq!(
+            message.message_type().unwrap(),
+            MessageType::Data(DataMessageType::SourceCapabilities)
+        );
+
+        let Message::Pd3p2SourceCapabilities(capabilities) =
+            message.decode(MessageRecipient::Sop).unwrap()
+        else {
+            panic!("expected Source_Capabilities");
+        };
+        assert_eq!(capabilities.num_pdos, 2);
+
+        let pdos = capabilities.pdos().unwrap();
+        assert_eq!(pdos.len(), 2);
+        assert!(matches!(pdos[1], Pdo::Pd3p2FixedSupplyPdo(_)));
+        assert_eq!(pdos[1].max_voltage(), Millivolt(9000));
+    }
+
+    #[test]
+    fn test_control_message() {
+        // GoodCRC, PD 3.0, Message ID 1.
+        let message = RawMessage::parse(&[0x81, 0x02]).unwrap();
+        assert_eq!(
+            message.decode(MessageRecipient::Sop).unwrap(),
+            Message::Pd3p2Control(ControlMessageType::GoodCrc)
+        );
+    }
+}
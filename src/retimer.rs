@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! Inventory of the USB4/Thunderbolt retimers behind the Type-C ports.
+//!
+//! The Linux thunderbolt driver exposes the retimers it enumerated as
+//! `/sys/bus/thunderbolt/devices/<domain>-<route>:<port>.<index>`, where
+//! `<port>` is the USB4 port of the router and `<index>` the position of the
+//! retimer on the link, starting at 1 next to the router. The USB4 port links
+//! to the Type-C connector it is wired to.
+//!
+//! Retimer modes are set with SET_RETIMER_MODE, see
+//! [`crate::OsControl::set_retimer_mode`].
+
+use std::path::Path;
+
+use crate::backends::sysfs::sysfs_reader::SysfsReader;
+use crate::Result;
+
+/// The directory holding the thunderbolt devices.
+pub const THUNDERBOLT_SYSFS_PATH: &str = "/sys/bus/thunderbolt/devices";
+
+/// A retimer on a USB4 link.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Retimer {
+    /// The sysfs name, e.g. `0-0:1.1`.
+    pub name: String,
+    /// The router the retimer is attached to, e.g. `0-0`.
+    pub router: String,
+    /// The USB4 port of the router.
+    pub port: u32,
+    /// The position of the retimer on the link, starting at 1.
+    pub index: u32,
+    /// The Type-C connector the USB4 port is wired to, if the kernel knows.
+    pub connector_nr: Option<usize>,
+    /// The vendor ID.
+    pub vendor: u32,
+    /// The device ID.
+    pub device: u32,
+    /// The version of the NVM, i.e. the firmware, as `major.minor`. `None`
+    /// if the kernel cannot read it.
+    pub nvm_version: Option<String>,
+}
+
+/// Splits a retimer name into its router, USB4 port and index. Returns
+/// `None` for the other thunderbolt devices.
+pub fn parse_retimer_name(name: &str) -> Option<(&str, u32, u32)> {
+    let (router, retimer) = name.split_once(':')?;
+    let (port, index) = retimer.split_once('.')?;
+    let (domain, route) = router.split_once('-')?;
+    if domain.parse::<u32>().is_err() || u64::from_str_radix(route, 16).is_err() {
+        return None;
+    }
+
+    Some((router, port.parse().ok()?, index.parse().ok()?))
+}
+
+/// Reads a hex attribute such as `vendor`, printed as `0x8087`.
+fn read_hex(reader: &mut SysfsReader, path: &Path) -> Result<u32> {
+    reader.set_path(&path.to_string_lossy())?;
+    reader.read_hex_u32()
+}
+
+/// Returns the Type-C connector `port` of `router` is wired to.
+fn connector_nr(devices: &Path, router: &str, port: u32) -> Option<usize> {
+    let connector = devices
+        .join(router)
+        .join(format!("usb4_port{port}"))
+        .join("connector");
+    let target = std::fs::read_link(connector).ok()?;
+    let name = target.file_name()?.to_string_lossy().into_owned();
+    name.strip_prefix("port")?.parse().ok()
+}
+
+/// Lists the retimers under `devices`, usually [`THUNDERBOLT_SYSFS_PATH`].
+pub fn list_retimers_in(devices: &Path) -> Result<Vec<Retimer>> {
+    let mut reader = SysfsReader::new()?;
+    let mut retimers = vec![];
+    for entry in std::fs::read_dir(devices)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some((router, port, index)) = parse_retimer_name(&name) else {
+            continue;
+        };
+
+        let path = entry.path();
+        // The retimer may be going away, e.g. if the port was unplugged.
+        let (Ok(vendor), Ok(device)) = (
+            read_hex(&mut reader, &path.join("vendor")),
+            read_hex(&mut reader, &path.join("device")),
+        ) else {
+            continue;
+        };
+        let nvm_version = std::fs::read_to_string(path.join("nvm_version"))
+            .ok()
+            .map(|version| version.trim().to_string());
+
+        retimers.push(Retimer {
+            router: router.to_string(),
+            port,
+            index,
+            connector_nr: connector_nr(devices, router, port),
+            vendor,
+            device,
+            nvm_version,
+            name,
+        });
+    }
+
+    retimers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(retimers)
+}
+
+/// Lists the retimers enumerated by the thunderbolt driver. Returns an empty
+/// list if there is no thunderbolt bus.
+pub fn list_retimers() -> Result<Vec<Retimer>> {
+    let devices = Path::new(THUNDERBOLT_SYSFS_PATH);
+    if !devices.exists() {
+        return Ok(vec![]);
+    }
+
+    list_retimers_in(devices)
+}
+
+/// Lists the retimers wired to `connector_nr`.
+pub fn connector_retimers(connector_nr: usize) -> Result<Vec<Retimer>> {
+    Ok(list_retimers()?
+        .into_iter()
+        .filter(|retimer| retimer.connector_nr == Some(connector_nr))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_retimers() {
+        let devices = std::env::temp_dir().join(format!("retimer-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&devices);
+
+        let retimer = devices.join("0-0:1.1");
+        std::fs::create_dir_all(&retimer).unwrap();
+        std::fs::write(retimer.join("vendor"), "0x8087\n").unwrap();
+        std::fs::write(retimer.join("device"), "0x15ee\n").unwrap();
+        std::fs::write(retimer.join("nvm_version"), "21.0\n").unwrap();
+
+        let port = devices.join("0-0/usb4_port1");
+        std::fs::create_dir_all(&port).unwrap();
+        std::os::unix::fs::symlink("../../../port1", port.join("connector")).unwrap();
+
+        // A retimer whose IDs cannot be read is skipped.
+        let unreadable = devices.join("0-0:1.2");
+        std::fs::create_dir_all(&unreadable).unwrap();
+        std::fs::write(unreadable.join("device"), "0x15ee\n").unwrap();
+
+        // Routers and XDomains are not retimers.
+        std::fs::create_dir_all(devices.join("0-1")).unwrap();
+        std::fs::create_dir_all(devices.join("domain0")).unwrap();
+
+        let retimers = list_retimers_in(&devices).unwrap();
+        std::fs::remove_dir_all(&devices).unwrap();
+
+        assert_eq!(
+            retimers,
+            [Retimer {
+                name: "0-0:1.1".into(),
+                router: "0-0".into(),
+                port: 1,
+                index: 1,
+                connector_nr: Some(1),
+                vendor: 0x8087,
+                device: 0x15ee,
+                nvm_version: Some("21.0".into()),
+            }]
+        );
+    }
+}
@@ -0,0 +1,380 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! Parsing of the Linux TCPM (Type-C Port Manager) debugfs log.
+//!
+//! Each port managed by TCPM exposes a log at
+//! `/sys/kernel/debug/usb/tcpm-<port>`, with one timestamped line per state
+//! machine transition, CC or VBUS change and PD message sent or received.
+//! Only the header of a PD message is logged, but the PDOs of received
+//! Source Capabilities are logged on the lines that follow it. Reading the
+//! log consumes it.
+
+use std::io::Cursor;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::pd::pd3p2::BatterySupplyPdo;
+use crate::pd::pd3p2::FixedSupplyPdo;
+use crate::pd::pd3p2::SprProgrammableSupplyPdo;
+use crate::pd::pd3p2::VariableSupplyPdo;
+use crate::pd::Message;
+use crate::pd::MessageHeader;
+use crate::pd::MessageRecipient;
+use crate::pd::MessageType;
+use crate::pd::Pdo;
+use crate::pd::RawMessage;
+use crate::BitReader;
+use crate::FromBytes;
+use crate::Milliamp;
+use crate::Millivolt;
+use crate::Milliwatt;
+use crate::Result;
+
+/// The directory holding the TCPM logs.
+pub const TCPM_DEBUGFS_PATH: &str = "/sys/kernel/debug/usb";
+
+/// The direction of a PD message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// Received from the port partner or cable.
+    Rx,
+    /// Sent by the port.
+    Tx,
+}
+
+/// A PD message logged by TCPM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdMessage {
+    pub direction: Direction,
+    /// The Message Header.
+    pub header: MessageHeader,
+    /// The Message Type, if it is known.
+    pub message_type: Option<MessageType>,
+    /// The decoded message, for Control Messages. TCPM does not log the data
+    /// objects of other messages.
+    pub message: Option<Message>,
+}
+
+/// An event in the TCPM log.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TcpmEvent {
+    /// The state machine moved from one state to another.
+    StateChange { from: String, to: String },
+    /// A state change was scheduled after `delay`.
+    PendingStateChange {
+        from: String,
+        to: String,
+        delay: Duration,
+    },
+    /// The CC lines changed. The values are the kernel's `enum typec_cc_status`.
+    CcChange { cc1: (u8, u8), cc2: (u8, u8) },
+    /// VBUS was turned on or off.
+    Vbus { on: bool },
+    /// A PD message was sent or received.
+    PdMessage(PdMessage),
+    /// A PDO of the Source Capabilities received before, at `index`.
+    SourcePdo { index: usize, pdo: Pdo },
+    /// A PD transmission completed with the given status.
+    PdTxComplete { status: i32 },
+    /// Any other line.
+    Other(String),
+}
+
+/// A timestamped line of the TCPM log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TcpmLogEntry {
+    /// The time since boot.
+    pub timestamp: Duration,
+    pub event: TcpmEvent,
+}
+
+impl std::fmt::Display for TcpmLogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{:>5}.{:06}] ",
+            self.timestamp.as_secs(),
+            self.timestamp.subsec_micros()
+        )?;
+
+        match &self.event {
+            TcpmEvent::StateChange { from, to } => write!(f, "state {from} -> {to}"),
+            TcpmEvent::PendingStateChange { from, to, delay } => {
+                write!(f, "state {from} -> {to} in {} ms", delay.as_millis())
+            }
+            TcpmEvent::CcChange { cc1, cc2 } => {
+                write!(f, "CC1 {} -> {}, CC2 {} -> {}", cc1.0, cc1.1, cc2.0, cc2.1)
+            }
+            TcpmEvent::Vbus { on } => write!(f, "VBUS {}", if *on { "on" } else { "off" }),
+            TcpmEvent::PdMessage(msg) => {
+                let direction = match msg.direction {
+                    Direction::Rx => "RX",
+                    Direction::Tx => "TX",
+                };
+                write!(f, "PD {direction} ")?;
+                match (&msg.message, &msg.message_type) {
+                    (Some(message), _) => write!(f, "{:?}", message),
+                    (None, Some(message_type)) => write!(f, "{:?}", message_type),
+                    (None, None) => write!(f, "type {:#x}", msg.header.message_type),
+                }
+            }
+            TcpmEvent::SourcePdo { index, pdo } => write!(f, "PDO {index}: {:?}", pdo),
+            TcpmEvent::PdTxComplete { status } => write!(f, "PD TX complete, status {status}"),
+            TcpmEvent::Other(line) => write!(f, "{line}"),
+        }
+    }
+}
+
+/// Parses the contents of a TCPM log. Lines without a timestamp are
+/// ignored.
+pub fn parse_log(log: &str) -> Vec<TcpmLogEntry> {
+    let line_re = Regex::new(r"^\[\s*(\d+)\.(\d{6})\]\s?(.*)$").unwrap();
+    let state_re = Regex::new(r"^state change (\S+) -> (\S+)").unwrap();
+    let pending_re = Regex::new(r"^pending state change (\S+) -> (\S+) @ (\d+) ms").unwrap();
+    let cc_re = Regex::new(r"^CC1: (\d+) -> (\d+), CC2: (\d+) -> (\d+)").unwrap();
+    let pd_re = Regex::new(r"^PD (RX|TX), header: (0x[0-9a-fA-F]+)").unwrap();
+    let pdo_re = Regex::new(
+        r"^\s*PDO (\d+): type (\d), (\d+)(?:-(\d+))? mV, (\d+) (mA|mW)(?: \[([A-Z]*)\])?",
+    )
+    .unwrap();
+    let tx_complete_re = Regex::new(r"^PD TX complete, status: (-?\d+)").unwrap();
+
+    log.lines()
+        .filter_map(|line| {
+            let captures = line_re.captures(line)?;
+            let timestamp = Duration::from_secs(captures[1].parse().ok()?)
+                + Duration::from_micros(captures[2].parse().ok()?);
+            let text = &captures[3];
+
+            let event = if let Some(c) = pending_re.captures(text) {
+                TcpmEvent::PendingStateChange {
+                    from: c[1].into(),
+                    to: c[2].into(),
+                    delay: Duration::from_millis(c[3].parse().ok()?),
+                }
+            } else if let Some(c) = state_re.captures(text) {
+                TcpmEvent::StateChange {
+                    from: c[1].into(),
+                    to: c[2].into(),
+                }
+            } else if let Some(c) = cc_re.captures(text) {
+                TcpmEvent::CcChange {
+                    cc1: (c[1].parse().ok()?, c[2].parse().ok()?),
+                    cc2: (c[3].parse().ok()?, c[4].parse().ok()?),
+                }
+            } else if let Some(c) = tx_complete_re.captures(text) {
+                TcpmEvent::PdTxComplete {
+                    status: c[1].parse().ok()?,
+                }
+            } else if let Some(c) = pd_re.captures(text) {
+                let direction = if &c[1] == "RX" {
+                    Direction::Rx
+                } else {
+                    Direction::Tx
+                };
+                let header = u16::from_str_radix(&c[2][2..], 16).ok()?;
+                parse_pd_message(direction, header)
+                    .map_or_else(|| TcpmEvent::Other(text.into()), TcpmEvent::PdMessage)
+            } else if let Some(c) = pdo_re.captures(text) {
+                parse_pdo(&c).map_or_else(
+                    || TcpmEvent::Other(text.into()),
+                    |pdo| TcpmEvent::SourcePdo {
+                        index: c[1].parse().unwrap_or_default(),
+                        pdo,
+                    },
+                )
+            } else if text.starts_with("VBUS on") {
+                TcpmEvent::Vbus { on: true }
+            } else if text.starts_with("VBUS off") {
+                TcpmEvent::Vbus { on: false }
+            } else {
+                TcpmEvent::Other(text.into())
+            };
+
+            Some(TcpmLogEntry { timestamp, event })
+        })
+        .collect()
+}
+
+/// Builds a PD message from its header. Only Control Messages, which carry
+/// no data objects, can be decoded.
+fn parse_pd_message(direction: Direction, header: u16) -> Option<PdMessage> {
+    let mut bytes = header.to_le_bytes().to_vec();
+    let header = MessageHeader::from_bytes(&mut BitReader::new(Cursor::new(&bytes))).ok()?;
+
+    // Pad the data objects, which are not logged, so the Message Type can be
+    // decoded.
+    bytes.resize(2 + 4 * usize::from(header.num_data_objects), 0);
+
+    let raw = RawMessage::parse(&bytes).ok();
+    let message_type = raw.as_ref().and_then(|raw| raw.message_type().ok());
+    let message = raw
+        .filter(|_| header.num_data_objects == 0)
+        .and_then(|raw| raw.decode(MessageRecipient::Sop).ok());
+
+    Some(PdMessage {
+        direction,
+        header,
+        message_type,
+        message,
+    })
+}
+
+/// Builds a PDO from a line logged by the kernel's `tcpm_log_source_caps()`,
+/// e.g. ` PDO 0: type 0, 5000 mV, 3000 mA [RSUD]`. The flags of Fixed
+/// Supply PDOs are the first letters of the capabilities that are set.
+fn parse_pdo(c: &regex::Captures) -> Option<Pdo> {
+    let first = c[3].parse().ok()?;
+    let max_voltage = match c.get(4) {
+        Some(max_voltage) => Millivolt(max_voltage.as_str().parse().ok()?),
+        None => Millivolt(first),
+    };
+    let min_voltage = Millivolt(first);
+    let value = c[5].parse().ok()?;
+
+    let pdo = match (&c[2], &c[6]) {
+        ("0", "mA") => {
+            let flags = c.get(7).map_or("", |flags| flags.as_str());
+            Pdo::Pd3p2FixedSupplyPdo(FixedSupplyPdo {
+                dual_role_power: flags.contains('R'),
+                higher_capability: flags.contains('H'),
+                unconstrained_power: flags.contains('E'),
+                usb_communications_capable: flags.contains('U'),
+                dual_role_data: flags.contains('D'),
+                voltage: max_voltage,
+                operational_current: Milliamp(value),
+                ..Default::default()
+            })
+        }
+        ("1", "mW") => Pdo::Pd3p2BatterySupplyPdo(BatterySupplyPdo {
+            max_voltage,
+            min_voltage,
+            operational_power: Milliwatt(value),
+        }),
+        ("2", "mA") => Pdo::Pd3p2VariableSupplyPdo(VariableSupplyPdo {
+            max_voltage,
+            min_voltage,
+            max_current: Milliamp(value),
+        }),
+        ("3", "mA") => Pdo::Pd3p2AugmentedPdo(SprProgrammableSupplyPdo {
+            max_voltage,
+            min_voltage,
+            max_current: Milliamp(value),
+        }),
+        _ => return None,
+    };
+
+    Some(pdo)
+}
+
+/// Returns the paths of the TCPM logs, one per port.
+pub fn find_logs() -> Result<Vec<PathBuf>> {
+    let mut logs = vec![];
+    for entry in std::fs::read_dir(TCPM_DEBUGFS_PATH)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with("tcpm-") {
+            logs.push(entry.path());
+        }
+    }
+
+    logs.sort();
+    Ok(logs)
+}
+
+/// Reads and parses the TCPM log at `path`.
+pub fn read_log(path: &Path) -> Result<Vec<TcpmLogEntry>> {
+    let log = std::fs::read_to_string(path)?;
+    Ok(parse_log(&log))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pd::pd3p2::ControlMessageType;
+    use crate::pd::pd3p2::DataMessageType;
+
+    const LOG: &str = "\
+[    5.102311] CC1: 0 -> 0, CC2: 0 -> 5 [state TOGGLING, polarity 0, connected]
+[    5.102318] state change TOGGLING -> SNK_ATTACH_WAIT [rev3 NONE_AMS]
+[    5.102325] pending state change SNK_ATTACH_WAIT -> SNK_DEBOUNCED @ 170 ms [rev3 NONE_AMS]
+[    5.290846] VBUS on
+[    5.412087] PD RX, header: 0x21a1 [1]
+[    5.412093]  PDO 0: type 0, 5000 mV, 3000 mA [RSUD]
+[    5.412096]  PDO 1: type 0, 9000 mV, 3000 mA []
+[    5.412504] PD TX, header: 0x1082
+[    5.413377] PD TX complete, status: 0
+[    5.415019] PD RX, header: 0x3a3 [1]
+";
+
+    #[test]
+    fn test_parse_log() {
+        let entries = parse_log(LOG);
+        assert_eq!(entries.len(), 10);
+
+        assert_eq!(
+            entries[0].event,
+            TcpmEvent::CcChange {
+                cc1: (0, 0),
+                cc2: (0, 5)
+            }
+        );
+        assert_eq!(
+            entries[2].event,
+            TcpmEvent::PendingStateChange {
+                from: "SNK_ATTACH_WAIT".into(),
+                to: "SNK_DEBOUNCED".into(),
+                delay: Duration::from_millis(170),
+            }
+        );
+        assert_eq!(entries[3].event, TcpmEvent::Vbus { on: true });
+
+        let TcpmEvent::PdMessage(rx) = &entries[4].event else {
+            panic!("expected a PD message, got {:?}", entries[4].event);
+        };
+        assert_eq!(rx.direction, Direction::Rx);
+        assert_eq!(
+            rx.message_type,
+            Some(MessageType::Data(DataMessageType::SourceCapabilities))
+        );
+        assert_eq!(rx.message, None);
+
+        let TcpmEvent::SourcePdo { index, pdo } = &entries[5].event else {
+            panic!("expected a PDO, got {:?}", entries[5].event);
+        };
+        assert_eq!(*index, 0);
+        assert_eq!(pdo.max_voltage(), Millivolt(5000));
+        assert_eq!(pdo.max_current(), Milliamp(3000));
+        let Pdo::Pd3p2FixedSupplyPdo(fixed) = pdo else {
+            panic!("expected a Fixed Supply PDO, got {:?}", pdo);
+        };
+        assert!(fixed.dual_role_power);
+        assert!(fixed.usb_communications_capable);
+        assert!(fixed.dual_role_data);
+        assert!(!fixed.unconstrained_power);
+
+        let TcpmEvent::PdMessage(tx) = &entries[7].event else {
+            panic!("expected a PD message, got {:?}", entries[7].event);
+        };
+        assert_eq!(tx.direction, Direction::Tx);
+        assert_eq!(
+            tx.message_type,
+            Some(MessageType::Data(DataMessageType::Request))
+        );
+
+        let TcpmEvent::PdMessage(accept) = &entries[9].event else {
+            panic!("expected a PD message, got {:?}", entries[9].event);
+        };
+        assert_eq!(
+            accept.message,
+            Some(Message::Pd3p2Control(ControlMessageType::Accept))
+        );
+        assert_eq!(
+            entries[9].to_string(),
+            "[    5.415019] PD RX Pd3p2Control(Accept)"
+        );
+    }
+}
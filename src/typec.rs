@@ -8,39 +8,65 @@
 use std::mem::ManuallyDrop;
 use std::str::FromStr;
 
+use proc_macros::CApiWrapper;
+
 use crate::backends;
+use crate::pd::pd3p2::DiscoverModesResponse;
+use crate::pd::pd3p2::DiscoverSvidsResponse;
 use crate::pd::Message;
 use crate::pd::MessageRecipient;
 use crate::pd::MessageResponseType;
 use crate::pd::Pdo;
 use crate::ucsi::AlternateMode;
+use crate::ucsi::AttentionVdo;
 use crate::ucsi::CableProperty;
 use crate::ucsi::Capability;
 use crate::ucsi::ConnectorCapability;
 use crate::ucsi::ConnectorStatus;
+use crate::ucsi::ErrorStatus;
 use crate::ucsi::GetAlternateModesRecipient;
+use crate::ucsi::LpmPpmInfo;
 use crate::ucsi::PdoSourceCapabilitiesType;
 use crate::ucsi::PdoType;
+use crate::ucsi::PowerDirection;
+use crate::ucsi::PowerLevel;
+use crate::ucsi::PowerOperationMode;
+use crate::ucsi::RawResponse;
+use crate::ucsi::UsbTypeCCurrent;
+use crate::vendor::HwdbResolver;
+use crate::vendor::VendorResolver;
 use crate::BcdWrapper;
 #[cfg(feature = "c_api")]
 use crate::CError;
 use crate::Error;
+use crate::Milliamp;
+use crate::Millivolt;
+use crate::Milliwatt;
 use crate::OsBackend;
+use crate::OsControl;
 use crate::Result;
 
+#[cfg(feature = "c_api")]
+mod c_api {
+    pub(crate) use crate::ucsi::UcsiPowerDirection;
+    pub(crate) use crate::ucsi::UcsiPowerOperationMode;
+}
+
 /// The main library struct.
 /// # Examples
 ///
-/// ```
-/// use libtypec_rs::TypecRs;
-/// use libtypec_rs::OsBackends;
+/// ```no_run
+/// use libtypec_rs::typec::TypecRs;
+/// use libtypec_rs::typec::OsBackends;
 ///
-/// let typec = TypecRs::new(OsBackends::Ucsi);
+/// let typec = TypecRs::new(OsBackends::UcsiDebugfs);
 /// assert!(typec.is_ok());
 /// ```
 pub struct TypecRs {
     /// The OS backend used for this instance.
     os_backend: Box<dyn OsBackend>,
+    /// Resolves vendor names in Discover Identity responses.
+    vendor_resolver: Box<dyn VendorResolver>,
 }
 
 /// The OS backends supported by the library.
@@ -68,20 +94,97 @@ impl FromStr for OsBackends {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "TypeCRs", repr_c)]
+/// A summary of the power contract in place on a connector.
+pub struct PowerContract {
+    /// Whether the connector is providing or consuming power.
+    #[c_api(rename_type = "c_api::UcsiPowerDirection")]
+    pub direction: PowerDirection,
+    /// How the contract was established, i.e. USB PD, BC 1.2, USB Type-C
+    /// Current or default USB power.
+    #[c_api(rename_type = "c_api::UcsiPowerOperationMode")]
+    pub mode: PowerOperationMode,
+    /// The object position of the PDO selected by the Request Data Object.
+    /// Object positions start at 1, zero means no PDO could be identified.
+    pub selected_pdo_position: u8,
+    /// The contract voltage.
+    pub voltage: Millivolt,
+    /// The contract operating current.
+    pub current: Milliamp,
+    /// The contract operating power.
+    pub power: Milliwatt,
+    /// The maximum power offered in the partner's Source Capabilities.
+    pub partner_max_power: Milliwatt,
+    /// Whether the cable current rating is lower than what the source
+    /// offers for this contract.
+    pub cable_limited: bool,
+}
+
+/// Treats a command the backend or the PPM does not support as a missing,
+/// optional piece of information.
+fn optional<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(val) => Ok(Some(val)),
+        Err(Error::NotSupported { .. } | Error::CommandNotSupported { .. }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Copies `response` to the `*out_response_len` bytes at `out_response` and
+/// sets `*out_response_len` to its length. Returns -ENOSPC without copying
+/// anything if the buffer is too small. A null `out_response` discards the
+/// response.
+///
+/// # Safety
+/// `out_response` must be null or valid for `*out_response_len` bytes.
+#[cfg(feature = "c_api")]
+unsafe fn copy_response(
+    response: &[u8],
+    out_response: *mut u8,
+    out_response_len: &mut usize,
+) -> std::ffi::c_int {
+    if out_response.is_null() {
+        *out_response_len = 0;
+        return 0;
+    }
+
+    let buffer_len = std::mem::replace(out_response_len, response.len());
+    if response.len() > buffer_len {
+        return -nix::libc::ENOSPC;
+    }
+
+    std::ptr::copy_nonoverlapping(response.as_ptr(), out_response, response.len());
+    0
+}
+
 // The Rust API.
 impl TypecRs {
     /// Initializes the library with the given `backend`.
     pub fn new(backend: OsBackends) -> Result<Self> {
-        match backend {
-            OsBackends::UcsiDebugfs => Ok(Self {
-                os_backend: Box::new(backends::ucsi_debugfs::UcsiDebugfsBackend::new()?),
-            }),
-            OsBackends::Sysfs => Ok(Self {
-                os_backend: Box::new(backends::sysfs::SysfsBackend::new()?),
-            }),
+        let os_backend: Box<dyn OsBackend> = match backend {
+            OsBackends::UcsiDebugfs => Box::new(backends::ucsi_debugfs::UcsiDebugfsBackend::new()?),
+            OsBackends::Sysfs => Box::new(backends::sysfs::SysfsBackend::new()?),
+        };
+
+        Ok(Self::with_backend(os_backend))
+    }
+
+    /// Initializes the library on top of `os_backend`, e.g. a UCSI backend
+    /// talking to a [`crate::backends::ucsi_debugfs::simulator::PpmSimulator`].
+    pub fn with_backend(os_backend: Box<dyn OsBackend>) -> Self {
+        Self {
+            os_backend,
+            vendor_resolver: Box::new(HwdbResolver),
         }
     }
 
+    /// Sets the resolver used to fill in vendor names in Discover Identity
+    /// responses. Defaults to [`HwdbResolver`].
+    pub fn set_vendor_resolver(&mut self, resolver: Box<dyn VendorResolver>) {
+        self.vendor_resolver = resolver;
+    }
+
     /// Returns the platform policy capabilities.
     pub fn capabilities(&mut self) -> Result<Capability> {
         self.os_backend.capabilities()
@@ -107,6 +210,40 @@ impl TypecRs {
         self.os_backend.alternate_modes(recipient, connector_nr)
     }
 
+    /// Returns the Discover SVIDs responses of `recipient`, rebuilt from its
+    /// alternate modes.
+    pub fn discover_svids(
+        &mut self,
+        recipient: GetAlternateModesRecipient,
+        connector_nr: usize,
+    ) -> Result<Vec<DiscoverSvidsResponse>> {
+        self.os_backend.discover_svids(recipient, connector_nr)
+    }
+
+    /// Returns the Discover Modes response of `recipient` for `svid`,
+    /// rebuilt from its alternate modes.
+    pub fn discover_modes(
+        &mut self,
+        recipient: GetAlternateModesRecipient,
+        connector_nr: usize,
+        svid: u32,
+    ) -> Result<DiscoverModesResponse> {
+        self.os_backend
+            .discover_modes(recipient, connector_nr, svid)
+    }
+
+    /// Returns the offsets, into `alternate_modes(Connector, connector_nr)`,
+    /// of the alternate modes that are currently supported on `connector_nr`.
+    pub fn supported_alternate_modes(&mut self, connector_nr: usize) -> Result<Vec<usize>> {
+        self.os_backend.supported_alternate_modes(connector_nr)
+    }
+
+    /// Returns the offsets, into `alternate_modes(Connector, connector_nr)`,
+    /// of the alternate modes `connector_nr` is operating in.
+    pub fn current_alternate_modes(&mut self, connector_nr: usize) -> Result<Vec<usize>> {
+        self.os_backend.current_alternate_modes(connector_nr)
+    }
+
     /// Returns the cable properties of `connector_nr`.
     pub fn cable_properties(&mut self, connector_nr: usize) -> Result<CableProperty> {
         self.os_backend.cable_properties(connector_nr)
@@ -117,6 +254,91 @@ impl TypecRs {
         self.os_backend.connector_status(connector_nr)
     }
 
+    /// Returns the reasons the previous command on `connector_nr` failed.
+    pub fn error_status(&mut self, connector_nr: Option<usize>) -> Result<ErrorStatus> {
+        self.os_backend.error_status(connector_nr)
+    }
+
+    /// Returns the most recent Attention VDM received on `connector_nr`.
+    pub fn attention_vdo(&mut self, connector_nr: usize) -> Result<AttentionVdo> {
+        self.os_backend.attention_vdo(connector_nr)
+    }
+
+    /// Returns the vendor, product and firmware version of the LPM behind
+    /// `connector_nr`.
+    pub fn lpm_ppm_info(&mut self, connector_nr: usize) -> Result<LpmPpmInfo> {
+        self.os_backend.lpm_ppm_info(connector_nr)
+    }
+
+    /// Returns the source or sink power level of `connector_nr`.
+    pub fn power_level(
+        &mut self,
+        connector_nr: usize,
+        power_direction: PowerDirection,
+    ) -> Result<PowerLevel> {
+        self.os_backend.power_level(connector_nr, power_direction)
+    }
+
+    /// Returns the commands that reset the PPM and its connectors, and
+    /// configure its notifications. These change the state of the system
+    /// and are meant for diagnostic and recovery tools.
+    pub fn control(&mut self) -> Result<&mut dyn OsControl> {
+        self.os_backend.control()
+    }
+
+    /// Limits the power `connector_nr` sources or sinks to `max_power`, or
+    /// lets the PPM decide if zero.
+    ///
+    /// The sysfs backend returns `Error::NotSupported`, as the kernel does not
+    /// let userspace change the power level of a port.
+    pub fn set_power_level(
+        &mut self,
+        connector_nr: usize,
+        power_direction: PowerDirection,
+        max_power: Milliwatt,
+        usb_type_c_current: UsbTypeCCurrent,
+    ) -> Result<()> {
+        self.control()?.set_power_level(
+            connector_nr,
+            power_direction,
+            PowerLevel {
+                max_power,
+                usb_type_c_current,
+            },
+        )
+    }
+
+    /// Enables or disables the Sink Path of `connector_nr`.
+    pub fn set_sink_path(&mut self, connector_nr: usize, enable: bool) -> Result<()> {
+        self.control()?.set_sink_path(connector_nr, enable)
+    }
+
+    /// Sets the mode of the retimers of `connector_nr`. See
+    /// [`crate::retimer`] to list them.
+    pub fn set_retimer_mode(&mut self, connector_nr: usize, retimer_mode: u32) -> Result<()> {
+        self.control()?.set_retimer_mode(connector_nr, retimer_mode)
+    }
+
+    /// Passes the vendor-defined firmware update `request` to the LPM of
+    /// `connector_nr` and returns its response. Requests larger than
+    /// MESSAGE_OUT are split in chunks if the PPM supports chunking.
+    pub fn fw_update_request(&mut self, connector_nr: usize, request: &[u8]) -> Result<Vec<u8>> {
+        self.control()?.fw_update_request(connector_nr, request)
+    }
+
+    /// Passes the security `request` to `connector_nr` and returns the
+    /// response. Requests larger than MESSAGE_OUT are split in chunks if the
+    /// PPM supports chunking.
+    pub fn security_request(&mut self, connector_nr: usize, request: &[u8]) -> Result<Vec<u8>> {
+        self.control()?.security_request(connector_nr, request)
+    }
+
+    /// Sends `control`, the CONTROL data structure in little-endian order,
+    /// to the PPM as is. Only UCSI backends support this.
+    pub fn raw_ucsi_command(&mut self, control: &[u8]) -> Result<RawResponse> {
+        self.control()?.raw_ucsi_command(control)
+    }
+
     /// Get a USB PD message.
     ///
     /// # Arguments
@@ -130,8 +352,17 @@ impl TypecRs {
         recipient: MessageRecipient,
         response_type: MessageResponseType,
     ) -> Result<Message> {
-        self.os_backend
-            .pd_message(connector_nr, recipient, response_type)
+        let mut message = self
+            .os_backend
+            .pd_message(connector_nr, recipient, response_type)?;
+
+        if let Message::Pd3p2DiscoverIdentityResponse(response) = &mut message {
+            response
+                .id_header_vdo
+                .resolve_vendor(self.vendor_resolver.as_ref());
+        }
+
+        Ok(message)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -142,7 +373,7 @@ impl TypecRs {
     /// `connector_nr` Represents connector to be queried
     /// `partner_pdo` Whether to retrieve partner PDOs
     /// `pdo_offset` Index from which PDO needs to be retrieved
-    /// `nr_pdos` Represents number of PDOs to be retrieved
+    /// `nr_pdos` Represents number of PDOs to be retrieved, or 0 for all of them
     /// `pdo_type` Whether to retrieve source or sink PDOs
     /// `source_capabilities_type` Represents the type of Source PDOs requested.
     /// `revision` Indicates the USB PD revision used to interpret the read
@@ -167,6 +398,135 @@ impl TypecRs {
             revision,
         )
     }
+
+    /// Returns a summary of the power contract on `connector_nr`.
+    ///
+    /// This is assembled from the connector status, the Source PDOs of
+    /// whoever is providing power and the cable properties.
+    pub fn power_contract(&mut self, connector_nr: usize) -> Result<PowerContract> {
+        let status = self.connector_status(connector_nr)?;
+        let mut contract = PowerContract {
+            direction: status.power_direction,
+            mode: status.power_operation_mode,
+            ..Default::default()
+        };
+
+        if !status.connect_status {
+            return Ok(contract);
+        }
+
+        let revision = self.capabilities()?.pd_version;
+        let partner_source_pdos = optional(self.pdos(
+            connector_nr,
+            true,
+            0,
+            0,
+            PdoType::Source,
+            PdoSourceCapabilitiesType::CurrentSupportedSourceCapabilities,
+            revision,
+        ))?
+        .unwrap_or_default();
+
+        contract.partner_max_power = partner_source_pdos
+            .iter()
+            .map(|pdo| pdo.max_power())
+            .max_by_key(|power| power.0)
+            .unwrap_or_default();
+
+        // The current the source is willing to provide for this contract.
+        let mut offered_current = Milliamp(0);
+
+        match status.power_operation_mode {
+            PowerOperationMode::PowerDelivery => {
+                // See USB PD 3.2 - 6.4.2 Request Message
+                let rdo = status.negotiated_power_level;
+                let position = (rdo >> 28) as usize;
+                let operating = (rdo >> 10) & 0x3ff;
+
+                let source_pdos = match status.power_direction {
+                    PowerDirection::Consumer => partner_source_pdos,
+                    PowerDirection::Provider => optional(self.pdos(
+                        connector_nr,
+                        false,
+                        0,
+                        0,
+                        PdoType::Source,
+                        PdoSourceCapabilitiesType::CurrentSupportedSourceCapabilities,
+                        revision,
+                    ))?
+                    .unwrap_or_default(),
+                };
+
+                let selected_pdo = position.checked_sub(1).and_then(|idx| source_pdos.get(idx));
+
+                match selected_pdo {
+                    Some(pdo) => {
+                        contract.selected_pdo_position = position as u8;
+                        offered_current = pdo.max_current();
+
+                        match pdo {
+                            Pdo::Pd3p2FixedSupplyPdo(_) | Pdo::Pd3p2VariableSupplyPdo(_) => {
+                                contract.voltage = pdo.max_voltage();
+                                contract.current = Milliamp(operating * 10);
+                            }
+                            Pdo::Pd3p2BatterySupplyPdo(_) => {
+                                contract.voltage = pdo.max_voltage();
+                                contract.power = Milliwatt(operating * 250);
+                                contract.current = Milliamp(
+                                    (contract.power.0 * 1000)
+                                        .checked_div(contract.voltage.0)
+                                        .unwrap_or(0),
+                                );
+                            }
+                            Pdo::Pd3p2AugmentedPdo(_) => {
+                                contract.voltage = Millivolt(((rdo >> 9) & 0xfff) * 20);
+                                contract.current = Milliamp((rdo & 0x7f) * 50);
+                            }
+                        }
+                    }
+                    None => {
+                        // Without the PDO, the operating field can only be
+                        // interpreted as power in 250mW units.
+                        contract.power = Milliwatt(operating * 250);
+                    }
+                }
+            }
+            PowerOperationMode::BatteryCharging | PowerOperationMode::UsbTypeCCurrent1_5A => {
+                contract.voltage = Millivolt(5000);
+                contract.current = Milliamp(1500);
+            }
+            PowerOperationMode::UsbTypeCCurrent3A => {
+                contract.voltage = Millivolt(5000);
+                contract.current = Milliamp(3000);
+            }
+            PowerOperationMode::UsbTypeCCurrent5A => {
+                contract.voltage = Millivolt(5000);
+                contract.current = Milliamp(5000);
+            }
+            PowerOperationMode::UsbDefaultOperation => {
+                contract.voltage = Millivolt(5000);
+                contract.current = Milliamp(500);
+            }
+            PowerOperationMode::Reserved | PowerOperationMode::Reserved2 => {}
+        }
+
+        if contract.power.0 == 0 {
+            contract.power = Milliwatt(contract.voltage.0 * contract.current.0 / 1000);
+        }
+
+        if offered_current.0 == 0 {
+            offered_current = contract.current;
+        }
+
+        if let Some(cable) = optional(self.cable_properties(connector_nr))? {
+            // The cable current capability is given in 50mA units, zero
+            // meaning it was not reported.
+            let cable_current = u32::from(cable.b_current_capability) * 50;
+            contract.cable_limited = cable_current > 0 && cable_current < offered_current.0;
+        }
+
+        Ok(contract)
+    }
 }
 
 // The C API.
@@ -305,6 +665,53 @@ impl TypecRs {
         let _ = unsafe { Vec::from_raw_parts(modes, nmodes, mem_sz) };
     }
 
+    #[no_mangle]
+    /// Returns the alternate modes that are currently supported on a
+    /// connector in `out_cam_supported` and 0 on success, -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to query.
+    ///
+    /// # Safety
+    /// The caller must ensure that out_cam_supported is a valid pointer.
+    extern "C" fn libtypec_rs_get_supported_alternate_modes(
+        &mut self,
+        connector_nr: usize,
+        out_cam_supported: &mut crate::ucsi::UcsiCamSupported,
+    ) -> std::ffi::c_int {
+        match self.supported_alternate_modes(connector_nr) {
+            Ok(offsets) => {
+                *out_cam_supported = crate::ucsi::CamSupported::from_offsets(&offsets).into();
+                0
+            }
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Returns the alternate modes a connector is operating in in
+    /// `out_current_modes` and 0 on success, -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to query.
+    ///
+    /// # Safety
+    /// The caller must ensure that out_current_modes is a valid pointer.
+    extern "C" fn libtypec_rs_get_current_alternate_modes(
+        &mut self,
+        connector_nr: usize,
+        out_current_modes: &mut crate::ucsi::UcsiCurrentAlternatingModes,
+    ) -> std::ffi::c_int {
+        match self.current_alternate_modes(connector_nr) {
+            Ok(offsets) => {
+                *out_current_modes =
+                    crate::ucsi::CurrentAlternatingModes::from_offsets(&offsets).into();
+                0
+            }
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
     #[no_mangle]
     /// Returns the cable property of a connector in `out_cable_properties` and
     /// 0 on success, -errno on failure.
@@ -351,6 +758,289 @@ impl TypecRs {
         }
     }
 
+    #[no_mangle]
+    /// Returns the reasons the previous command on a connector failed in
+    /// `out_error_status` and 0 on success, -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector the previous command targeted.
+    ///
+    /// # Safety
+    /// The caller must ensure that out_error_status is a valid pointer.
+    extern "C" fn libtypec_rs_get_error_status(
+        &mut self,
+        connector_nr: usize,
+        out_error_status: &mut crate::ucsi::UcsiErrorStatus,
+    ) -> std::ffi::c_int {
+        match self.error_status(Some(connector_nr)) {
+            Ok(status) => {
+                *out_error_status = status.into();
+                0
+            }
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Returns the most recent Attention VDM received on a connector in
+    /// `out_attention_vdo` and 0 on success, -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to query.
+    ///
+    /// # Safety
+    /// The caller must ensure that out_attention_vdo is a valid pointer.
+    extern "C" fn libtypec_rs_get_attention_vdo(
+        &mut self,
+        connector_nr: usize,
+        out_attention_vdo: &mut crate::ucsi::UcsiAttentionVdo,
+    ) -> std::ffi::c_int {
+        match self.attention_vdo(connector_nr) {
+            Ok(vdo) => {
+                *out_attention_vdo = vdo.into();
+                0
+            }
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Returns the vendor, product and firmware version of the LPM behind a
+    /// connector in `out_lpm_ppm_info` and 0 on success, -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to query.
+    ///
+    /// # Safety
+    /// The caller must ensure that out_lpm_ppm_info is a valid pointer.
+    extern "C" fn libtypec_rs_get_lpm_ppm_info(
+        &mut self,
+        connector_nr: usize,
+        out_lpm_ppm_info: &mut crate::ucsi::UcsiLpmPpmInfo,
+    ) -> std::ffi::c_int {
+        match self.lpm_ppm_info(connector_nr) {
+            Ok(info) => {
+                *out_lpm_ppm_info = info.into();
+                0
+            }
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Returns the source or sink power level of a connector in
+    /// `out_power_level` and 0 on success, -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to query.
+    /// `power_direction` Whether to read the source or the sink power level.
+    ///
+    /// # Safety
+    /// The caller must ensure that out_power_level is a valid pointer.
+    extern "C" fn libtypec_rs_get_power_level(
+        &mut self,
+        connector_nr: usize,
+        power_direction: crate::ucsi::UcsiPowerDirection,
+        out_power_level: &mut crate::ucsi::UcsiPowerLevel,
+    ) -> std::ffi::c_int {
+        match self.power_level(connector_nr, power_direction.into()) {
+            Ok(level) => {
+                *out_power_level = level.into();
+                0
+            }
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Resets the PPM. Returns 0 on success, -errno on failure.
+    extern "C" fn libtypec_rs_ppm_reset(&mut self) -> std::ffi::c_int {
+        match self.control().and_then(|control| control.ppm_reset()) {
+            Ok(()) => 0,
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Resets a connector. Returns 0 on success, -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to reset.
+    /// `reset_type` Whether to perform a Hard Reset or a Data Reset.
+    extern "C" fn libtypec_rs_connector_reset(
+        &mut self,
+        connector_nr: usize,
+        reset_type: crate::ucsi::UcsiConnectorResetType,
+    ) -> std::ffi::c_int {
+        match self
+            .control()
+            .and_then(|control| control.connector_reset(connector_nr, reset_type.into()))
+        {
+            Ok(()) => 0,
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Acknowledges the indications in the CCI. Returns 0 on success, -errno
+    /// on failure.
+    ///
+    /// # Arguments
+    /// `acknowledgement` The indications to acknowledge.
+    extern "C" fn libtypec_rs_ack_cc_ci(
+        &mut self,
+        acknowledgement: crate::ucsi::UcsiAcknowledgement,
+    ) -> std::ffi::c_int {
+        match self
+            .control()
+            .and_then(|control| control.ack_cc_ci(acknowledgement.into()))
+        {
+            Ok(()) => 0,
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Enables the notifications in `notification_enable` and disables the
+    /// others. Returns 0 on success, -errno on failure.
+    extern "C" fn libtypec_rs_set_notification_enable(
+        &mut self,
+        notification_enable: crate::ucsi::UcsiNotificationEnable,
+    ) -> std::ffi::c_int {
+        match self
+            .control()
+            .and_then(|control| control.set_notification_enable(notification_enable.into()))
+        {
+            Ok(()) => 0,
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Limits the power a connector sources or sinks. Returns 0 on success,
+    /// -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to configure.
+    /// `power_direction` Whether to set the source or the sink power level.
+    /// `max_power` The maximum power in mW, or 0 to let the PPM decide.
+    /// `usb_type_c_current` The USB Type-C Current to advertise.
+    extern "C" fn libtypec_rs_set_power_level(
+        &mut self,
+        connector_nr: usize,
+        power_direction: crate::ucsi::UcsiPowerDirection,
+        max_power: u32,
+        usb_type_c_current: crate::ucsi::UcsiUsbTypeCCurrent,
+    ) -> std::ffi::c_int {
+        match self.set_power_level(
+            connector_nr,
+            power_direction.into(),
+            Milliwatt(max_power),
+            usb_type_c_current.into(),
+        ) {
+            Ok(()) => 0,
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Enables or disables the Sink Path of a connector. Returns 0 on success,
+    /// -errno on failure.
+    extern "C" fn libtypec_rs_set_sink_path(
+        &mut self,
+        connector_nr: usize,
+        enable: bool,
+    ) -> std::ffi::c_int {
+        match self.set_sink_path(connector_nr, enable) {
+            Ok(()) => 0,
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Sets the mode of the retimers of a connector. Returns 0 on success,
+    /// -errno on failure.
+    extern "C" fn libtypec_rs_set_retimer_mode(
+        &mut self,
+        connector_nr: usize,
+        retimer_mode: u32,
+    ) -> std::ffi::c_int {
+        match self.set_retimer_mode(connector_nr, retimer_mode) {
+            Ok(()) => 0,
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Passes a vendor-defined firmware update request to the LPM of a
+    /// connector. Returns 0 on success, -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to update.
+    /// `request` The request, `request_len` bytes long.
+    /// `out_response` A buffer of `*out_response_len` bytes that receives the
+    /// response. `*out_response_len` is set to the length of the response.
+    /// If the buffer is too small, nothing is written and -ENOSPC is
+    /// returned.
+    ///
+    /// # Safety
+    /// The caller must ensure that the buffers are valid for the given
+    /// lengths. A null `request` is an empty request, which is rejected, and
+    /// the response is discarded if `out_response` is null.
+    extern "C" fn libtypec_rs_fw_update_request(
+        &mut self,
+        connector_nr: usize,
+        request: *const u8,
+        request_len: usize,
+        out_response: *mut u8,
+        out_response_len: &mut usize,
+    ) -> std::ffi::c_int {
+        let request = if request.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(request, request_len) }
+        };
+        match self.fw_update_request(connector_nr, request) {
+            Ok(response) => unsafe { copy_response(&response, out_response, out_response_len) },
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
+    #[no_mangle]
+    /// Passes a security request to a connector. Returns 0 on success,
+    /// -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to query.
+    /// `request` The request, `request_len` bytes long.
+    /// `out_response` A buffer of `*out_response_len` bytes that receives the
+    /// response. `*out_response_len` is set to the length of the response.
+    /// If the buffer is too small, nothing is written and -ENOSPC is
+    /// returned.
+    ///
+    /// # Safety
+    /// The caller must ensure that the buffers are valid for the given
+    /// lengths. A null `request` is an empty request, which is rejected, and
+    /// the response is discarded if `out_response` is null.
+    extern "C" fn libtypec_rs_security_request(
+        &mut self,
+        connector_nr: usize,
+        request: *const u8,
+        request_len: usize,
+        out_response: *mut u8,
+        out_response_len: &mut usize,
+    ) -> std::ffi::c_int {
+        let request = if request.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(request, request_len) }
+        };
+        match self.security_request(connector_nr, request) {
+            Ok(response) => unsafe { copy_response(&response, out_response, out_response_len) },
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
     #[no_mangle]
     /// Returns the USB PD response message in `out_pd_message` and 0 on
     /// success, -errno on failure.
@@ -439,6 +1129,29 @@ impl TypecRs {
         }
     }
 
+    #[no_mangle]
+    /// Returns a summary of the power contract on a connector in
+    /// `out_power_contract` and 0 on success, -errno on failure.
+    ///
+    /// # Arguments
+    /// `connector_nr` The connector number to query.
+    ///
+    /// # Safety
+    /// The caller must ensure that out_power_contract is a valid pointer.
+    extern "C" fn libtypec_rs_get_power_contract(
+        &mut self,
+        connector_nr: usize,
+        out_power_contract: &mut TypeCRsPowerContract,
+    ) -> std::ffi::c_int {
+        match self.power_contract(connector_nr) {
+            Ok(contract) => {
+                *out_power_contract = contract.into();
+                0
+            }
+            Err(err) => -CError::from(err).0,
+        }
+    }
+
     #[no_mangle]
     /// Frees the memory returned by libtypec_rs_get_pdos.
     ///
@@ -453,3 +1166,44 @@ impl TypecRs {
         let _ = unsafe { Vec::from_raw_parts(pdos, npdos, mem_sz) };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::ucsi_debugfs::simulator::PpmSimulator;
+    use crate::backends::ucsi_debugfs::simulator::SimulatedConnector;
+    use crate::backends::ucsi_debugfs::UcsiDebugfsBackend;
+
+    #[test]
+    fn test_power_contract() {
+        let connector = SimulatedConnector {
+            status: ConnectorStatus {
+                power_operation_mode: PowerOperationMode::PowerDelivery,
+                connect_status: true,
+                power_direction: PowerDirection::Consumer,
+                // Object position 2, 1.5A operating current.
+                negotiated_power_level: 0x200258c8,
+                ..Default::default()
+            },
+            // 5V 3A and 9V 3A.
+            partner_source_pdos: vec![0x0801912c, 0x0002d12c],
+            ..Default::default()
+        };
+        // PD 3.2, which is what most PPMs report rather than 3.1.
+        let capability = Capability {
+            pd_version: BcdWrapper(0x320),
+            ..Default::default()
+        };
+        let ppm = PpmSimulator::new(capability, vec![connector]);
+        let mut typec =
+            TypecRs::with_backend(Box::new(UcsiDebugfsBackend::with_transport(Box::new(ppm))));
+
+        let contract = typec.power_contract(0).unwrap();
+        assert_eq!(contract.selected_pdo_position, 2);
+        assert_eq!(contract.voltage, Millivolt(9000));
+        assert_eq!(contract.current, Milliamp(1500));
+        assert_eq!(contract.power, Milliwatt(13500));
+        assert_eq!(contract.partner_max_power, Milliwatt(27000));
+        assert!(!contract.cable_limited);
+    }
+}
@@ -12,16 +12,60 @@ use proc_macros::CApiWrapper;
 use crate::bitflags_wrapper;
 use crate::pd::MessageRecipient;
 use crate::pd::MessageResponseType;
+use crate::vdo::svid;
+use crate::vdo::Vdo;
 use crate::BcdWrapper;
 use crate::BitReader;
 use crate::Error;
 use crate::FromBytes;
+use crate::Milliwatt;
 use crate::Result;
 use crate::ToBytes;
 
+pub mod commands;
+pub mod trace;
+
 /// See UCSI - Table A-2 Parameter Values
 pub const UCSI_MAX_NUM_ALT_MODE: usize = 128;
 
+/// The size of the GET_CAM_SUPPORTED bit vector, i.e. one bit per Alternate
+/// Mode.
+pub const UCSI_CAM_SUPPORTED_LEN: usize = 16;
+
+/// The UCSI revisions whose command and data layouts differ.
+///
+/// UCSI 1.2 limits MESSAGE_IN to 16 bytes and defines fewer fields in
+/// GET_CONNECTOR_CAPABILITY and GET_CONNECTOR_STATUS than later revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum UcsiVersion {
+    #[default]
+    V1_2,
+    V2_0,
+    V2_1,
+    V3_0,
+}
+
+impl UcsiVersion {
+    /// The size of MESSAGE_IN for this revision.
+    pub fn message_in_len(&self) -> usize {
+        match self {
+            UcsiVersion::V1_2 => 16,
+            _ => 256,
+        }
+    }
+}
+
+impl From<BcdWrapper> for UcsiVersion {
+    fn from(bcd: BcdWrapper) -> Self {
+        match bcd.0 {
+            0..=0x01ff => UcsiVersion::V1_2,
+            0x0200..=0x020f => UcsiVersion::V2_0,
+            0x0210..=0x02ff => UcsiVersion::V2_1,
+            _ => UcsiVersion::V3_0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
 #[c_api(prefix = "Ucsi", repr_c)]
 /// See Table 6-24: GET_ALTERNATE_MODES Command.
@@ -53,8 +97,78 @@ pub enum PdoSourceCapabilitiesType {
     MaximumSupportedSourceCapabilities,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Ucsi", repr_c)]
+/// The kind of reset requested by CONNECTOR_RESET.
+pub enum ConnectorResetType {
+    /// A USB PD Hard Reset, or a Type-C error recovery if there is no PD
+    /// contract.
+    #[default]
+    Hard = 0,
+    /// A USB PD Data Reset. Added in UCSI 2.0.
+    Data = 1,
+}
+
+bitflags_wrapper! {
+    Ucsi,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// The indications acknowledged by ACK_CC_CI.
+    pub struct Acknowledgement: u8 {
+        /// Acknowledges the Connector Change Indicator.
+        const CONNECTOR_CHANGE = 1 << 0;
+        /// Acknowledges the Command Completed Indicator.
+        const COMMAND_COMPLETED = 1 << 1;
+    }
+}
+
+bitflags_wrapper! {
+    Ucsi,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// The notifications enabled by SET_NOTIFICATION_ENABLE. See UCSI 3.0 -
+    /// Table 6-8.
+    pub struct NotificationEnable: u16 {
+        const COMMAND_COMPLETED = 1 << 0;
+        const EXTERNAL_SUPPLY_CHANGE = 1 << 1;
+        const POWER_OPERATION_MODE_CHANGE = 1 << 2;
+        const ATTENTION = 1 << 3;
+        const FW_UPDATE_REQUEST = 1 << 4;
+        const SUPPORTED_PROVIDER_CAPABILITIES_CHANGE = 1 << 5;
+        const NEGOTIATED_POWER_LEVEL_CHANGE = 1 << 6;
+        const PD_RESET_COMPLETE = 1 << 7;
+        const SUPPORTED_CAM_CHANGE = 1 << 8;
+        const BATTERY_CHARGING_STATUS_CHANGE = 1 << 9;
+        const SECURITY_REQUEST_FROM_PORT_PARTNER = 1 << 10;
+        const CONNECTOR_PARTNER_CHANGE = 1 << 11;
+        const POWER_DIRECTION_CHANGE = 1 << 12;
+        const SET_RETIMER_MODE = 1 << 13;
+        const CONNECT_CHANGE = 1 << 14;
+        const ERROR = 1 << 15;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Command {
+    /// This command is used to reset the PPM. All notifications are disabled
+    /// afterwards.
+    PpmReset,
+    /// This command is used to reset the connector identified by this
+    /// command.
+    ConnectorReset {
+        /// This field shall be set to the connector being reset.
+        connector_nr: usize,
+        /// The kind of reset.
+        reset_type: ConnectorResetType,
+    },
+    /// This command is used to acknowledge the indications in the CCI.
+    AckCcCi {
+        /// The indications being acknowledged.
+        acknowledgement: Acknowledgement,
+    },
+    /// This command is used to choose the notifications the PPM raises.
+    SetNotificationEnable {
+        /// The notifications to enable. The others are disabled.
+        notification_enable: NotificationEnable,
+    },
     /// This command is used to get the PPM capabilities.
     GetCapability,
     /// This command is used to get the capabilities of a connector.
@@ -129,21 +243,109 @@ pub enum Command {
         /// This field shall be set to the connector being queried.
         connector_nr: usize,
     },
+    /// This command is used to get the details of the error reported by the
+    /// PPM for the previous command.
+    GetErrorStatus {
+        /// The connector the previous command targeted. Only used by UCSI 2.0
+        /// and later, older PPMs expect `None`.
+        connector_nr: Option<usize>,
+    },
     /// This command is used to get the PD message from the connector
     GetPdMessage {
         /// This field shall be set to the connector being queried.
         connector_nr: u32,
         /// This field indicates the recipient of the PD message.
         recipient: MessageRecipient,
+        /// The offset in bytes into the message to start reading from.
+        offset: u32,
+        /// The number of bytes to read, bounded by the size of MESSAGE_IN. The
+        /// field is 6 bits wide.
+        nr_bytes: u32,
         /// Response message type.
         message_type: MessageResponseType,
     },
+    /// This command is used to get the VDO of the most recent Attention
+    /// message received on the connector.
+    GetAttentionVdo {
+        /// This field shall be set to the connector being queried.
+        connector_nr: usize,
+    },
+    /// This command is used to limit the power the connector sources or
+    /// sinks.
+    SetPowerLevel {
+        /// This field shall be set to the connector being configured.
+        connector_nr: usize,
+        /// Whether to set the source or the sink power level.
+        power_direction: PowerDirection,
+        /// The power level to apply.
+        power_level: PowerLevel,
+    },
+    /// This command is used to enable or disable the Sink Path of the
+    /// connector.
+    SetSinkPath {
+        /// This field shall be set to the connector being configured.
+        connector_nr: usize,
+        /// Whether the Sink Path shall be enabled.
+        enable: bool,
+    },
+    /// This command is used to pass a firmware update request to the LPM of
+    /// the connector. The request is written to MESSAGE_OUT, split in chunks
+    /// if it does not fit and the PPM supports chunking.
+    LpmFwUpdateRequest {
+        /// This field shall be set to the connector being updated.
+        connector_nr: usize,
+        /// The number of bytes of the request in MESSAGE_OUT.
+        data_length: u8,
+        /// The position of this chunk in the request, starting from 0.
+        chunk_number: u8,
+        /// Set on the last chunk of the request.
+        last_chunk: bool,
+    },
+    /// This command is used to pass a security request, e.g. an
+    /// authentication message, to the connector. MESSAGE_OUT is used as for
+    /// LPM_FW_UPDATE_REQUEST.
+    SecurityRequest {
+        /// This field shall be set to the connector being queried.
+        connector_nr: usize,
+        /// The number of bytes of the request in MESSAGE_OUT.
+        data_length: u8,
+        /// The position of this chunk in the request, starting from 0.
+        chunk_number: u8,
+        /// Set on the last chunk of the request.
+        last_chunk: bool,
+    },
+    /// This command is used to set the mode of the retimers of the
+    /// connector.
+    SetRetimerMode {
+        /// This field shall be set to the connector being configured.
+        connector_nr: usize,
+        /// The mode the retimers shall operate in.
+        retimer_mode: u32,
+    },
+    /// This command is used to read back the power level set with
+    /// SET_POWER_LEVEL.
+    ReadPowerLevel {
+        /// This field shall be set to the connector being queried.
+        connector_nr: usize,
+        /// Whether to read the source or the sink power level.
+        power_direction: PowerDirection,
+    },
+    /// This command is used to get the identity and firmware version of the
+    /// LPM behind the connector.
+    GetLpmPpmInfo {
+        /// This field shall be set to the connector being queried.
+        connector_nr: usize,
+    },
 }
 
 impl Command {
     /// See UCSI 3.0 - Table A.1
     pub fn cmd_number(&self) -> u32 {
         match &self {
+            Command::PpmReset => 0x01,
+            Command::ConnectorReset { .. } => 0x03,
+            Command::AckCcCi { .. } => 0x04,
+            Command::SetNotificationEnable { .. } => 0x05,
             Command::GetCapability => 0x06,
             Command::GetConnectorCapability { .. } => 0x07,
             Command::GetAlternateModes { .. } => 0x0c,
@@ -152,7 +354,34 @@ impl Command {
             Command::GetPdos { .. } => 0x10,
             Command::GetCableProperty { .. } => 0x11,
             Command::GetConnectorStatus { .. } => 0x12,
+            Command::GetErrorStatus { .. } => 0x13,
             Command::GetPdMessage { .. } => 0x15,
+            Command::GetAttentionVdo { .. } => 0x16,
+            Command::SetPowerLevel { .. } => 0x14,
+            Command::LpmFwUpdateRequest { .. } => 0x19,
+            Command::SecurityRequest { .. } => 0x1a,
+            Command::SetRetimerMode { .. } => 0x1b,
+            Command::SetSinkPath { .. } => 0x1c,
+            Command::ReadPowerLevel { .. } => 0x1e,
+            Command::GetLpmPpmInfo { .. } => 0x22,
+        }
+    }
+
+    /// The first UCSI revision that defines this command.
+    pub fn min_version(&self) -> UcsiVersion {
+        match self {
+            Command::GetPdMessage { .. }
+            | Command::GetAttentionVdo { .. }
+            | Command::SetRetimerMode { .. }
+            | Command::LpmFwUpdateRequest { .. }
+            | Command::SecurityRequest { .. }
+            | Command::ConnectorReset {
+                reset_type: ConnectorResetType::Data,
+                ..
+            } => UcsiVersion::V2_0,
+            Command::SetSinkPath { .. } => UcsiVersion::V2_1,
+            Command::ReadPowerLevel { .. } | Command::GetLpmPpmInfo { .. } => UcsiVersion::V3_0,
+            _ => UcsiVersion::V1_2,
         }
     }
 }
@@ -162,7 +391,28 @@ impl ToBytes for Command {
         let command = self.cmd_number();
         bw.write(8, command)?;
         match self {
-            Command::GetCapability => {}
+            Command::PpmReset | Command::GetCapability => {}
+            Command::ConnectorReset {
+                connector_nr,
+                reset_type,
+            } => {
+                // Data length
+                bw.write(8, 0)?;
+                bw.write(7, *connector_nr as u32 + 1)?;
+                bw.write(1, *reset_type as u32)?;
+            }
+            Command::AckCcCi { acknowledgement } => {
+                // Data length
+                bw.write(8, 0)?;
+                bw.write(2, acknowledgement.bits())?;
+            }
+            Command::SetNotificationEnable {
+                notification_enable,
+            } => {
+                // Data length
+                bw.write(8, 0)?;
+                bw.write(16, notification_enable.bits())?;
+            }
             Command::GetConnectorCapability { connector_nr } => {
                 // Data length
                 bw.write(8, 0)?;
@@ -220,18 +470,90 @@ impl ToBytes for Command {
                 bw.write(8, 0)?;
                 bw.write(7, *connector_nr as u32 + 1)?;
             }
+            Command::GetErrorStatus { connector_nr } => {
+                // Data length
+                bw.write(8, 0)?;
+                bw.write(7, connector_nr.map_or(0, |nr| nr as u32 + 1))?;
+            }
             Command::GetPdMessage {
                 connector_nr,
                 recipient,
+                offset,
+                nr_bytes,
                 message_type,
             } => {
                 // Data length
                 bw.write(8, 0)?;
                 bw.write(7, *connector_nr + 1)?;
                 bw.write(3, *recipient as u32)?;
-                bw.write(16, 0)?;
+                bw.write(8, *offset)?;
+                bw.write(6, *nr_bytes)?;
                 bw.write(6, *message_type as u32)?;
             }
+            Command::GetAttentionVdo { connector_nr } | Command::GetLpmPpmInfo { connector_nr } => {
+                // Data length
+                bw.write(8, 0)?;
+                bw.write(7, *connector_nr as u32 + 1)?;
+            }
+            Command::ReadPowerLevel {
+                connector_nr,
+                power_direction,
+            } => {
+                // Data length
+                bw.write(8, 0)?;
+                bw.write(7, *connector_nr as u32 + 1)?;
+                bw.write(1, *power_direction as u32)?;
+            }
+            Command::SetPowerLevel {
+                connector_nr,
+                power_direction,
+                power_level,
+            } => {
+                // Data length
+                bw.write(8, 0)?;
+                bw.write(7, *connector_nr as u32 + 1)?;
+                bw.write(1, *power_direction as u32)?;
+                power_level.to_bytes(bw)?;
+            }
+            Command::SetSinkPath {
+                connector_nr,
+                enable,
+            } => {
+                // Data length
+                bw.write(8, 0)?;
+                bw.write(7, *connector_nr as u32 + 1)?;
+                bw.write_bit(*enable)?;
+            }
+            Command::LpmFwUpdateRequest {
+                connector_nr,
+                data_length,
+                chunk_number,
+                last_chunk,
+            }
+            | Command::SecurityRequest {
+                connector_nr,
+                data_length,
+                chunk_number,
+                last_chunk,
+            } => {
+                bw.write(8, *data_length)?;
+                bw.write(7, *connector_nr as u32 + 1)?;
+                // Reserved
+                bw.write(1, 0)?;
+                bw.write(8, *chunk_number)?;
+                bw.write_bit(*last_chunk)?;
+            }
+            Command::SetRetimerMode {
+                connector_nr,
+                retimer_mode,
+            } => {
+                // Data length
+                bw.write(8, 0)?;
+                bw.write(7, *connector_nr as u32 + 1)?;
+                // Reserved
+                bw.write(9, 0)?;
+                bw.write(32, *retimer_mode)?;
+            }
         }
 
         bw.byte_align()?;
@@ -239,6 +561,225 @@ impl ToBytes for Command {
     }
 }
 
+impl FromBytes for Command {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let command = reader.read::<u32>(8)?;
+        match command {
+            0x01 => return Ok(Command::PpmReset),
+            0x06 => return Ok(Command::GetCapability),
+            _ => {}
+        }
+        let data_length = reader.read::<u8>(8)?;
+
+        // The remaining commands address a connector, numbered from 1.
+        let read_connector_nr = |reader: &mut BitReader| -> Result<usize> {
+            let connector_nr = reader.read::<u32>(7)?;
+            connector_nr
+                .checked_sub(1)
+                .map(|nr| nr as usize)
+                .ok_or_else(|| Error::ParseError {
+                    field: "connector_nr".into(),
+                    value: connector_nr,
+                    #[cfg(feature = "backtrace")]
+                    backtrace: std::backtrace::Backtrace::capture(),
+                })
+        };
+
+        match command {
+            0x03 => {
+                let connector_nr = read_connector_nr(reader)?;
+                let reset_type = if reader.read_bit()? {
+                    ConnectorResetType::Data
+                } else {
+                    ConnectorResetType::Hard
+                };
+                Ok(Command::ConnectorReset {
+                    connector_nr,
+                    reset_type,
+                })
+            }
+            0x04 => Ok(Command::AckCcCi {
+                acknowledgement: Acknowledgement::from_bits_truncate(reader.read::<u8>(2)?),
+            }),
+            0x05 => Ok(Command::SetNotificationEnable {
+                notification_enable: NotificationEnable::from_bits_retain(reader.read::<u16>(16)?),
+            }),
+            0x07 => Ok(Command::GetConnectorCapability {
+                connector_nr: read_connector_nr(reader)?,
+            }),
+            0x0c => {
+                let recipient = reader.read::<u32>(3)?;
+                let recipient =
+                    GetAlternateModesRecipient::n(recipient).ok_or_else(|| Error::ParseError {
+                        field: "recipient".into(),
+                        value: recipient,
+                        #[cfg(feature = "backtrace")]
+                        backtrace: std::backtrace::Backtrace::capture(),
+                    })?;
+                // Reserved
+                reader.skip(5)?;
+                let connector_nr = read_connector_nr(reader)?;
+                // Reserved
+                reader.skip(1)?;
+                let offset = reader.read::<u32>(8)? as usize;
+                Ok(Command::GetAlternateModes {
+                    recipient,
+                    connector_nr,
+                    offset,
+                })
+            }
+            0x0d => Ok(Command::GetCamSupported {
+                connector_nr: read_connector_nr(reader)?,
+            }),
+            0x0e => Ok(Command::GetCurrentCam {
+                connector_nr: read_connector_nr(reader)?,
+            }),
+            0x10 => {
+                let connector_nr = read_connector_nr(reader)?;
+                let partner_pdo = reader.read_bit()?;
+                let pdo_offset = reader.read::<u32>(8)?;
+                let nr_pdos = reader.read::<u32>(2)? as usize;
+                let pdo_type = if reader.read_bit()? {
+                    PdoType::Source
+                } else {
+                    PdoType::Sink
+                };
+                let source_capabilities_type = reader.read::<u32>(2)?;
+                let source_capabilities_type =
+                    PdoSourceCapabilitiesType::n(source_capabilities_type).ok_or_else(|| {
+                        Error::ParseError {
+                            field: "source_capabilities_type".into(),
+                            value: source_capabilities_type,
+                            #[cfg(feature = "backtrace")]
+                            backtrace: std::backtrace::Backtrace::capture(),
+                        }
+                    })?;
+                Ok(Command::GetPdos {
+                    connector_nr,
+                    partner_pdo,
+                    pdo_offset,
+                    nr_pdos,
+                    pdo_type,
+                    source_capabilities_type,
+                })
+            }
+            0x11 => Ok(Command::GetCableProperty {
+                connector_nr: read_connector_nr(reader)?,
+            }),
+            0x12 => Ok(Command::GetConnectorStatus {
+                connector_nr: read_connector_nr(reader)?,
+            }),
+            0x13 => {
+                let connector_nr = reader.read::<u32>(7)?;
+                Ok(Command::GetErrorStatus {
+                    connector_nr: connector_nr.checked_sub(1).map(|nr| nr as usize),
+                })
+            }
+            0x15 => {
+                let connector_nr = read_connector_nr(reader)? as u32;
+                let recipient = reader.read::<u32>(3)?;
+                let recipient =
+                    MessageRecipient::n(recipient).ok_or_else(|| Error::ParseError {
+                        field: "recipient".into(),
+                        value: recipient,
+                        #[cfg(feature = "backtrace")]
+                        backtrace: std::backtrace::Backtrace::capture(),
+                    })?;
+                let offset = reader.read::<u32>(8)?;
+                let nr_bytes = reader.read::<u32>(6)?;
+                let message_type = reader.read::<u32>(6)?;
+                let message_type =
+                    MessageResponseType::n(message_type).ok_or_else(|| Error::ParseError {
+                        field: "message_type".into(),
+                        value: message_type,
+                        #[cfg(feature = "backtrace")]
+                        backtrace: std::backtrace::Backtrace::capture(),
+                    })?;
+                Ok(Command::GetPdMessage {
+                    connector_nr,
+                    recipient,
+                    offset,
+                    nr_bytes,
+                    message_type,
+                })
+            }
+            0x16 => Ok(Command::GetAttentionVdo {
+                connector_nr: read_connector_nr(reader)?,
+            }),
+            0x14 => {
+                let connector_nr = read_connector_nr(reader)?;
+                let power_direction = if reader.read_bit()? {
+                    PowerDirection::Provider
+                } else {
+                    PowerDirection::Consumer
+                };
+                let power_level = PowerLevel::from_bytes(reader)?;
+                Ok(Command::SetPowerLevel {
+                    connector_nr,
+                    power_direction,
+                    power_level,
+                })
+            }
+            0x19 | 0x1a => {
+                let connector_nr = read_connector_nr(reader)?;
+                // Reserved
+                reader.skip(1)?;
+                let chunk_number = reader.read::<u8>(8)?;
+                let last_chunk = reader.read_bit()?;
+                if command == 0x19 {
+                    Ok(Command::LpmFwUpdateRequest {
+                        connector_nr,
+                        data_length,
+                        chunk_number,
+                        last_chunk,
+                    })
+                } else {
+                    Ok(Command::SecurityRequest {
+                        connector_nr,
+                        data_length,
+                        chunk_number,
+                        last_chunk,
+                    })
+                }
+            }
+            0x1b => {
+                let connector_nr = read_connector_nr(reader)?;
+                // Reserved
+                reader.skip(9)?;
+                Ok(Command::SetRetimerMode {
+                    connector_nr,
+                    retimer_mode: reader.read::<u32>(32)?,
+                })
+            }
+            0x1c => Ok(Command::SetSinkPath {
+                connector_nr: read_connector_nr(reader)?,
+                enable: reader.read_bit()?,
+            }),
+            0x1e => {
+                let connector_nr = read_connector_nr(reader)?;
+                let power_direction = if reader.read_bit()? {
+                    PowerDirection::Provider
+                } else {
+                    PowerDirection::Consumer
+                };
+                Ok(Command::ReadPowerLevel {
+                    connector_nr,
+                    power_direction,
+                })
+            }
+            0x22 => Ok(Command::GetLpmPpmInfo {
+                connector_nr: read_connector_nr(reader)?,
+            }),
+            _ => Err(Error::ParseError {
+                field: "command".into(),
+                value: command,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
 #[c_api(prefix = "Ucsi", repr_c)]
 /// This struct represents the GET_CONNECTOR_STATUS data.
@@ -302,6 +843,153 @@ pub struct ConnectorStatus {
     pub voltage_reading: u32,
 }
 
+impl FromBytes for ConnectorStatus {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let connector_status_change = ConnectorStatusChange::from_bytes(reader)?;
+        let power_operation_mode = reader.read::<u32>(3)?;
+        let power_operation_mode =
+            PowerOperationMode::n(power_operation_mode).ok_or_else(|| Error::ParseError {
+                field: "power_operation_mode".into(),
+                value: power_operation_mode,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })?;
+        let connect_status = reader.read_bit()?;
+        let power_direction = if reader.read_bit()? {
+            PowerDirection::Provider
+        } else {
+            PowerDirection::Consumer
+        };
+        let connector_partner_flags = reader.read::<u32>(8)?;
+        let connector_partner_type = reader.read::<u32>(3)?;
+        let connector_partner_type =
+            ConnectorPartnerType::n(connector_partner_type).ok_or_else(|| Error::ParseError {
+                field: "connector_partner_type".into(),
+                value: connector_partner_type,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })?;
+        let negotiated_power_level = reader.read::<u32>(32)?;
+        let battery_charging_capability_status = reader.read::<u32>(2)?;
+        let battery_charging_capability_status = BatteryChargingCapabilityStatus::n(
+            battery_charging_capability_status,
+        )
+        .ok_or_else(|| Error::ParseError {
+            field: "battery_charging_capability_status".into(),
+            value: battery_charging_capability_status,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+        let provider_capabilities_limited_reason = reader.read::<u32>(4)?;
+        let pd_version_operation_mode = reader.read::<u32>(16)?;
+        let orientation = if reader.read_bit()? {
+            ConnectorOrientation::Reverse
+        } else {
+            ConnectorOrientation::Normal
+        };
+        let sink_path_status = if reader.read_bit()? {
+            SinkPathStatus::Ready
+        } else {
+            SinkPathStatus::NotReady
+        };
+        let reverse_current_protection_status = reader.read_bit()?;
+
+        // The power readings were added in UCSI 3.0 and do not fit in the 16
+        // bytes of MESSAGE_IN of older PPMs. They are left at zero if missing.
+        let mut read_power_reading = || -> Result<(bool, u32, u32, u32, u32, u32)> {
+            Ok((
+                reader.read_bit()?,
+                reader.read::<u32>(4)?,
+                reader.read::<u32>(16)?,
+                reader.read::<u32>(16)?,
+                reader.read::<u32>(4)?,
+                reader.read::<u32>(16)?,
+            ))
+        };
+        let (
+            power_reading_ready,
+            scale_current,
+            peak_current,
+            average_current,
+            scale_voltage,
+            voltage_reading,
+        ) = read_power_reading().unwrap_or_default();
+
+        Ok(Self {
+            connector_status_change,
+            power_operation_mode,
+            connect_status,
+            power_direction,
+            connector_partner_flags,
+            connector_partner_type,
+            negotiated_power_level,
+            battery_charging_capability_status,
+            provider_capabilities_limited_reason,
+            pd_version_operation_mode,
+            orientation,
+            sink_path_status,
+            reverse_current_protection_status,
+            power_reading_ready,
+            scale_current,
+            peak_current,
+            average_current,
+            scale_voltage,
+            voltage_reading,
+        })
+    }
+}
+
+impl ConnectorStatus {
+    /// Decodes a GET_CONNECTOR_STATUS response laid out as defined by
+    /// `version`. Fields introduced by later revisions are left at their
+    /// defaults, as older PPMs may report them as reserved bits.
+    pub fn from_bytes_for_version(reader: &mut BitReader, version: UcsiVersion) -> Result<Self> {
+        let mut status = Self::from_bytes(reader)?;
+        if version < UcsiVersion::V2_0 {
+            status.pd_version_operation_mode = 0;
+            status.orientation = ConnectorOrientation::default();
+        }
+        if version < UcsiVersion::V2_1 {
+            status.sink_path_status = SinkPathStatus::default();
+            status.reverse_current_protection_status = false;
+        }
+        if version < UcsiVersion::V3_0 {
+            status.power_reading_ready = false;
+            status.scale_current = 0;
+            status.peak_current = 0;
+            status.average_current = 0;
+            status.scale_voltage = 0;
+            status.voltage_reading = 0;
+        }
+        Ok(status)
+    }
+}
+
+impl ToBytes for ConnectorStatus {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        self.connector_status_change.to_bytes(bw)?;
+        bw.write(3, self.power_operation_mode as u32)?;
+        bw.write_bit(self.connect_status)?;
+        bw.write(1, self.power_direction as u32)?;
+        bw.write(8, self.connector_partner_flags)?;
+        bw.write(3, self.connector_partner_type as u32)?;
+        bw.write(32, self.negotiated_power_level)?;
+        bw.write(2, self.battery_charging_capability_status as u32)?;
+        bw.write(4, self.provider_capabilities_limited_reason)?;
+        bw.write(16, self.pd_version_operation_mode)?;
+        bw.write(1, self.orientation as u32)?;
+        bw.write(1, self.sink_path_status as u32)?;
+        bw.write_bit(self.reverse_current_protection_status)?;
+        bw.write_bit(self.power_reading_ready)?;
+        bw.write(4, self.scale_current)?;
+        bw.write(16, self.peak_current)?;
+        bw.write(16, self.average_current)?;
+        bw.write(4, self.scale_voltage)?;
+        bw.write(16, self.voltage_reading)?;
+        Ok(())
+    }
+}
+
 /// Connector Status Change Field Description for GET_CONNECTOR_STATUS. See
 /// UCSI Table 6-44 for more information.
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
@@ -349,6 +1037,47 @@ pub struct ConnectorStatusChange {
     pub connector_partner_changed: bool,
 }
 
+impl FromBytes for ConnectorStatusChange {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let status_change = Self {
+            reserved1: reader.read_bit()?,
+            external_supply_change: reader.read_bit()?,
+            power_operation_mode_change: reader.read_bit()?,
+            attention: reader.read_bit()?,
+            reserved2: reader.read_bit()?,
+            supported_provider_capabilities_change: reader.read_bit()?,
+            negotiated_power_level_change: reader.read_bit()?,
+            pd_reset_complete: reader.read_bit()?,
+            supported_cam_change: reader.read_bit()?,
+            battery_charging_status_change: reader.read_bit()?,
+            reserved3: reader.read_bit()?,
+            connector_partner_changed: reader.read_bit()?,
+        };
+        // Bits 12-15 are not modeled yet.
+        reader.skip(4)?;
+        Ok(status_change)
+    }
+}
+
+impl ToBytes for ConnectorStatusChange {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write_bit(self.reserved1)?;
+        bw.write_bit(self.external_supply_change)?;
+        bw.write_bit(self.power_operation_mode_change)?;
+        bw.write_bit(self.attention)?;
+        bw.write_bit(self.reserved2)?;
+        bw.write_bit(self.supported_provider_capabilities_change)?;
+        bw.write_bit(self.negotiated_power_level_change)?;
+        bw.write_bit(self.pd_reset_complete)?;
+        bw.write_bit(self.supported_cam_change)?;
+        bw.write_bit(self.battery_charging_status_change)?;
+        bw.write_bit(self.reserved3)?;
+        bw.write_bit(self.connector_partner_changed)?;
+        bw.write(4, 0)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
 #[c_api(prefix = "Ucsi", repr_c)]
 pub enum ConnectorOrientation {
@@ -542,6 +1271,22 @@ impl FromBytes for CableProperty {
     }
 }
 
+impl ToBytes for CableProperty {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write(2, self.speed_exponent as u32)?;
+        bw.write(14, u32::from(self.speed_mantissa))?;
+        bw.write(8, u32::from(self.b_current_capability))?;
+        bw.write_bit(self.vbus_in_cable)?;
+        bw.write(1, self.cable_type as u32)?;
+        bw.write(1, self.directionality as u32)?;
+        bw.write(2, self.plug_end_type as u32)?;
+        bw.write_bit(self.mode_support)?;
+        bw.write(2, u32::from(self.cable_pd_revision))?;
+        bw.write(4, u32::from(self.latency))?;
+        Ok(())
+    }
+}
+
 /// The response to a GET_ALTERNATE_MODES command.
 ///
 /// See USCI 3.0 - Table 6.26.
@@ -568,13 +1313,42 @@ impl FromBytes for AlternateMode {
     }
 }
 
+impl ToBytes for AlternateMode {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write(16, self.svid[0])?;
+        bw.write(32, self.vdo[0])?;
+        bw.write(16, self.svid[1])?;
+        bw.write(32, self.vdo[1])?;
+        Ok(())
+    }
+}
+
+impl AlternateMode {
+    /// Returns the name registered for the SVID of the first mode, if any.
+    /// See [`crate::vdo::svid`].
+    pub fn name(&self) -> Option<String> {
+        svid::name(self.svid[0])
+    }
+
+    /// Decodes the VDO of the first mode with the decoder registered for its
+    /// SVID. `recipient` must be the recipient the mode was retrieved from.
+    /// Returns `None` if no decoder is registered.
+    pub fn decode(&self, recipient: GetAlternateModesRecipient) -> Result<Option<Vdo>> {
+        svid::decode(self.svid[0], self.vdo[0], recipient)
+    }
+}
+
 impl std::fmt::Debug for AlternateMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let vdo = format!("{:#08x}", self.vdo[0]);
-        f.debug_struct("UcsiAlternateMode")
-            .field("svid", &self.svid[0])
-            .field("vdo", &vdo)
-            .finish()
+        let mut debug = f.debug_struct("UcsiAlternateMode");
+        debug.field("svid", &self.svid[0]);
+        // The recipient is not part of the mode, so it is decoded as a mode
+        // of the port partner.
+        match self.decode(GetAlternateModesRecipient::Sop) {
+            Ok(Some(vdo)) => debug.field("vdo", &vdo),
+            _ => debug.field("vdo", &format_args!("{:#08x}", self.vdo[0])),
+        };
+        debug.finish()
     }
 }
 
@@ -582,10 +1356,58 @@ impl std::fmt::Debug for AlternateMode {
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
 #[c_api(prefix = "Ucsi", repr_c)]
 pub struct CamSupported {
-    /// Whether an alternate mode is supported.
-    pub cam_supported: bool,
+    /// A bit vector with one bit per Alternate Mode in the list returned by
+    /// GET_ALTERNATE_MODES for the connector. A bit is set if the mode is
+    /// currently supported.
+    #[c_api(no_prefix)]
+    pub cam_supported: [u8; UCSI_CAM_SUPPORTED_LEN],
 }
 
+impl FromBytes for CamSupported {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let mut cam_supported = [0; UCSI_CAM_SUPPORTED_LEN];
+        reader.read_bytes(&mut cam_supported)?;
+        Ok(Self { cam_supported })
+    }
+}
+
+impl ToBytes for CamSupported {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write_bytes(&self.cam_supported)?;
+        Ok(())
+    }
+}
+
+impl CamSupported {
+    /// Builds the bit vector from offsets into the list of Alternate Modes.
+    /// Offsets that do not fit are ignored.
+    pub fn from_offsets(offsets: &[usize]) -> Self {
+        let mut cam_supported = Self::default();
+        for offset in offsets
+            .iter()
+            .filter(|offset| **offset < UCSI_MAX_NUM_ALT_MODE)
+        {
+            cam_supported.cam_supported[offset / 8] |= 1 << (offset % 8);
+        }
+        cam_supported
+    }
+
+    /// Returns whether the Alternate Mode at `offset` is supported.
+    pub fn is_supported(&self, offset: usize) -> bool {
+        self.cam_supported
+            .get(offset / 8)
+            .is_some_and(|byte| byte & (1 << (offset % 8)) != 0)
+    }
+
+    /// Returns the offsets of the supported Alternate Modes.
+    pub fn offsets(&self) -> Vec<usize> {
+        (0..UCSI_MAX_NUM_ALT_MODE)
+            .filter(|offset| self.is_supported(*offset))
+            .collect()
+    }
+}
+
+/// See UCSI - Table 6-30: GET_CURRENT_CAM Data
 #[derive(Debug, Clone, PartialEq, CApiWrapper)]
 #[c_api(prefix = "Ucsi", repr_c)]
 pub struct CurrentAlternatingModes {
@@ -597,13 +1419,67 @@ pub struct CurrentAlternatingModes {
     /// set this field to 0xFF.
     #[c_api(no_prefix)]
     pub current_alternate_mode: [usize; UCSI_MAX_NUM_ALT_MODE],
+    /// The number of valid entries in `current_alternate_mode`.
+    pub num_alternate_modes: usize,
 }
 
 impl Default for CurrentAlternatingModes {
     fn default() -> Self {
         Self {
             current_alternate_mode: [0; UCSI_MAX_NUM_ALT_MODE],
+            num_alternate_modes: 0,
+        }
+    }
+}
+
+impl FromBytes for CurrentAlternatingModes {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        // The list ends with 0xFF or with MESSAGE_IN. PPMs that report a
+        // single mode leave the following bytes zeroed, so the list also ends
+        // at the first offset that is not greater than the previous one.
+        let mut offsets: Vec<usize> = vec![];
+        while offsets.len() < UCSI_MAX_NUM_ALT_MODE {
+            let Ok(offset) = reader.read::<u8>(8) else {
+                break;
+            };
+            let offset = usize::from(offset);
+            if offset == 0xff || offsets.last().is_some_and(|last| offset <= *last) {
+                break;
+            }
+            offsets.push(offset);
+        }
+
+        Ok(Self::from_offsets(&offsets))
+    }
+}
+
+impl ToBytes for CurrentAlternatingModes {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        for offset in self.offsets() {
+            bw.write(8, *offset as u32)?;
         }
+        if self.num_alternate_modes == 0 {
+            bw.write(8, 0xff)?;
+        }
+        Ok(())
+    }
+}
+
+impl CurrentAlternatingModes {
+    /// Builds the list from offsets into the list of Alternate Modes.
+    pub fn from_offsets(offsets: &[usize]) -> Self {
+        let mut modes = Self::default();
+        for (mode, offset) in modes.current_alternate_mode.iter_mut().zip(offsets) {
+            *mode = *offset;
+            modes.num_alternate_modes += 1;
+        }
+        modes
+    }
+
+    /// Returns the offsets of the Alternate Modes the connector is operating
+    /// in.
+    pub fn offsets(&self) -> &[usize] {
+        &self.current_alternate_mode[..self.num_alternate_modes]
     }
 }
 
@@ -623,25 +1499,29 @@ bitflags_wrapper! {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
-#[c_api(prefix = "Ucsi", repr_c)]
-/// Connector capability data extended operation mode.
-pub enum ConnectorCapabilityExtendedOperationMode {
-    #[default]
-    Usb4Gen2,
-    EprSource,
-    EprSink,
-    Usb4Gen3,
-    Usb4Gen4,
+bitflags_wrapper! {
+    Ucsi,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// Connector capability data extended operation mode.
+    pub struct ConnectorCapabilityExtendedOperationMode: u8 {
+        const USB4_GEN2 = 1 << 0;
+        const EPR_SOURCE = 1 << 1;
+        const EPR_SINK = 1 << 2;
+        const USB4_GEN3 = 1 << 3;
+        const USB4_GEN4 = 1 << 4;
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
-#[c_api(prefix = "Ucsi", repr_c)]
-/// Connector capability data miscellaneous capabilities.
-pub enum ConnectorCapabilityMiscellaneousCapabilities {
-    #[default]
-    FwUpdate,
-    Security,
+bitflags_wrapper! {
+    Ucsi,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// Connector capability data miscellaneous capabilities.
+    pub struct ConnectorCapabilityMiscellaneousCapabilities: u8 {
+        /// The connector supports FW_UPDATE_REQUEST.
+        const FW_UPDATE = 1 << 0;
+        /// The connector supports SECURITY_REQUEST.
+        const SECURITY = 1 << 1;
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
@@ -676,7 +1556,10 @@ pub struct ConnectorCapability {
     /// This bit is valid only when the operation mode is DRP. This bit shall be
     /// set to one if the connector is capable of accepting swap to SNK.
     pub swap_to_snk: bool,
+    /// The additional modes supported by the connector. Any combination of
+    /// them may be set.
     pub extended_operation_mode: ConnectorCapabilityExtendedOperationMode,
+    /// The optional requests the connector accepts.
     pub miscellaneous_capabilities: ConnectorCapabilityMiscellaneousCapabilities,
     /// This is debug level information. True if the LPM supports this feature.
     /// Otherwise, false.
@@ -702,27 +1585,15 @@ impl FromBytes for ConnectorCapability {
         let swap_to_ufp = reader.read_bit()?;
         let swap_to_src = reader.read_bit()?;
         let swap_to_snk = reader.read_bit()?;
-        let extended_operation_mode_value = reader.read::<u32>(8)?;
-        let extended_operation_mode = ConnectorCapabilityExtendedOperationMode::n(
-            extended_operation_mode_value,
-        )
-        .ok_or_else(|| Error::ParseError {
-            field: "extended_operation_mode".into(),
-            value: extended_operation_mode_value,
-            #[cfg(feature = "backtrace")]
-            backtrace: std::backtrace::Backtrace::capture(),
-        })?;
-        let miscellaneous_capabilities_value = reader.read::<u32>(4)?;
+        // Reserved bits are ignored, so that PPMs implementing a later
+        // revision can still be parsed.
+        let extended_operation_mode =
+            ConnectorCapabilityExtendedOperationMode::from_bits_truncate(reader.read::<u8>(8)?);
         let miscellaneous_capabilities =
-            ConnectorCapabilityMiscellaneousCapabilities::n(miscellaneous_capabilities_value)
-                .ok_or_else(|| Error::ParseError {
-                    field: "miscellaneous_capabilities".into(),
-                    value: miscellaneous_capabilities_value,
-                    #[cfg(feature = "backtrace")]
-                    backtrace: std::backtrace::Backtrace::capture(),
-                })?;
+            ConnectorCapabilityMiscellaneousCapabilities::from_bits_truncate(reader.read::<u8>(4)?);
         let reverse_current_protection_support = reader.read_bit()?;
         let partner_pd_revision = reader.read::<u8>(2)?;
+        reader.skip(3)?; // Skip reserved bits
 
         Ok(Self {
             operation_mode,
@@ -740,6 +1611,46 @@ impl FromBytes for ConnectorCapability {
     }
 }
 
+impl ConnectorCapability {
+    /// Decodes a GET_CONNECTOR_CAPABILITY response laid out as defined by
+    /// `version`. Fields introduced by later revisions are left at their
+    /// defaults.
+    pub fn from_bytes_for_version(reader: &mut BitReader, version: UcsiVersion) -> Result<Self> {
+        let mut capability = Self::from_bytes(reader)?;
+        if version < UcsiVersion::V2_0 {
+            capability.swap_to_dfp = false;
+            capability.swap_to_ufp = false;
+            capability.swap_to_src = false;
+            capability.swap_to_snk = false;
+            capability.extended_operation_mode = Default::default();
+            capability.miscellaneous_capabilities = Default::default();
+        }
+        if version < UcsiVersion::V2_1 {
+            capability.reverse_current_protection_support = false;
+            capability.partner_pd_revision = 0;
+        }
+        Ok(capability)
+    }
+}
+
+impl ToBytes for ConnectorCapability {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write(8, self.operation_mode.bits())?;
+        bw.write_bit(self.provider)?;
+        bw.write_bit(self.consumer)?;
+        bw.write_bit(self.swap_to_dfp)?;
+        bw.write_bit(self.swap_to_ufp)?;
+        bw.write_bit(self.swap_to_src)?;
+        bw.write_bit(self.swap_to_snk)?;
+        bw.write(8, self.extended_operation_mode.bits())?;
+        bw.write(4, self.miscellaneous_capabilities.bits())?;
+        bw.write_bit(self.reverse_current_protection_support)?;
+        bw.write(2, u32::from(self.partner_pd_revision))?;
+        bw.write(3, 0)?; // Reserved
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
 #[c_api(prefix = "Ucsi", repr_c)]
 pub struct Capability {
@@ -778,6 +1689,11 @@ pub struct Capability {
     /// This field shall only be valid if the device indicates that it supports
     /// USB Type -C in the bmAttributes field.
     pub usb_type_c_version: BcdWrapper,
+    /// The UCSI revision implemented by the PPM (bcdUCSIVersion).
+    ///
+    /// This is not part of the GET_CAPABILITY data. It is filled in by the
+    /// backend and is zero if the revision could not be determined.
+    pub ucsi_version: BcdWrapper,
 }
 
 impl FromBytes for Capability {
@@ -800,10 +1716,26 @@ impl FromBytes for Capability {
             bc_version,
             pd_version,
             usb_type_c_version,
+            ucsi_version: BcdWrapper::default(),
         })
     }
 }
 
+impl ToBytes for Capability {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        self.bm_attributes.to_bytes(bw)?;
+        bw.write(7, self.num_connectors as u32)?;
+        bw.write(1, 0)?; // Reserved
+        self.bm_optional_features.to_bytes(bw)?;
+        bw.write(8, self.num_alt_modes as u32)?;
+        bw.write(8, 0)?; // Reserved
+        bw.write(16, self.bc_version.0)?;
+        bw.write(16, self.pd_version.0)?;
+        bw.write(16, self.usb_type_c_version.0)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
 #[c_api(prefix = "Ucsi", repr_c)]
 pub struct BmAttributes {
@@ -845,6 +1777,20 @@ impl FromBytes for BmAttributes {
     }
 }
 
+impl ToBytes for BmAttributes {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write_bit(self.disabled_state_support)?;
+        bw.write_bit(self.battery_charging)?;
+        bw.write_bit(self.usb_power_delivery)?;
+        bw.write(3, 0)?; // Reserved
+        bw.write_bit(self.usb_type_c_current)?;
+        bw.write(1, 0)?; // Reserved
+        self.bm_power_source.to_bytes(bw)?;
+        bw.write(16, 0)?; // Reserved
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
 #[c_api(prefix = "Ucsi", repr_c)]
 pub struct BmOptionalFeatures {
@@ -927,6 +1873,28 @@ impl FromBytes for BmOptionalFeatures {
     }
 }
 
+impl ToBytes for BmOptionalFeatures {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write_bit(self.set_ccom_supported)?;
+        bw.write_bit(self.set_power_level_supported)?;
+        bw.write_bit(self.alternate_mode_details_supported)?;
+        bw.write_bit(self.alternate_mode_override_supported)?;
+        bw.write_bit(self.pdo_details_supported)?;
+        bw.write_bit(self.cable_details_supported)?;
+        bw.write_bit(self.external_supply_notification_supported)?;
+        bw.write_bit(self.pd_reset_notification_supported)?;
+        bw.write_bit(self.get_pd_message_supported)?;
+        bw.write_bit(self.get_attention_vdo_supported)?;
+        bw.write_bit(self.fw_update_request_supported)?;
+        bw.write_bit(self.negotiated_power_level_change_supported)?;
+        bw.write_bit(self.security_request_supported)?;
+        bw.write_bit(self.set_retimer_mode_supported)?;
+        bw.write_bit(self.chunking_supported)?;
+        bw.write(9, 0)?; // Reserved
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
 #[c_api(prefix = "Ucsi", repr_c)]
 pub struct BmPowerSource {
@@ -951,3 +1919,362 @@ impl FromBytes for BmPowerSource {
         })
     }
 }
+
+impl ToBytes for BmPowerSource {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write_bit(self.ac_supply)?;
+        bw.write(1, 0)?; // Reserved
+        bw.write_bit(self.other)?;
+        bw.write(3, 0)?; // Reserved
+        bw.write_bit(self.uses_vbus)?;
+        bw.write(1, 0)?; // Reserved
+        Ok(())
+    }
+}
+
+/// The Command Status and Connector Change Indication data structure, written
+/// by the PPM to notify the OPM. See UCSI 3.0 - Table 3-1.
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Ucsi", repr_c)]
+pub struct Cci {
+    /// Set when the PPM has no more data to return in MESSAGE_IN. Reserved
+    /// before UCSI 3.0.
+    pub end_of_message: bool,
+    /// The connector that had a status change, starting from 1, or 0 if no
+    /// connector changed.
+    pub connector_change: u8,
+    /// The number of valid bytes in MESSAGE_IN.
+    pub data_length: u8,
+    /// Set when the PPM received a vendor defined message.
+    pub vendor_defined_message: bool,
+    /// Set when the PPM has a security request pending.
+    pub security_request: bool,
+    /// Set when the PPM has a firmware update request pending.
+    pub fw_update_request: bool,
+    /// Set when the PPM does not support the command.
+    pub not_supported: bool,
+    /// Set when the PPM completed a CANCEL command.
+    pub cancel_completed: bool,
+    /// Set when the PPM completed a PPM_RESET command.
+    pub reset_completed: bool,
+    /// Set when the PPM is busy and cannot process the command.
+    pub busy: bool,
+    /// Set when the PPM completed an ACK_CC_CI command.
+    pub ack_command_completed: bool,
+    /// Set when the command failed. The details can be retrieved with
+    /// GET_ERROR_STATUS.
+    pub error: bool,
+    /// Set when the PPM completed a command.
+    pub command_completed: bool,
+}
+
+impl FromBytes for Cci {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let end_of_message = reader.read_bit()?;
+        let connector_change = reader.read::<u8>(7)?;
+        let data_length = reader.read::<u8>(8)?;
+        let vendor_defined_message = reader.read_bit()?;
+        reader.skip(6)?; // Skip reserved bits
+        let security_request = reader.read_bit()?;
+        let fw_update_request = reader.read_bit()?;
+        let not_supported = reader.read_bit()?;
+        let cancel_completed = reader.read_bit()?;
+        let reset_completed = reader.read_bit()?;
+        let busy = reader.read_bit()?;
+        let ack_command_completed = reader.read_bit()?;
+        let error = reader.read_bit()?;
+        let command_completed = reader.read_bit()?;
+
+        Ok(Self {
+            end_of_message,
+            connector_change,
+            data_length,
+            vendor_defined_message,
+            security_request,
+            fw_update_request,
+            not_supported,
+            cancel_completed,
+            reset_completed,
+            busy,
+            ack_command_completed,
+            error,
+            command_completed,
+        })
+    }
+}
+
+impl Cci {
+    /// Returns the valid part of MESSAGE_IN.
+    pub fn message_in<'a>(&self, message_in: &'a [u8]) -> Result<&'a [u8]> {
+        message_in
+            .get(..usize::from(self.data_length))
+            .ok_or_else(|| Error::ParseError {
+                field: "data_length".into(),
+                value: self.data_length.into(),
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })
+    }
+}
+
+/// The outcome of a command sent as is to the PPM.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RawResponse {
+    /// The command status reported by the PPM, or `None` if the backend
+    /// cannot read the CCI.
+    pub cci: Option<Cci>,
+    /// The contents of MESSAGE_IN. Empty if the command did not complete
+    /// successfully.
+    pub message_in: Vec<u8>,
+}
+
+bitflags_wrapper! {
+    Ucsi,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// The reasons a command failed. See UCSI 3.0 - Table 6-47.
+    pub struct ErrorInformation: u16 {
+        const UNRECOGNIZED_COMMAND = 1 << 0;
+        const NON_EXISTENT_CONNECTOR_NUMBER = 1 << 1;
+        const INVALID_COMMAND_SPECIFIC_PARAMETERS = 1 << 2;
+        const INCOMPATIBLE_CONNECTOR_PARTNER = 1 << 3;
+        const CC_COMMUNICATION_ERROR = 1 << 4;
+        const COMMAND_UNSUCCESSFUL_DUE_TO_DEAD_BATTERY = 1 << 5;
+        const CONTRACT_NEGOTIATION_FAILURE = 1 << 6;
+        const OVERCURRENT = 1 << 7;
+        const UNDEFINED = 1 << 8;
+        const PORT_PARTNER_REJECTED_SWAP = 1 << 9;
+        const HARD_RESET = 1 << 10;
+        const PPM_POLICY_CONFLICT = 1 << 11;
+        const SWAP_REJECTED = 1 << 12;
+        const REVERSE_CURRENT_PROTECTION = 1 << 13;
+        const SET_SINK_PATH_REJECTED = 1 << 14;
+    }
+}
+
+/// The response to a `GET_ERROR_STATUS` command.
+/// See UCSI 3.0 - Table 6-46: GET_ERROR_STATUS Data
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Ucsi", repr_c)]
+pub struct ErrorStatus {
+    /// The reasons the previous command failed.
+    pub error_information: ErrorInformation,
+}
+
+impl FromBytes for ErrorStatus {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let error_information_value = reader.read::<u16>(16)?;
+        // Later revisions may define more reasons.
+        let error_information = ErrorInformation::from_bits_truncate(error_information_value);
+
+        Ok(Self { error_information })
+    }
+}
+
+impl ToBytes for ErrorStatus {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write(16, self.error_information.bits())?;
+        Ok(())
+    }
+}
+
+/// The response to a `GET_ATTENTION_VDO` command.
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Ucsi", repr_c)]
+pub struct AttentionVdo {
+    /// The VDM Header of the Attention message.
+    pub vdm_header: u32,
+    /// The VDO of the Attention message, if any.
+    pub vdo: u32,
+}
+
+impl AttentionVdo {
+    /// The SVID the Attention message was sent for.
+    pub fn svid(&self) -> u32 {
+        self.vdm_header >> 16
+    }
+}
+
+impl FromBytes for AttentionVdo {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let vdm_header = reader.read::<u32>(32)?;
+        let vdo = reader.read::<u32>(32)?;
+        Ok(Self { vdm_header, vdo })
+    }
+}
+
+impl ToBytes for AttentionVdo {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write(32, self.vdm_header)?;
+        bw.write(32, self.vdo)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Ucsi", repr_c)]
+/// The USB Type-C Current advertised by a source.
+pub enum UsbTypeCCurrent {
+    /// The PPM picks the current.
+    #[default]
+    PpmDefined = 0,
+    Current3A = 1,
+    Current1_5A = 2,
+    DefaultUsbPower = 3,
+}
+
+/// The response to a `READ_POWER_LEVEL` command.
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Ucsi", repr_c)]
+pub struct PowerLevel {
+    /// The maximum power of the connector. Zero if left to the PPM.
+    pub max_power: Milliwatt,
+    /// The USB Type-C Current of the connector.
+    pub usb_type_c_current: UsbTypeCCurrent,
+}
+
+impl FromBytes for PowerLevel {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        // In 500mW units.
+        let max_power = Milliwatt(reader.read::<u32>(8)? * 500);
+        let usb_type_c_current = reader.read::<u32>(2)?;
+        let usb_type_c_current =
+            UsbTypeCCurrent::n(usb_type_c_current).ok_or_else(|| Error::ParseError {
+                field: "usb_type_c_current".into(),
+                value: usb_type_c_current,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })?;
+        Ok(Self {
+            max_power,
+            usb_type_c_current,
+        })
+    }
+}
+
+impl ToBytes for PowerLevel {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        let max_power = self.max_power.0 / 500;
+        if max_power > 0xff {
+            return Err(Error::ParseError {
+                field: "max_power".into(),
+                value: self.max_power.0,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            });
+        }
+        bw.write(8, max_power)?;
+        bw.write(2, self.usb_type_c_current as u32)?;
+        Ok(())
+    }
+}
+
+/// The response to a `GET_LPM_PPM_INFO` command.
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Ucsi", repr_c)]
+pub struct LpmPpmInfo {
+    /// The USB Vendor ID of the LPM or PPM.
+    pub vendor_id: u32,
+    /// The Product ID of the LPM or PPM.
+    pub product_id: u32,
+    /// The USB-IF XID of the LPM or PPM.
+    pub xid: u32,
+    /// The firmware version.
+    pub fw_version: u32,
+    /// The firmware sub-version.
+    pub fw_version_sub: u32,
+    /// The hardware version. Only valid if `hw_version_valid` is set.
+    pub hw_version: u32,
+    /// Whether MESSAGE_IN was long enough to hold the hardware version,
+    /// which does not fit in the 16 bytes some transports are limited to.
+    pub hw_version_valid: bool,
+}
+
+impl FromBytes for LpmPpmInfo {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let vendor_id = reader.read::<u32>(16)?;
+        let product_id = reader.read::<u32>(16)?;
+        let xid = reader.read::<u32>(32)?;
+        let fw_version = reader.read::<u32>(32)?;
+        let fw_version_sub = reader.read::<u32>(32)?;
+        let hw_version = reader.read::<u32>(32).ok();
+
+        Ok(Self {
+            vendor_id,
+            product_id,
+            xid,
+            fw_version,
+            fw_version_sub,
+            hw_version: hw_version.unwrap_or_default(),
+            hw_version_valid: hw_version.is_some(),
+        })
+    }
+}
+
+impl ToBytes for LpmPpmInfo {
+    fn to_bytes(&self, bw: &mut crate::BitWriter) -> Result<()> {
+        bw.write(16, self.vendor_id)?;
+        bw.write(16, self.product_id)?;
+        bw.write(32, self.xid)?;
+        bw.write(32, self.fw_version)?;
+        bw.write(32, self.fw_version_sub)?;
+        bw.write(32, self.hw_version)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cci() {
+        // Command completed with 8 bytes in MESSAGE_IN.
+        let bytes = 0x8000_0800u32.to_le_bytes();
+        let cci = Cci::from_bytes(&mut BitReader::new(std::io::Cursor::new(&bytes))).unwrap();
+        assert!(cci.command_completed);
+        assert_eq!(cci.data_length, 8);
+        assert_eq!(cci.message_in(&[0; 16]).unwrap().len(), 8);
+        assert!(cci.message_in(&[0; 4]).is_err());
+
+        // Error indicator, with a change on connector 2.
+        let bytes = 0xc000_0004u32.to_le_bytes();
+        let cci = Cci::from_bytes(&mut BitReader::new(std::io::Cursor::new(&bytes))).unwrap();
+        assert_eq!(cci.connector_change, 2);
+        assert!(cci.error);
+
+        let bytes = 0x1000_0000u32.to_le_bytes();
+        let cci = Cci::from_bytes(&mut BitReader::new(std::io::Cursor::new(&bytes))).unwrap();
+        assert!(cci.busy);
+    }
+
+    #[test]
+    fn test_connector_capability() {
+        // DRP, USB 3, provider and consumer, USB4 Gen3 with EPR source and
+        // sink, FW update and security, partner PD revision 3.0.
+        let bytes = 0x18c38344u32.to_le_bytes();
+        let capability =
+            ConnectorCapability::from_bytes(&mut BitReader::new(std::io::Cursor::new(&bytes)))
+                .unwrap();
+        assert_eq!(
+            capability.extended_operation_mode,
+            ConnectorCapabilityExtendedOperationMode::EPR_SOURCE
+                | ConnectorCapabilityExtendedOperationMode::EPR_SINK
+                | ConnectorCapabilityExtendedOperationMode::USB4_GEN3
+        );
+        assert_eq!(
+            capability.miscellaneous_capabilities,
+            ConnectorCapabilityMiscellaneousCapabilities::FW_UPDATE
+                | ConnectorCapabilityMiscellaneousCapabilities::SECURITY
+        );
+        assert!(!capability.reverse_current_protection_support);
+        assert_eq!(capability.partner_pd_revision, 3);
+
+        let mut buf = [0; 4];
+        capability
+            .to_bytes(&mut crate::BitWriter::new(std::io::Cursor::new(
+                &mut buf[..],
+            )))
+            .unwrap();
+        assert_eq!(buf, bytes);
+    }
+}
@@ -0,0 +1,444 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! Typed UCSI commands.
+//!
+//! Each command is a struct implementing [`UcsiCommand`], which pairs the
+//! [`Command`] written to CONTROL with the type MESSAGE_IN is decoded into.
+//! The structs have the fields of the [`Command`] variant of the same name
+//! and are declared with the `ucsi_command!` macro.
+
+use std::io::Cursor;
+
+use crate::pd::MessageRecipient;
+use crate::pd::MessageResponseType;
+use crate::ucsi::Acknowledgement;
+use crate::ucsi::AlternateMode;
+use crate::ucsi::AttentionVdo;
+use crate::ucsi::CableProperty;
+use crate::ucsi::CamSupported;
+use crate::ucsi::Capability;
+use crate::ucsi::Command;
+use crate::ucsi::ConnectorCapability;
+use crate::ucsi::ConnectorResetType;
+use crate::ucsi::ConnectorStatus;
+use crate::ucsi::CurrentAlternatingModes;
+use crate::ucsi::ErrorStatus;
+use crate::ucsi::GetAlternateModesRecipient;
+use crate::ucsi::LpmPpmInfo;
+use crate::ucsi::NotificationEnable;
+use crate::ucsi::PdoSourceCapabilitiesType;
+use crate::ucsi::PdoType;
+use crate::ucsi::PowerDirection;
+use crate::ucsi::PowerLevel;
+use crate::ucsi::UcsiVersion;
+use crate::BitReader;
+use crate::Error;
+use crate::FromBytes;
+use crate::Result;
+
+/// A UCSI command and the data the PPM returns for it.
+pub trait UcsiCommand {
+    /// The decoded contents of MESSAGE_IN.
+    type Response;
+
+    /// The command to write to CONTROL.
+    fn command(&self) -> Command;
+
+    /// The data to write to MESSAGE_OUT before the command, if any.
+    fn message_out(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Decodes `message_in`, laid out as defined by `version`. All the fields
+    /// are decoded if the revision is unknown.
+    fn parse_response(
+        &self,
+        message_in: &[u8],
+        version: Option<UcsiVersion>,
+    ) -> Result<Self::Response>;
+}
+
+/// Decodes `message_in` as a `T`.
+fn parse<T: FromBytes>(message_in: &[u8]) -> Result<T> {
+    T::from_bytes(&mut BitReader::new(Cursor::new(message_in)))
+}
+
+/// Returns whether the PPM returned no data, i.e. only zeros.
+fn is_null(message_in: &[u8]) -> bool {
+    message_in.iter().all(|byte| *byte == 0)
+}
+
+/// Declares the struct of a command, with the fields of the [`Command`]
+/// variant of the same name, and implements [`UcsiCommand`] for it.
+///
+/// Without a response type, MESSAGE_IN is ignored. With a response type
+/// alone, MESSAGE_IN is decoded with its [`FromBytes`] implementation,
+/// regardless of the UCSI revision. Otherwise, the given closure-like body
+/// decodes it.
+macro_rules! ucsi_command {
+    (@struct $(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name;
+
+        impl $name {
+            fn to_command(&self) -> Command {
+                Command::$name
+            }
+        }
+    };
+    (@struct $(#[$meta:meta])* $name:ident { $($field:ident: $ty:ty),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name {
+            $(pub $field: $ty,)*
+        }
+
+        impl $name {
+            #[allow(clippy::clone_on_copy)]
+            fn to_command(&self) -> Command {
+                Command::$name {
+                    $($field: self.$field.clone(),)*
+                }
+            }
+        }
+    };
+    ($(#[$meta:meta])* $name:ident $({ $($fields:tt)* })?) => {
+        ucsi_command!(@struct $(#[$meta])* $name $({ $($fields)* })?);
+
+        impl UcsiCommand for $name {
+            type Response = ();
+
+            fn command(&self) -> Command {
+                self.to_command()
+            }
+
+            fn parse_response(
+                &self,
+                _message_in: &[u8],
+                _version: Option<UcsiVersion>,
+            ) -> Result<()> {
+                Ok(())
+            }
+        }
+    };
+    ($(#[$meta:meta])* $name:ident $({ $($fields:tt)* })? -> $response:ty) => {
+        ucsi_command!(@struct $(#[$meta])* $name $({ $($fields)* })?);
+
+        impl UcsiCommand for $name {
+            type Response = $response;
+
+            fn command(&self) -> Command {
+                self.to_command()
+            }
+
+            fn parse_response(
+                &self,
+                message_in: &[u8],
+                _version: Option<UcsiVersion>,
+            ) -> Result<$response> {
+                parse(message_in)
+            }
+        }
+    };
+    (
+        $(#[$meta:meta])* $name:ident $({ $($fields:tt)* })? -> $response:ty,
+        |$self:ident, $message_in:ident, $version:ident| $parse:block
+    ) => {
+        ucsi_command!(@struct $(#[$meta])* $name $({ $($fields)* })?);
+
+        impl UcsiCommand for $name {
+            type Response = $response;
+
+            fn command(&self) -> Command {
+                self.to_command()
+            }
+
+            fn parse_response(
+                &$self,
+                $message_in: &[u8],
+                $version: Option<UcsiVersion>,
+            ) -> Result<$response> {
+                $parse
+            }
+        }
+    };
+}
+
+ucsi_command! {
+    /// PPM_RESET.
+    PpmReset
+}
+
+ucsi_command! {
+    /// CONNECTOR_RESET.
+    ConnectorReset {
+        connector_nr: usize,
+        reset_type: ConnectorResetType,
+    }
+}
+
+ucsi_command! {
+    /// ACK_CC_CI.
+    AckCcCi { acknowledgement: Acknowledgement }
+}
+
+ucsi_command! {
+    /// SET_NOTIFICATION_ENABLE.
+    SetNotificationEnable { notification_enable: NotificationEnable }
+}
+
+ucsi_command! {
+    /// GET_CAPABILITY.
+    GetCapability -> Capability
+}
+
+ucsi_command! {
+    /// GET_CONNECTOR_CAPABILITY.
+    GetConnectorCapability { connector_nr: usize } -> ConnectorCapability,
+    |self, message_in, version| {
+        let mut reader = BitReader::new(Cursor::new(message_in));
+        match version {
+            Some(version) => ConnectorCapability::from_bytes_for_version(&mut reader, version),
+            None => ConnectorCapability::from_bytes(&mut reader),
+        }
+    }
+}
+
+ucsi_command! {
+    /// GET_ALTERNATE_MODES. The response is `None` past the last Alternate Mode.
+    GetAlternateModes {
+        recipient: GetAlternateModesRecipient,
+        connector_nr: usize,
+        offset: usize,
+    } -> Option<AlternateMode>,
+    |self, message_in, _version| {
+        if is_null(message_in) {
+            return Ok(None);
+        }
+
+        parse(message_in).map(Some)
+    }
+}
+
+ucsi_command! {
+    /// GET_CAM_SUPPORTED.
+    GetCamSupported { connector_nr: usize } -> CamSupported
+}
+
+ucsi_command! {
+    /// GET_CURRENT_CAM.
+    GetCurrentCam { connector_nr: usize } -> CurrentAlternatingModes
+}
+
+ucsi_command! {
+    /// GET_PDOS. The response holds the raw PDOs, up to the first empty one.
+    /// `nr_pdos` is the number of PDOs to return minus 1.
+    GetPdos {
+        connector_nr: usize,
+        partner_pdo: bool,
+        pdo_offset: u32,
+        nr_pdos: usize,
+        pdo_type: PdoType,
+        source_capabilities_type: PdoSourceCapabilitiesType,
+    } -> Vec<u32>,
+    |self, message_in, _version| {
+        Ok(message_in
+            .chunks_exact(4)
+            .take(self.nr_pdos + 1)
+            .map(|pdo| u32::from_le_bytes([pdo[0], pdo[1], pdo[2], pdo[3]]))
+            .take_while(|pdo| *pdo != 0)
+            .collect())
+    }
+}
+
+ucsi_command! {
+    /// GET_CABLE_PROPERTY.
+    GetCableProperty { connector_nr: usize } -> CableProperty
+}
+
+ucsi_command! {
+    /// GET_CONNECTOR_STATUS.
+    GetConnectorStatus { connector_nr: usize } -> ConnectorStatus,
+    |self, message_in, version| {
+        let mut reader = BitReader::new(Cursor::new(message_in));
+        match version {
+            Some(version) => ConnectorStatus::from_bytes_for_version(&mut reader, version),
+            None => ConnectorStatus::from_bytes(&mut reader),
+        }
+    }
+}
+
+ucsi_command! {
+    /// GET_ERROR_STATUS.
+    GetErrorStatus { connector_nr: Option<usize> } -> ErrorStatus
+}
+
+ucsi_command! {
+    /// GET_PD_MESSAGE. The response holds `nr_bytes` bytes of the message, or
+    /// is empty past its end.
+    GetPdMessage {
+        connector_nr: u32,
+        recipient: MessageRecipient,
+        offset: u32,
+        nr_bytes: u32,
+        message_type: MessageResponseType,
+    } -> Vec<u8>,
+    |self, message_in, _version| {
+        if is_null(message_in) {
+            return Ok(vec![]);
+        }
+
+        message_in
+            .get(..self.nr_bytes as usize)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| Error::ParseError {
+                field: "nr_bytes".into(),
+                value: self.nr_bytes,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })
+    }
+}
+
+ucsi_command! {
+    /// GET_ATTENTION_VDO.
+    GetAttentionVdo { connector_nr: usize } -> AttentionVdo
+}
+
+ucsi_command! {
+    /// SET_POWER_LEVEL.
+    SetPowerLevel {
+        connector_nr: usize,
+        power_direction: PowerDirection,
+        power_level: PowerLevel,
+    }
+}
+
+ucsi_command! {
+    /// SET_SINK_PATH.
+    SetSinkPath {
+        connector_nr: usize,
+        enable: bool,
+    }
+}
+
+/// A chunk of LPM_FW_UPDATE_REQUEST. The response is the raw MESSAGE_IN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LpmFwUpdateRequest<'a> {
+    pub connector_nr: usize,
+    pub chunk_number: u8,
+    pub last_chunk: bool,
+    /// The chunk, written to MESSAGE_OUT.
+    pub data: &'a [u8],
+}
+
+impl UcsiCommand for LpmFwUpdateRequest<'_> {
+    type Response = Vec<u8>;
+
+    fn command(&self) -> Command {
+        Command::LpmFwUpdateRequest {
+            connector_nr: self.connector_nr,
+            data_length: self.data.len() as u8,
+            chunk_number: self.chunk_number,
+            last_chunk: self.last_chunk,
+        }
+    }
+
+    fn message_out(&self) -> &[u8] {
+        self.data
+    }
+
+    fn parse_response(&self, message_in: &[u8], _version: Option<UcsiVersion>) -> Result<Vec<u8>> {
+        Ok(message_in.to_vec())
+    }
+}
+
+/// A chunk of SECURITY_REQUEST. The response is the raw MESSAGE_IN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityRequest<'a> {
+    pub connector_nr: usize,
+    pub chunk_number: u8,
+    pub last_chunk: bool,
+    /// The chunk, written to MESSAGE_OUT.
+    pub data: &'a [u8],
+}
+
+impl UcsiCommand for SecurityRequest<'_> {
+    type Response = Vec<u8>;
+
+    fn command(&self) -> Command {
+        Command::SecurityRequest {
+            connector_nr: self.connector_nr,
+            data_length: self.data.len() as u8,
+            chunk_number: self.chunk_number,
+            last_chunk: self.last_chunk,
+        }
+    }
+
+    fn message_out(&self) -> &[u8] {
+        self.data
+    }
+
+    fn parse_response(&self, message_in: &[u8], _version: Option<UcsiVersion>) -> Result<Vec<u8>> {
+        Ok(message_in.to_vec())
+    }
+}
+
+ucsi_command! {
+    /// SET_RETIMER_MODE.
+    SetRetimerMode {
+        connector_nr: usize,
+        retimer_mode: u32,
+    }
+}
+
+ucsi_command! {
+    /// READ_POWER_LEVEL.
+    ReadPowerLevel {
+        connector_nr: usize,
+        power_direction: PowerDirection,
+    } -> PowerLevel
+}
+
+ucsi_command! {
+    /// GET_LPM_PPM_INFO.
+    GetLpmPpmInfo { connector_nr: usize } -> LpmPpmInfo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_pdos_response() {
+        let command = GetPdos {
+            connector_nr: 0,
+            partner_pdo: false,
+            pdo_offset: 0,
+            nr_pdos: 3,
+            pdo_type: PdoType::Source,
+            source_capabilities_type: PdoSourceCapabilitiesType::CurrentSupportedSourceCapabilities,
+        };
+        assert_eq!(command.command().cmd_number(), 0x10);
+
+        // Two PDOs followed by an empty slot.
+        let mut message_in = [0; 16];
+        message_in[..4].copy_from_slice(&0x0801912cu32.to_le_bytes());
+        message_in[4..8].copy_from_slice(&0x0002d12cu32.to_le_bytes());
+        let pdos = command
+            .parse_response(&message_in, Some(UcsiVersion::V1_2))
+            .unwrap();
+        assert_eq!(pdos, [0x0801912c, 0x0002d12c]);
+
+        let modes = GetAlternateModes {
+            recipient: GetAlternateModesRecipient::Connector,
+            connector_nr: 0,
+            offset: 0,
+        }
+        .parse_response(&[0; 16], Some(UcsiVersion::V1_2))
+        .unwrap();
+        assert!(modes.is_none());
+    }
+}
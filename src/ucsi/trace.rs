@@ -0,0 +1,444 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! Decoding of the UCSI kernel tracepoints.
+//!
+//! The Linux UCSI driver traces the commands it sends to the PPM, the
+//! notifications it receives and the connector status changes it handles.
+//! [`Tracer`] enables the tracepoints through tracefs and decodes the lines
+//! read from `trace_pipe`. [`parse_trace`] decodes previously captured trace
+//! text.
+//!
+//! Older kernels trace the full CONTROL and CCI values, which are decoded
+//! into a [`Command`]. Newer kernels only trace the name of the command that
+//! completed.
+
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Cursor;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::ucsi::BatteryChargingCapabilityStatus;
+use crate::ucsi::Command;
+use crate::ucsi::ConnectorPartnerType;
+use crate::ucsi::ConnectorStatus;
+use crate::ucsi::ConnectorStatusChange;
+use crate::ucsi::GetAlternateModesRecipient;
+use crate::ucsi::PowerDirection;
+use crate::ucsi::PowerOperationMode;
+use crate::vdo::svid;
+use crate::vdo::Vdo;
+use crate::BitReader;
+use crate::FromBytes;
+use crate::Result;
+
+/// The default tracefs mount point.
+pub const TRACEFS_PATH: &str = "/sys/kernel/tracing";
+
+/// The command names used by the kernel, indexed by command number. See UCSI
+/// 3.0 - Table A.1.
+const COMMAND_NAMES: &[&str] = &[
+    "Unknown command",
+    "PPM_RESET",
+    "Cancel",
+    "CONNECTOR_RESET",
+    "ACK_CC_CI",
+    "SET_NOTIFICATION_ENABLE",
+    "GET_CAPABILITY",
+    "GET_CONNECTOR_CAPABILITY",
+    "SET_UOM",
+    "SET_UOR",
+    "SET_PDM",
+    "SET_PDR",
+    "GET_ALTERNATE_MODES",
+    "GET_CAM_SUPPORTED",
+    "GET_CURRENT_CAM",
+    "SET_NEW_CAM",
+    "GET_PDOS",
+    "GET_CABLE_PROPERTY",
+    "GET_CONNECTOR_STATUS",
+    "GET_ERROR_STATUS",
+    "SET_POWER_LEVEL",
+    "GET_PD_MESSAGE",
+    "GET_ATTENTION_VDO",
+    "Reserved",
+    "GET_CAM_CS",
+    "LPM_FW_UPDATE_REQUEST",
+    "SECURITY_REQUEST",
+    "SET_RETIMER_MODE",
+    "SET_SINK_PATH",
+    "SET_PDOS",
+    "READ_POWER_LEVEL",
+    "CHUNKING_SUPPORT",
+    "VENDOR_DEFINED_COMMAND",
+    "SET_USB",
+    "GET_LPM_PPM_INFO",
+];
+
+/// Returns the command number for a command name printed by the kernel.
+pub fn command_number(name: &str) -> Option<u32> {
+    COMMAND_NAMES
+        .iter()
+        .position(|n| *n == name)
+        .filter(|nr| *nr != 0)
+        .map(|nr| nr as u32)
+}
+
+/// Returns the name of a command number, as printed by the kernel.
+pub fn command_name(cmd_number: u32) -> Option<&'static str> {
+    COMMAND_NAMES
+        .get(cmd_number as usize)
+        .filter(|_| cmd_number != 0)
+        .copied()
+}
+
+/// A decoded UCSI trace event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent {
+    /// The OPM wrote the CONTROL data structure (`ucsi_command`).
+    Control {
+        /// The raw CONTROL value.
+        raw: u64,
+        /// The command, if this library models it.
+        command: Option<Command>,
+    },
+    /// The PPM notified the OPM (`ucsi_notify`).
+    Cci {
+        /// The raw CCI value.
+        raw: u32,
+    },
+    /// The OPM acknowledged a command completion or a connector change
+    /// (`ucsi_ack`).
+    Ack { name: String },
+    /// A command completed (`ucsi_run_command` or `ucsi_reset_ppm`).
+    CommandCompletion {
+        /// The command name printed by the kernel.
+        name: String,
+        /// The command number, if the name is known.
+        cmd_number: Option<u32>,
+        /// The error returned by the driver, or 0 on success.
+        ret: i32,
+    },
+    /// A connector status, either changed (`ucsi_connector_change`) or read
+    /// when the port was registered (`ucsi_register_port`). Only the fields
+    /// printed by the kernel are set.
+    ConnectorStatus {
+        /// The connector number, starting from 0.
+        connector_nr: usize,
+        status: ConnectorStatus,
+    },
+    /// An alternate mode was registered (`ucsi_register_altmode`).
+    RegisterAltmode {
+        recipient: GetAlternateModesRecipient,
+        svid: u32,
+        mode: u32,
+        vdo: u32,
+        /// The VDO decoded with the decoder registered for the SVID, if any.
+        decoded: Option<Vdo>,
+    },
+    /// Any other UCSI event.
+    Other { name: String, body: String },
+}
+
+/// A line of the trace buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceRecord {
+    /// The task that hit the tracepoint, e.g. `kworker/u16:3-123`.
+    pub task: String,
+    /// The CPU the tracepoint was hit on.
+    pub cpu: u32,
+    /// The trace clock timestamp.
+    pub timestamp: Duration,
+    pub event: TraceEvent,
+}
+
+/// Decodes a trace line. Returns `None` for lines that are not UCSI events,
+/// such as the header of the `trace` file.
+pub fn parse_line(line: &str) -> Option<TraceRecord> {
+    static LINE_RE: OnceLock<Regex> = OnceLock::new();
+    let line_re = LINE_RE.get_or_init(|| {
+        Regex::new(
+            r"^\s*(.+?)\s+\[(\d+)\]\s+(?:\S+\s+)?(\d+)\.(\d{6}):\s+(?:ucsi:)?(ucsi_\w+):\s*(.*)$",
+        )
+        .unwrap()
+    });
+
+    let captures = line_re.captures(line)?;
+    let timestamp = Duration::from_secs(captures[3].parse().ok()?)
+        + Duration::from_micros(captures[4].parse().ok()?);
+    let event = parse_event(&captures[5], captures[6].trim());
+
+    Some(TraceRecord {
+        task: captures[1].into(),
+        cpu: captures[2].parse().ok()?,
+        timestamp,
+        event,
+    })
+}
+
+/// Decodes captured trace text, e.g. a copy of the tracefs `trace` file.
+pub fn parse_trace(trace: &str) -> Vec<TraceRecord> {
+    trace.lines().filter_map(parse_line).collect()
+}
+
+/// The patterns of the bodies of the UCSI trace events.
+struct EventPatterns {
+    control: Regex,
+    cci: Regex,
+    ack: Regex,
+    completion: Regex,
+    status: Regex,
+    altmode: Regex,
+}
+
+impl EventPatterns {
+    /// Returns the patterns, which are compiled on first use.
+    fn get() -> &'static Self {
+        static PATTERNS: OnceLock<EventPatterns> = OnceLock::new();
+        PATTERNS.get_or_init(|| Self {
+            control: Regex::new(r"^control=([0-9a-fA-F]+)").unwrap(),
+            cci: Regex::new(r"^CCI=([0-9a-fA-F]+)").unwrap(),
+            ack: Regex::new(r"^ACK (.*)$").unwrap(),
+            completion: Regex::new(r"^(.+?) -> (?:OK|FAIL) \(err=(-?\d+)\)").unwrap(),
+            status: Regex::new(
+                r"^port(\d+) status: change=([0-9a-fA-F]+), opmode=([0-9a-fA-F]+), connected=(\d+), sourcing=(\d+), partner_flags=([0-9a-fA-F]+), partner_type=([0-9a-fA-F]+), request_data_obj=([0-9a-fA-F]+), BC status=([0-9a-fA-F]+)",
+            )
+            .unwrap(),
+            altmode: Regex::new(
+                r"^(.+?) alt mode: svid ([0-9a-fA-F]+), mode (\d+) vdo ([0-9a-fA-F]+)",
+            )
+            .unwrap(),
+        })
+    }
+}
+
+fn parse_event(name: &str, body: &str) -> TraceEvent {
+    let patterns = EventPatterns::get();
+
+    let other = || TraceEvent::Other {
+        name: name.into(),
+        body: body.into(),
+    };
+
+    let event = if let Some(c) = patterns.control.captures(body) {
+        u64::from_str_radix(&c[1], 16)
+            .ok()
+            .map(|raw| TraceEvent::Control {
+                raw,
+                command: decode_control(raw),
+            })
+    } else if let Some(c) = patterns.cci.captures(body) {
+        u32::from_str_radix(&c[1], 16)
+            .ok()
+            .map(|raw| TraceEvent::Cci { raw })
+    } else if let Some(c) = patterns.ack.captures(body) {
+        Some(TraceEvent::Ack { name: c[1].into() })
+    } else if let Some(c) = patterns.status.captures(body) {
+        parse_connector_status(&c)
+    } else if let Some(c) = patterns.altmode.captures(body) {
+        parse_altmode(&c)
+    } else if let Some(c) = patterns.completion.captures(body) {
+        c[2].parse().ok().map(|ret| TraceEvent::CommandCompletion {
+            name: c[1].into(),
+            cmd_number: command_number(&c[1]),
+            ret,
+        })
+    } else {
+        None
+    };
+
+    event.unwrap_or_else(other)
+}
+
+/// Decodes a raw CONTROL value into a [`Command`].
+pub fn decode_control(raw: u64) -> Option<Command> {
+    let bytes = raw.to_le_bytes();
+    Command::from_bytes(&mut BitReader::new(Cursor::new(&bytes))).ok()
+}
+
+fn parse_connector_status(c: &regex::Captures) -> Option<TraceEvent> {
+    let hex = |i: usize| u32::from_str_radix(&c[i], 16).ok();
+
+    let connector_nr = c[1].parse::<usize>().ok()?.checked_sub(1)?;
+    let change = (hex(2)? as u16).to_le_bytes();
+    let connector_status_change =
+        ConnectorStatusChange::from_bytes(&mut BitReader::new(Cursor::new(&change))).ok()?;
+    let power_direction = if &c[5] == "0" {
+        PowerDirection::Consumer
+    } else {
+        PowerDirection::Provider
+    };
+
+    let status = ConnectorStatus {
+        connector_status_change,
+        power_operation_mode: PowerOperationMode::n(hex(3)?)?,
+        connect_status: &c[4] != "0",
+        power_direction,
+        connector_partner_flags: hex(6)?,
+        connector_partner_type: ConnectorPartnerType::n(hex(7)?)?,
+        negotiated_power_level: hex(8)?,
+        battery_charging_capability_status: BatteryChargingCapabilityStatus::n(hex(9)?)?,
+        ..Default::default()
+    };
+
+    Some(TraceEvent::ConnectorStatus {
+        connector_nr,
+        status,
+    })
+}
+
+fn parse_altmode(c: &regex::Captures) -> Option<TraceEvent> {
+    let recipient = match &c[1] {
+        "port" => GetAlternateModesRecipient::Connector,
+        "partner" => GetAlternateModesRecipient::Sop,
+        "plug" | "plug (prime)" => GetAlternateModesRecipient::SopPrime,
+        "plug (double prime)" => GetAlternateModesRecipient::SopDoublePrime,
+        _ => return None,
+    };
+    let svid = u32::from_str_radix(&c[2], 16).ok()?;
+    let mode = c[3].parse().ok()?;
+    let vdo = u32::from_str_radix(&c[4], 16).ok()?;
+    let decoded = svid::decode(svid, vdo, recipient).ok().flatten();
+
+    Some(TraceEvent::RegisterAltmode {
+        recipient,
+        svid,
+        mode,
+        vdo,
+        decoded,
+    })
+}
+
+/// Streams the UCSI tracepoints from tracefs.
+///
+/// The tracepoints are enabled on creation and disabled on drop. Reading
+/// `trace_pipe` consumes the trace buffer and blocks until events are
+/// available, and requires root.
+pub struct Tracer {
+    tracefs: PathBuf,
+    pipe: BufReader<File>,
+}
+
+impl Tracer {
+    /// Enables the UCSI tracepoints in the tracefs mounted at
+    /// [`TRACEFS_PATH`].
+    pub fn new() -> Result<Self> {
+        Self::with_tracefs(Path::new(TRACEFS_PATH))
+    }
+
+    /// Enables the UCSI tracepoints in the tracefs mounted at `tracefs`.
+    pub fn with_tracefs(tracefs: &Path) -> Result<Self> {
+        std::fs::write(tracefs.join("events/ucsi/enable"), "1")?;
+        let pipe = BufReader::new(File::open(tracefs.join("trace_pipe"))?);
+
+        Ok(Self {
+            tracefs: tracefs.to_path_buf(),
+            pipe,
+        })
+    }
+}
+
+impl Iterator for Tracer {
+    type Item = Result<TraceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.pipe.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    if let Some(record) = parse_line(&line) {
+                        return Some(Ok(record));
+                    }
+                }
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        let _ = std::fs::write(self.tracefs.join("events/ucsi/enable"), "0");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdo::displayport::DISPLAYPORT_SVID;
+
+    const TRACE: &str = "\
+# tracer: nop
+#
+#           TASK-PID     CPU#  |||||  TIMESTAMP  FUNCTION
+#              | |         |   |||||     |         |
+ kworker/u16:3-123     [002] d..1.   412.100200: ucsi_command: control=0000000000010012 (GET_CONNECTOR_STATUS)
+ kworker/u16:3-123     [002] d..1.   412.100900: ucsi_notify: CCI=80000800 command complete
+ kworker/u16:3-123     [002] .....   412.101003: ucsi_run_command: GET_CONNECTOR_STATUS -> OK (err=0)
+ kworker/u16:3-123     [002] .....   412.101050: ucsi_connector_change: port1 status: change=4044, opmode=3, connected=1, sourcing=0, partner_flags=1, partner_type=1, request_data_obj=1304b12c, BC status=1
+ kworker/u16:3-123     [002] .....   412.102000: ucsi_register_altmode: partner alt mode: svid ff01, mode 1 vdo 1c0045
+";
+
+    #[test]
+    fn test_parse_trace() {
+        let records = parse_trace(TRACE);
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[0].task, "kworker/u16:3-123");
+        assert_eq!(records[0].cpu, 2);
+        assert_eq!(
+            records[0].timestamp,
+            Duration::from_secs(412) + Duration::from_micros(100200)
+        );
+
+        assert_eq!(
+            records[0].event,
+            TraceEvent::Control {
+                raw: 0x10012,
+                command: Some(Command::GetConnectorStatus { connector_nr: 0 }),
+            }
+        );
+        assert_eq!(records[1].event, TraceEvent::Cci { raw: 0x80000800 });
+        assert_eq!(
+            records[2].event,
+            TraceEvent::CommandCompletion {
+                name: "GET_CONNECTOR_STATUS".into(),
+                cmd_number: Some(0x12),
+                ret: 0,
+            }
+        );
+
+        let TraceEvent::ConnectorStatus {
+            connector_nr,
+            status,
+        } = &records[3].event
+        else {
+            panic!("expected a connector status, got {:?}", records[3].event);
+        };
+        assert_eq!(*connector_nr, 0);
+        assert!(status.connect_status);
+        assert!(status.connector_status_change.power_operation_mode_change);
+        assert!(status.connector_status_change.negotiated_power_level_change);
+        assert_eq!(
+            status.power_operation_mode,
+            PowerOperationMode::PowerDelivery
+        );
+        assert_eq!(
+            status.connector_partner_type,
+            ConnectorPartnerType::DfpAttached
+        );
+
+        let TraceEvent::RegisterAltmode { svid, decoded, .. } = &records[4].event else {
+            panic!("expected an alternate mode, got {:?}", records[4].event);
+        };
+        assert_eq!(*svid, DISPLAYPORT_SVID);
+        assert!(matches!(decoded, Some(Vdo::DisplayPortCapabilities(_))));
+    }
+}
@@ -4,23 +4,48 @@
 
 //! The VDO data structures
 
+use std::io::Cursor;
+
 use proc_macros::CApiWrapper;
 
+use crate::pd::pd3p2::vdo::ActiveCable1 as Pd3p2VdoActiveCable1;
+use crate::pd::pd3p2::vdo::ActiveCable2 as Pd3p2VdoActiveCable2;
 use crate::pd::pd3p2::vdo::CertStat as Pd3p2VdoCertStat;
 use crate::pd::pd3p2::vdo::Dfp as Pd3p2VdoDfp;
 use crate::pd::pd3p2::vdo::IdHeader as Pd3p2VdoIdHeader;
-use crate::pd::pd3p2::vdo::ProductType as Pd3p2VdoProductType;
+use crate::pd::pd3p2::vdo::PassiveCable as Pd3p2VdoPassiveCable;
 use crate::pd::pd3p2::vdo::Ufp as Pd3p2VdoUfp;
 use crate::pd::pd3p2::vdo::Vpd as Pd3p2VdoVpd;
+use crate::pd::pd3p2::EnterUsbDataObject as Pd3p2EnterUsbDataObject;
+use crate::vdo::displayport::Capabilities as DisplayPortCapabilities;
+use crate::vdo::displayport::Configure as DisplayPortConfigure;
+use crate::vdo::displayport::Status as DisplayPortStatus;
+use crate::vdo::thunderbolt::CableMode as ThunderboltCableMode;
+use crate::vdo::thunderbolt::DeviceMode as ThunderboltDeviceMode;
+use crate::BitReader;
+use crate::FromBytes;
+use crate::Result;
+
+pub mod displayport;
+pub mod svid;
+pub mod thunderbolt;
 
 #[cfg(feature = "c_api")]
 mod c_api {
+    pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoActiveCable1;
+    pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoActiveCable2;
     pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoCertStat;
     pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoDfp;
     pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoIdHeader;
-    pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoProductType;
+    pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoPassiveCable;
     pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoUfp;
     pub(crate) use crate::pd::pd3p2::vdo::Pd3p2VdoVpd;
+    pub(crate) use crate::pd::pd3p2::Pd3p2EnterUsbDataObject;
+    pub(crate) use crate::vdo::displayport::DisplayPortCapabilities;
+    pub(crate) use crate::vdo::displayport::DisplayPortConfigure;
+    pub(crate) use crate::vdo::displayport::DisplayPortStatus;
+    pub(crate) use crate::vdo::thunderbolt::ThunderboltCableMode;
+    pub(crate) use crate::vdo::thunderbolt::ThunderboltDeviceMode;
 }
 
 #[derive(Debug, Clone, PartialEq, CApiWrapper)]
@@ -29,8 +54,52 @@ mod c_api {
 pub enum Vdo {
     Pd3p2IdHeader(Pd3p2VdoIdHeader),
     Pd3p2CertStat(Pd3p2VdoCertStat),
-    Pd3p2ProductType(Pd3p2VdoProductType),
+    Pd3p2PassiveCable(Pd3p2VdoPassiveCable),
+    Pd3p2ActiveCable1(Pd3p2VdoActiveCable1),
+    Pd3p2ActiveCable2(Pd3p2VdoActiveCable2),
     Pd3p2Vpd(Pd3p2VdoVpd),
     Pd3p2Ufp(Pd3p2VdoUfp),
     Pd3p2Dfp(Pd3p2VdoDfp),
+    DisplayPortCapabilities(DisplayPortCapabilities),
+    DisplayPortStatus(DisplayPortStatus),
+    DisplayPortConfigure(DisplayPortConfigure),
+    ThunderboltDeviceMode(ThunderboltDeviceMode),
+    ThunderboltCableMode(ThunderboltCableMode),
+    Pd3p2EnterUsb(Pd3p2EnterUsbDataObject),
+}
+
+#[cfg(feature = "c_api")]
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq)]
+/// An optional VDO, as C has no equivalent to `Option<Vdo>`.
+pub(crate) enum TypeCRsOptionalVdo {
+    None,
+    Some(TypeCRsVdo),
+}
+
+#[cfg(feature = "c_api")]
+impl From<Option<Vdo>> for TypeCRsOptionalVdo {
+    fn from(value: Option<Vdo>) -> Self {
+        match value {
+            Some(vdo) => Self::Some(vdo.into()),
+            None => Self::None,
+        }
+    }
+}
+
+#[cfg(feature = "c_api")]
+impl From<TypeCRsOptionalVdo> for Option<Vdo> {
+    fn from(value: TypeCRsOptionalVdo) -> Self {
+        match value {
+            TypeCRsOptionalVdo::Some(vdo) => Some(vdo.into()),
+            TypeCRsOptionalVdo::None => None,
+        }
+    }
+}
+
+/// Decodes a single VDO.
+pub(crate) fn read_vdo<T: FromBytes>(vdo: u32) -> Result<T> {
+    let bytes = vdo.to_le_bytes();
+    let mut reader = BitReader::new(Cursor::new(&bytes));
+    T::from_bytes(&mut reader)
 }
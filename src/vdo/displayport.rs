@@ -0,0 +1,379 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! DisplayPort Alternate Mode VDOs.
+//!
+//! See VESA DisplayPort Alt Mode on USB Type-C Standard v2.1 - 5.2 USB PD
+//! Structured VDMs for DisplayPort.
+
+use bitstream_io::BitRead;
+use enumn::N;
+use proc_macros::CApiWrapper;
+
+use crate::bitflags_wrapper;
+use crate::BitReader;
+use crate::Error;
+use crate::FromBytes;
+use crate::Result;
+
+/// The SVID assigned by USB-IF to VESA for DisplayPort.
+pub const DISPLAYPORT_SVID: u32 = 0xff01;
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "DisplayPort", repr_c)]
+/// Whether the port can act as a DisplayPort source, sink or both.
+pub enum PortCapability {
+    #[default]
+    Reserved = 0,
+    /// UFP_D-capable (e.g. a display)
+    UfpD,
+    /// DFP_D-capable (e.g. a GPU)
+    DfpD,
+    /// Both DFP_D and UFP_D-capable
+    DfpDAndUfpD,
+}
+
+bitflags_wrapper! {
+    DisplayPort,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// Signalling for Transport of DisplayPort Protocol.
+    pub struct Signalling: u32 {
+        /// DP v1.3 signalling rates, i.e. up to HBR3.
+        const HBR3 = 0b0001;
+        /// UHBR10, formerly known as USB Gen2 signalling.
+        const UHBR10 = 0b0010;
+        /// UHBR20
+        const UHBR20 = 0b0100;
+    }
+}
+
+impl Signalling {
+    /// The name of the highest signalling rate in the set.
+    fn highest(&self, uhbr13_5: bool) -> &'static str {
+        if self.contains(Self::UHBR20) {
+            "UHBR20"
+        } else if uhbr13_5 {
+            "UHBR13.5"
+        } else if self.contains(Self::UHBR10) {
+            "UHBR10"
+        } else if self.contains(Self::HBR3) {
+            "HBR3"
+        } else {
+            "unspecified signalling"
+        }
+    }
+}
+
+bitflags_wrapper! {
+    DisplayPort,
+    #[derive(Debug, Clone, PartialEq, Default, Copy)]
+    /// DisplayPort pin assignments, as defined by the DP Alt Mode standard.
+    pub struct PinAssignment: u32 {
+        const A = 0b000001;
+        const B = 0b000010;
+        const C = 0b000100;
+        const D = 0b001000;
+        const E = 0b010000;
+        const F = 0b100000;
+    }
+}
+
+impl std::fmt::Display for PinAssignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+
+        let names = self.iter_names().map(|(name, _)| name).collect::<Vec<_>>();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "DisplayPort", repr_c)]
+/// The active component in the cable, if any.
+pub enum ActiveComponent {
+    #[default]
+    Passive = 0,
+    ActiveRetimer,
+    ActiveRedriver,
+    Optical,
+}
+
+/// The DisplayPort Capabilities VDO, returned in the Discover Modes response.
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "DisplayPort", repr_c)]
+pub struct Capabilities {
+    /// Port Capability
+    pub port_capability: PortCapability,
+    /// Signalling for Transport of DisplayPort Protocol
+    pub signalling: Signalling,
+    /// Whether the DisplayPort interface is presented on a USB Type-C
+    /// receptacle, as opposed to a plug.
+    pub receptacle: bool,
+    /// Whether USB 2.0 signalling is not used while in DisplayPort mode.
+    pub usb2_signalling_not_used: bool,
+    /// DFP_D Pin Assignments supported, as encoded in the VDO.
+    pub dfp_d_pin_assignments: PinAssignment,
+    /// UFP_D Pin Assignments supported, as encoded in the VDO.
+    pub ufp_d_pin_assignments: PinAssignment,
+    /// Whether UHBR13.5 is supported.
+    pub uhbr13_5_supported: bool,
+    /// The active component in the cable.
+    pub active_component: ActiveComponent,
+    /// Whether the port implements DP Alt Mode v2.1 or later.
+    pub dpam_v2_1: bool,
+}
+
+impl Capabilities {
+    /// The pin assignments supported when acting as UFP_D. A plug reports
+    /// these in the DFP_D field, a receptacle in the UFP_D field.
+    pub fn ufp_d_pins(&self) -> PinAssignment {
+        if self.receptacle {
+            self.ufp_d_pin_assignments
+        } else {
+            self.dfp_d_pin_assignments
+        }
+    }
+
+    /// The pin assignments supported when acting as DFP_D. A plug reports
+    /// these in the UFP_D field, a receptacle in the DFP_D field.
+    pub fn dfp_d_pins(&self) -> PinAssignment {
+        if self.receptacle {
+            self.dfp_d_pin_assignments
+        } else {
+            self.ufp_d_pin_assignments
+        }
+    }
+}
+
+impl std::fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pins = match self.port_capability {
+            PortCapability::DfpD => self.dfp_d_pins(),
+            _ => self.ufp_d_pins(),
+        };
+
+        write!(
+            f,
+            "pins {}; {}",
+            pins,
+            self.signalling.highest(self.uhbr13_5_supported)
+        )
+    }
+}
+
+impl FromBytes for Capabilities {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let port_capability = reader.read(2)?;
+        let port_capability =
+            PortCapability::n(port_capability).ok_or_else(|| Error::ParseError {
+                field: "port_capability".into(),
+                value: port_capability,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })?;
+        let signalling = Signalling::from_bits_truncate(reader.read(4)?);
+        let receptacle = reader.read_bit()?;
+        let usb2_signalling_not_used = reader.read_bit()?;
+        let dfp_d_pin_assignments = PinAssignment::from_bits_truncate(reader.read(8)?);
+        let ufp_d_pin_assignments = PinAssignment::from_bits_truncate(reader.read(8)?);
+        reader.skip(2)?; // Reserved
+        let uhbr13_5_supported = reader.read_bit()?;
+        reader.skip(1)?; // Reserved
+        let active_component = reader.read(2)?;
+        let active_component =
+            ActiveComponent::n(active_component).ok_or_else(|| Error::ParseError {
+                field: "active_component".into(),
+                value: active_component,
+                #[cfg(feature = "backtrace")]
+                backtrace: std::backtrace::Backtrace::capture(),
+            })?;
+        let dpam_v2_1 = reader.read_bit()?;
+        reader.skip(1)?; // Reserved
+
+        Ok(Self {
+            port_capability,
+            signalling,
+            receptacle,
+            usb2_signalling_not_used,
+            dfp_d_pin_assignments,
+            ufp_d_pin_assignments,
+            uhbr13_5_supported,
+            active_component,
+            dpam_v2_1,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "DisplayPort", repr_c)]
+/// Which DisplayPort roles are connected.
+pub enum ConnectionStatus {
+    #[default]
+    NotConnected = 0,
+    DfpD,
+    UfpD,
+    DfpDAndUfpD,
+}
+
+/// The DisplayPort Status VDO, sent in DisplayPort Status Update and
+/// Attention messages.
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "DisplayPort", repr_c)]
+pub struct Status {
+    /// DFP_D/UFP_D Connected
+    pub connection: ConnectionStatus,
+    /// Whether the adapter has detected a low power condition.
+    pub power_low: bool,
+    /// Whether the DisplayPort functionality is enabled.
+    pub enabled: bool,
+    /// Whether a multi-function (USB + DisplayPort) pin assignment is
+    /// preferred.
+    pub multi_function_preferred: bool,
+    /// Whether the port requests a switch to a USB configuration.
+    pub usb_configuration_requested: bool,
+    /// Whether the port requests to exit DisplayPort mode.
+    pub exit_requested: bool,
+    /// The HPD state, true being HPD_High.
+    pub hpd_high: bool,
+    /// Whether an IRQ_HPD has been detected.
+    pub irq_hpd: bool,
+}
+
+impl FromBytes for Status {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let connection = reader.read(2)?;
+        let connection = ConnectionStatus::n(connection).ok_or_else(|| Error::ParseError {
+            field: "connection".into(),
+            value: connection,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+        let power_low = reader.read_bit()?;
+        let enabled = reader.read_bit()?;
+        let multi_function_preferred = reader.read_bit()?;
+        let usb_configuration_requested = reader.read_bit()?;
+        let exit_requested = reader.read_bit()?;
+        let hpd_high = reader.read_bit()?;
+        let irq_hpd = reader.read_bit()?;
+        reader.skip(23)?; // Reserved
+
+        Ok(Self {
+            connection,
+            power_low,
+            enabled,
+            multi_function_preferred,
+            usb_configuration_requested,
+            exit_requested,
+            hpd_high,
+            irq_hpd,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "DisplayPort", repr_c)]
+/// The configuration selected by a DisplayPort Configure command.
+pub enum Configuration {
+    #[default]
+    /// Set configuration for USB
+    Usb = 0,
+    /// Set configuration for UFP_U as DFP_D
+    DfpD,
+    /// Set configuration for UFP_U as UFP_D
+    UfpD,
+    Reserved,
+}
+
+/// The DisplayPort Configurations VDO, sent in DisplayPort Configure
+/// commands.
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "DisplayPort", repr_c)]
+pub struct Configure {
+    /// Select Configuration
+    pub configuration: Configuration,
+    /// Signalling for Transport of DisplayPort Protocol
+    pub signalling: Signalling,
+    /// The pin assignment selected for the UFP_U.
+    pub pin_assignment: PinAssignment,
+}
+
+impl FromBytes for Configure {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let configuration = reader.read(2)?;
+        let configuration = Configuration::n(configuration).ok_or_else(|| Error::ParseError {
+            field: "configuration".into(),
+            value: configuration,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+        let signalling = Signalling::from_bits_truncate(reader.read(4)?);
+        reader.skip(2)?; // Reserved
+        let pin_assignment = PinAssignment::from_bits_truncate(reader.read(8)?);
+        reader.skip(16)?; // Reserved
+
+        Ok(Self {
+            configuration,
+            signalling,
+            pin_assignment,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_capabilities_from_bytes() {
+        // A UFP_D receptacle (e.g. a dock) supporting pin assignments C and D
+        // at UHBR10.
+        let bytes = 0x000c0049u32.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let capabilities = Capabilities::from_bytes(&mut reader).unwrap();
+
+        assert_eq!(capabilities.port_capability, PortCapability::UfpD);
+        assert_eq!(capabilities.signalling, Signalling::UHBR10);
+        assert!(capabilities.receptacle);
+        assert_eq!(
+            capabilities.ufp_d_pins(),
+            PinAssignment::C | PinAssignment::D
+        );
+        assert_eq!(capabilities.to_string(), "pins C,D; UHBR10");
+    }
+
+    #[test]
+    fn test_status_from_bytes() {
+        // A connected UFP_D with HPD high and a pending IRQ_HPD, as in the
+        // DP_STATUS_* bits of the Linux driver.
+        let bytes = 0x0000018au32.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let status = Status::from_bytes(&mut reader).unwrap();
+
+        assert_eq!(
+            status,
+            Status {
+                connection: ConnectionStatus::UfpD,
+                enabled: true,
+                hpd_high: true,
+                irq_hpd: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_configure_from_bytes() {
+        // UFP_U as DFP_D, DP v1.3 signalling and pin assignment C.
+        let bytes = 0x00000405u32.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let configure = Configure::from_bytes(&mut reader).unwrap();
+
+        assert_eq!(configure.configuration, Configuration::DfpD);
+        assert_eq!(configure.signalling, Signalling::HBR3);
+        assert_eq!(configure.pin_assignment, PinAssignment::C);
+    }
+}
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! A registry of known Standard and Vendor IDs (SVIDs).
+//!
+//! Each SVID maps to a human-readable name and, optionally, to a decoder for
+//! the mode VDOs returned in its Discover Modes responses. Applications can
+//! register their own vendor-specific decoders at runtime with [`register`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::RwLock;
+
+use crate::ucsi::GetAlternateModesRecipient;
+use crate::vdo::displayport;
+use crate::vdo::read_vdo;
+use crate::vdo::thunderbolt;
+use crate::vdo::Vdo;
+use crate::Result;
+
+/// The SVID assigned by USB-IF to Google.
+pub const GOOGLE_SVID: u32 = 0x18d1;
+
+/// The SVID assigned by USB-IF to Lenovo.
+pub const LENOVO_SVID: u32 = 0x17ef;
+
+/// Decodes the mode VDOs of a given SVID.
+pub trait SvidDecoder: Send + Sync {
+    /// Decodes a mode VDO reported by `recipient`. Some SVIDs use different
+    /// layouts for port partners and cable plugs.
+    fn decode(&self, vdo: u32, recipient: GetAlternateModesRecipient) -> Result<Vdo>;
+}
+
+/// An entry in the SVID registry.
+#[derive(Clone)]
+pub struct SvidEntry {
+    /// The name of the standard or vendor owning the SVID.
+    pub name: String,
+    /// The decoder for the mode VDOs, if any.
+    pub decoder: Option<Arc<dyn SvidDecoder>>,
+}
+
+impl std::fmt::Debug for SvidEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SvidEntry")
+            .field("name", &self.name)
+            .field("decoder", &self.decoder.is_some())
+            .finish()
+    }
+}
+
+struct DisplayPortDecoder;
+
+impl SvidDecoder for DisplayPortDecoder {
+    fn decode(&self, vdo: u32, _: GetAlternateModesRecipient) -> Result<Vdo> {
+        Ok(Vdo::DisplayPortCapabilities(read_vdo(vdo)?))
+    }
+}
+
+struct ThunderboltDecoder;
+
+impl SvidDecoder for ThunderboltDecoder {
+    fn decode(&self, vdo: u32, recipient: GetAlternateModesRecipient) -> Result<Vdo> {
+        match recipient {
+            GetAlternateModesRecipient::SopPrime | GetAlternateModesRecipient::SopDoublePrime => {
+                Ok(Vdo::ThunderboltCableMode(read_vdo(vdo)?))
+            }
+            _ => Ok(Vdo::ThunderboltDeviceMode(read_vdo(vdo)?)),
+        }
+    }
+}
+
+/// An SVID, its name and its decoder, if any.
+type KnownSvid = (u32, &'static str, Option<Arc<dyn SvidDecoder>>);
+
+fn registry() -> &'static RwLock<HashMap<u32, SvidEntry>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<u32, SvidEntry>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let known: [KnownSvid; 4] = [
+            (
+                displayport::DISPLAYPORT_SVID,
+                "DisplayPort",
+                Some(Arc::new(DisplayPortDecoder)),
+            ),
+            (
+                thunderbolt::THUNDERBOLT_SVID,
+                "Intel (Thunderbolt)",
+                Some(Arc::new(ThunderboltDecoder)),
+            ),
+            (GOOGLE_SVID, "Google", None),
+            (LENOVO_SVID, "Lenovo", None),
+        ];
+
+        let map = known
+            .into_iter()
+            .map(|(svid, name, decoder)| {
+                (
+                    svid,
+                    SvidEntry {
+                        name: name.into(),
+                        decoder,
+                    },
+                )
+            })
+            .collect();
+
+        RwLock::new(map)
+    })
+}
+
+/// Registers `name` and an optional `decoder` for `svid`, replacing any
+/// previous entry.
+pub fn register(svid: u32, name: &str, decoder: Option<Arc<dyn SvidDecoder>>) {
+    let entry = SvidEntry {
+        name: name.into(),
+        decoder,
+    };
+
+    registry()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(svid, entry);
+}
+
+/// Looks up `svid` in the registry.
+pub fn lookup(svid: u32) -> Option<SvidEntry> {
+    registry()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&svid)
+        .cloned()
+}
+
+/// Returns the name registered for `svid`, if any.
+pub fn name(svid: u32) -> Option<String> {
+    lookup(svid).map(|entry| entry.name)
+}
+
+/// Decodes `vdo` with the decoder registered for `svid`. Returns `None` if
+/// no decoder is registered.
+pub fn decode(svid: u32, vdo: u32, recipient: GetAlternateModesRecipient) -> Result<Option<Vdo>> {
+    match lookup(svid).and_then(|entry| entry.decoder) {
+        Some(decoder) => decoder.decode(vdo, recipient).map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct RawDecoder;
+
+    impl SvidDecoder for RawDecoder {
+        fn decode(&self, vdo: u32, _: GetAlternateModesRecipient) -> Result<Vdo> {
+            Ok(Vdo::Pd3p2CertStat(crate::pd::pd3p2::vdo::CertStat {
+                xid: vdo,
+            }))
+        }
+    }
+
+    #[test]
+    fn test_register_decoder() {
+        assert_eq!(name(GOOGLE_SVID).as_deref(), Some("Google"));
+        assert_eq!(name(0x1234), None);
+
+        register(0x1234, "Example", Some(Arc::new(RawDecoder)));
+
+        assert_eq!(name(0x1234).as_deref(), Some("Example"));
+        let vdo = decode(0x1234, 42, GetAlternateModesRecipient::Sop).unwrap();
+        assert_eq!(
+            vdo,
+            Some(Vdo::Pd3p2CertStat(crate::pd::pd3p2::vdo::CertStat {
+                xid: 42
+            }))
+        );
+    }
+}
@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! Thunderbolt 3 Alternate Mode VDOs.
+//!
+//! See USB4 Specification - Appendix A.2 Thunderbolt 3 Alternate Mode
+//! Discovery.
+
+use bitstream_io::BitRead;
+use bitstream_io::BitWrite;
+use enumn::N;
+use proc_macros::CApiWrapper;
+
+use crate::BitReader;
+use crate::BitWriter;
+use crate::Error;
+use crate::FromBytes;
+use crate::Result;
+use crate::ToBytes;
+
+/// The SVID assigned by USB-IF to Intel, used for Thunderbolt 3.
+pub const THUNDERBOLT_SVID: u32 = 0x8087;
+
+/// The mode value reported in the low 16 bits of Thunderbolt 3 Discover Modes
+/// responses.
+pub const THUNDERBOLT_MODE: u32 = 0x0001;
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Thunderbolt", repr_c)]
+/// The type of Thunderbolt adapter.
+pub enum Adapter {
+    #[default]
+    /// A legacy Thunderbolt adapter.
+    Legacy = 0,
+    /// A Thunderbolt 3 adapter.
+    Tbt3,
+}
+
+/// The Thunderbolt 3 Device Discover Mode VDO, returned by the port partner.
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Thunderbolt", repr_c)]
+pub struct DeviceMode {
+    /// Thunderbolt Alternate Mode, 0x0001 for Thunderbolt 3.
+    pub mode: u32,
+    /// Whether the device is a legacy or a Thunderbolt 3 adapter.
+    pub adapter: Adapter,
+    /// Whether the device is a Vpro dock or host.
+    pub vpro: bool,
+    /// Vendor specific bit B0.
+    pub vendor_specific_b0: bool,
+    /// Vendor specific bit B1.
+    pub vendor_specific_b1: bool,
+}
+
+impl FromBytes for DeviceMode {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let mode = reader.read(16)?;
+        let adapter = reader.read(1)?;
+        let adapter = Adapter::n(adapter).ok_or_else(|| Error::ParseError {
+            field: "adapter".into(),
+            value: adapter,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+        reader.skip(9)?; // Reserved
+        let vpro = reader.read_bit()?;
+        reader.skip(3)?; // Reserved
+        let vendor_specific_b0 = reader.read_bit()?;
+        let vendor_specific_b1 = reader.read_bit()?;
+
+        Ok(Self {
+            mode,
+            adapter,
+            vpro,
+            vendor_specific_b0,
+            vendor_specific_b1,
+        })
+    }
+}
+
+impl ToBytes for DeviceMode {
+    fn to_bytes(&self, bw: &mut BitWriter) -> Result<()> {
+        bw.write(16, self.mode)?;
+        bw.write(1, self.adapter as u32)?;
+        bw.write(9, 0)?; // Reserved
+        bw.write_bit(self.vpro)?;
+        bw.write(3, 0)?; // Reserved
+        bw.write_bit(self.vendor_specific_b0)?;
+        bw.write_bit(self.vendor_specific_b1)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Thunderbolt", repr_c)]
+/// The speeds supported by a Thunderbolt 3 cable.
+pub enum CableSpeed {
+    #[default]
+    Reserved = 0,
+    /// [USB 3.2] Gen1 only
+    Usb3_2Gen1,
+    /// 10Gb/s, i.e. [USB 3.2] Gen2 and Thunderbolt 3 at 10Gb/s.
+    Gen2,
+    /// 10Gb/s and 20Gb/s, i.e. Thunderbolt 3 Gen3.
+    Gen3,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, N, Copy, CApiWrapper)]
+#[c_api(prefix = "Thunderbolt", repr_c)]
+/// The TBT3 rounded frequency support of a cable.
+pub enum CableRounding {
+    #[default]
+    /// 3rd Gen non-rounded frequencies only.
+    Gen3NonRounded = 0,
+    /// 3rd and 4th Gen rounded and non-rounded frequencies.
+    Gen3Gen4RoundedAndNonRounded,
+    Reserved,
+    Reserved2,
+}
+
+/// The Thunderbolt 3 Cable Discover Mode VDO, returned by the cable plug.
+#[derive(Debug, Clone, PartialEq, Default, CApiWrapper)]
+#[c_api(prefix = "Thunderbolt", repr_c)]
+pub struct CableMode {
+    /// Thunderbolt Alternate Mode, 0x0001 for Thunderbolt 3.
+    pub mode: u32,
+    /// The speeds supported by the cable.
+    pub speed: CableSpeed,
+    /// The rounded frequency support of the cable.
+    pub rounding: CableRounding,
+    /// Whether this is an optical cable.
+    pub optical: bool,
+    /// Whether this is an active cable with a retimer, as opposed to a
+    /// redriver or a passive cable.
+    pub retimer: bool,
+    /// Whether the cable only supports unidirectional link training.
+    pub unidirectional_link_training: bool,
+    /// Whether this is an active cable.
+    pub active: bool,
+}
+
+impl CableMode {
+    /// Whether the cable can carry a Thunderbolt 3 Gen4 (40Gb/s) link.
+    pub fn supports_gen4(&self) -> bool {
+        self.speed == CableSpeed::Gen3
+            && self.rounding == CableRounding::Gen3Gen4RoundedAndNonRounded
+    }
+}
+
+impl FromBytes for CableMode {
+    fn from_bytes(reader: &mut BitReader) -> Result<Self> {
+        let mode = reader.read(16)?;
+        let speed = reader.read(3)?;
+        let speed = CableSpeed::n(speed).ok_or_else(|| Error::ParseError {
+            field: "speed".into(),
+            value: speed,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+        let rounding = reader.read(2)?;
+        let rounding = CableRounding::n(rounding).ok_or_else(|| Error::ParseError {
+            field: "rounding".into(),
+            value: rounding,
+            #[cfg(feature = "backtrace")]
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+        let optical = reader.read_bit()?;
+        let retimer = reader.read_bit()?;
+        let unidirectional_link_training = reader.read_bit()?;
+        reader.skip(1)?; // Reserved
+        let active = reader.read_bit()?;
+        reader.skip(6)?; // Reserved
+
+        Ok(Self {
+            mode,
+            speed,
+            rounding,
+            optical,
+            retimer,
+            unidirectional_link_training,
+            active,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_device_mode_round_trip() {
+        // A Thunderbolt 3 Vpro dock.
+        let raw = 0x04010001u32;
+        let bytes = raw.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let device = DeviceMode::from_bytes(&mut reader).unwrap();
+
+        assert_eq!(device.mode, THUNDERBOLT_MODE);
+        assert_eq!(device.adapter, Adapter::Tbt3);
+        assert!(device.vpro);
+        assert!(!device.vendor_specific_b0);
+        assert!(!device.vendor_specific_b1);
+
+        let mut buf = [0; 4];
+        let mut bw = BitWriter::new(Cursor::new(&mut buf[..]));
+        device.to_bytes(&mut bw).unwrap();
+        assert_eq!(u32::from_le_bytes(buf), raw);
+    }
+
+    #[test]
+    fn test_cable_mode_from_bytes() {
+        // An active 40Gb/s retimed cable.
+        let bytes = 0x024b0001u32.to_le_bytes();
+        let mut reader = BitReader::new(Cursor::new(&bytes));
+        let cable = CableMode::from_bytes(&mut reader).unwrap();
+
+        assert_eq!(cable.mode, THUNDERBOLT_MODE);
+        assert_eq!(cable.speed, CableSpeed::Gen3);
+        assert!(cable.retimer);
+        assert!(cable.active);
+        assert!(!cable.optical);
+        assert!(cable.supports_gen4());
+    }
+}
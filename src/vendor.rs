@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// SPDX-FileCopyrightText: © 2024 Google
+
+//! Resolution of USB Vendor IDs into vendor names.
+//!
+//! Parsing never resolves vendor names by itself, so that it stays
+//! deterministic and does not touch the filesystem. Vendor names are filled in
+//! as a separate enrichment step with a [`VendorResolver`], e.g. through
+//! [`crate::pd::pd3p2::vdo::IdHeader::resolve_vendor`].
+
+#[cfg(feature = "usb_ids")]
+mod usb_ids;
+
+/// Maps a USB Vendor ID to the name of the vendor.
+pub trait VendorResolver {
+    /// Returns the name of the vendor assigned `vendor_id` by USB-IF, if known.
+    fn resolve(&self, vendor_id: u32) -> Option<String>;
+}
+
+/// Resolves vendor names using the udev hardware database.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HwdbResolver;
+
+impl VendorResolver for HwdbResolver {
+    fn resolve(&self, vendor_id: u32) -> Option<String> {
+        let hwdb = udev::Hwdb::new().ok()?;
+        let modalias = format!("usb:v{:04X}*", vendor_id);
+
+        hwdb.query(modalias)
+            .find(|entry| entry.name() == "ID_VENDOR_FROM_DATABASE")
+            .and_then(|entry| entry.value().to_str().map(String::from))
+    }
+}
+
+/// Resolves vendor names using a hand-curated subset of the usb.ids database
+/// embedded in the library. Only a few dozen vendors commonly found in USB
+/// Type-C and USB PD products are included, so most vendors are not resolved.
+#[cfg(feature = "usb_ids")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UsbIdsResolver;
+
+#[cfg(feature = "usb_ids")]
+impl VendorResolver for UsbIdsResolver {
+    fn resolve(&self, vendor_id: u32) -> Option<String> {
+        let vendor_id = u16::try_from(vendor_id).ok()?;
+        usb_ids::USB_IDS
+            .binary_search_by_key(&vendor_id, |(id, _)| *id)
+            .ok()
+            .map(|idx| usb_ids::USB_IDS[idx].1.to_string())
+    }
+}
+
+/// A resolver that never resolves anything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopResolver;
+
+impl VendorResolver for NoopResolver {
+    fn resolve(&self, _: u32) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(all(test, feature = "usb_ids"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usb_ids_table_is_sorted() {
+        assert!(usb_ids::USB_IDS.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(
+            UsbIdsResolver.resolve(0x18d1).as_deref(),
+            Some("Google Inc.")
+        );
+        assert_eq!(UsbIdsResolver.resolve(0x10000), None);
+    }
+}